@@ -10,13 +10,15 @@ use clap_complete::{
 use clap_mangen::Man;
 use vergen_gitcl::{Emitter, GitclBuilder};
 
-// Mock mpd::QueuePosition for cli.rs to be able to import it. Also see header
-// comment in cli.rs.
+// Mock mpd::QueuePosition and mpd::SingleOrRange for cli.rs to be able to
+// import them. Also see header comment in cli.rs.
 #[path = "src/mpd/queue_position.rs"]
 mod queue_position;
+#[path = "src/mpd/single_or_range.rs"]
+mod single_or_range;
 
 mod mpd {
-    pub use super::queue_position::QueuePosition;
+    pub use super::{queue_position::QueuePosition, single_or_range::SingleOrRange};
 }
 
 static NAME: &str = "rmpc";
@@ -28,6 +28,24 @@ pub enum AlbumSortMode {
     Date,
 }
 
+impl AlbumDisplayMode {
+    pub fn cycle(self) -> Self {
+        match self {
+            AlbumDisplayMode::SplitByDate => AlbumDisplayMode::NameOnly,
+            AlbumDisplayMode::NameOnly => AlbumDisplayMode::SplitByDate,
+        }
+    }
+}
+
+impl AlbumSortMode {
+    pub fn cycle(self) -> Self {
+        match self {
+            AlbumSortMode::Name => AlbumSortMode::Date,
+            AlbumSortMode::Date => AlbumSortMode::Name,
+        }
+    }
+}
+
 impl From<ArtistsFile> for Artists {
     fn from(value: ArtistsFile) -> Self {
         Self { album_display_mode: value.album_display_mode, album_sort_by: value.album_sort_by }
@@ -8,6 +8,7 @@ use super::defaults;
 
 #[derive(Debug, Default, Clone)]
 pub struct Cava {
+    pub backend: CavaBackend,
     pub framerate: u16,
     pub autosens: bool,
     pub sensitivity: u16,
@@ -20,6 +21,8 @@ pub struct Cava {
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CavaFile {
+    #[serde(default)]
+    backend: CavaBackend,
     #[serde(default = "defaults::u16::<60>")]
     framerate: u16,
     #[serde(default = "defaults::bool::<true>")]
@@ -37,6 +40,17 @@ pub struct CavaFile {
     eq: Vec<f64>,
 }
 
+/// Where the visualiser data comes from.
+#[derive(Debug, Display, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CavaBackend {
+    /// Spawn the external `cava` binary and read its raw output.
+    #[default]
+    Cava,
+    /// Read PCM samples directly from the configured fifo and compute the bars
+    /// in rmpc. Does not require cava to be installed.
+    Builtin,
+}
+
 #[derive(Debug, Display, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[strum(serialize_all = "lowercase")]
 pub enum CavaInputMethod {
@@ -96,6 +110,7 @@ pub struct CavaInput {
 impl From<CavaFile> for Cava {
     fn from(value: CavaFile) -> Self {
         Cava {
+            backend: value.backend,
             framerate: value.framerate,
             autosens: value.autosens,
             sensitivity: value.sensitivity,
@@ -120,7 +135,7 @@ impl From<CavaFile> for Cava {
 }
 
 impl Cava {
-    pub fn to_cava_config_file(&self, bars: u16) -> Result<String> {
+    pub fn to_cava_config_file(&self, bars: u16, channels: usize) -> Result<String> {
         let mut buf = String::new();
 
         writeln!(buf, "[general]")?;
@@ -153,7 +168,7 @@ impl Cava {
 
         writeln!(buf, "[output]")?;
         writeln!(buf, "method = raw")?;
-        writeln!(buf, "channels = mono")?;
+        writeln!(buf, "channels = {}", if channels == 2 { "stereo" } else { "mono" })?;
         writeln!(buf, "data_format = binary")?;
         writeln!(buf, "bit_format = 16bit")?;
         writeln!(buf, "reverse = 0")?;
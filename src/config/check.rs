@@ -0,0 +1,763 @@
+//! Validation of the config and theme files used by `rmpc config check`.
+//! Unlike regular config loading, this does not stop at the first problem
+//! but collects everything it can find and points to where in the files the
+//! problems are whenever possible.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use ron::error::Position;
+use serde::de::DeserializeOwned;
+
+use super::{
+    ConfigFile,
+    DeserError,
+    album_art::ImageMethodFile,
+    include::{self, Node},
+    keys::{GlobalAction, KeyConfig, KeySequence},
+    tabs::{PaneConversionError, TabName},
+    theme::UiConfigFile,
+};
+use crate::shared::image;
+
+const KEYBIND_SECTIONS: [&str; 9] = [
+    "global",
+    "navigation",
+    "queue",
+    "albums",
+    "artists",
+    "directories",
+    "playlists",
+    "search",
+    "logs",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: Option<PathBuf>,
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        if let Some(file) = &self.file {
+            write!(f, "{}", file.display())?;
+            if let Some(Position { line, col }) = self.position {
+                write!(f, ":{line}:{col}")?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    fn push(&mut self, severity: Severity, location: Location, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            file: location.file,
+            position: location.position,
+            message: message.into(),
+        });
+    }
+
+    fn error(&mut self, location: Location, message: impl Into<String>) {
+        self.push(Severity::Error, location, message);
+    }
+
+    fn warning(&mut self, location: Location, message: impl Into<String>) {
+        self.push(Severity::Warning, location, message);
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+
+        let count = |severity| self.diagnostics.iter().filter(|d| d.severity == severity).count();
+        match (count(Severity::Error), count(Severity::Warning)) {
+            (0, 0) => writeln!(f, "No problems found"),
+            (errors, warnings) => writeln!(f, "Found {errors} error(s) and {warnings} warning(s)"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Location {
+    file: Option<PathBuf>,
+    position: Option<Position>,
+}
+
+impl Location {
+    fn file(path: &Path) -> Self {
+        Self { file: Some(path.to_path_buf()), position: None }
+    }
+}
+
+/// A single config or theme file together with its parsed structure.
+struct Source {
+    path: PathBuf,
+    content: String,
+    root: Option<Node>,
+}
+
+impl Source {
+    fn read(path: &Path) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let root = include::parse(&content).ok();
+        Ok(Self { path: path.to_path_buf(), content, root })
+    }
+
+    fn location(&self, offset: usize) -> Location {
+        Location { file: Some(self.path.clone()), position: Some(position(&self.content, offset)) }
+    }
+
+    /// Finds the offset of the nested field, ie. `["keybinds", "global"]`.
+    fn find_field(&self, fields: &[&str]) -> Option<usize> {
+        fields.iter().try_fold(0, |start, field| find_identifier(&self.content, start, field))
+    }
+
+    fn keybinds(&self, section: &str) -> impl Iterator<Item = (&str, KeySequence)> {
+        let entries =
+            match self.root.as_ref().and_then(|root| root.field("keybinds")?.field(section)) {
+                Some(Node::Map(entries)) => entries.as_slice(),
+                _ => &[],
+            };
+
+        entries.iter().filter_map(|(raw, _)| {
+            Some((raw.as_str(), ron::de::from_str::<KeySequence>(raw).ok()?))
+        })
+    }
+
+    /// Finds the location of `nth` occurrence of the raw key in the given
+    /// keybinds section.
+    fn find_key(&self, section: &str, raw_key: &str, nth: usize) -> Option<Location> {
+        let mut offset = self.find_field(&["keybinds", section])?;
+        for _ in 0..=nth {
+            offset += self.content[offset..].find(raw_key)? + 1;
+        }
+        Some(self.location(offset - 1))
+    }
+}
+
+/// Checks the config at the given path and the theme it references or the
+/// theme passed on the command line.
+pub fn check(config_path: &Path, theme_cli: Option<&Path>) -> Report {
+    let mut report = Report::default();
+
+    let hostname = include::hostname();
+    let resolved = match include::resolve(config_path, hostname.as_deref()) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            report.error(Location::file(config_path), err.to_string());
+            return report;
+        }
+    };
+
+    let mut sources = Vec::new();
+    for path in &resolved.files {
+        match Source::read(path) {
+            Ok(source) => sources.push(source),
+            Err(err) => report.error(Location::file(path), format!("Failed to read file: {err}")),
+        }
+    }
+
+    // Each file is deserialized on its own first so that the errors point to
+    // the file they are in. Merged config is checked only when all of them are
+    // valid as the positions in it do not correspond to any real file.
+    let config_file = if let [source] = sources.as_slice() {
+        deserialize::<ConfigFile>(&source.content, Some(&source.path), &mut report)
+    } else {
+        let all_valid = sources.iter().fold(true, |acc, source| {
+            deserialize::<ConfigFile>(&source.content, Some(&source.path), &mut report).is_some()
+                && acc
+        });
+        all_valid.then(|| deserialize(&resolved.content, None, &mut report)).flatten()
+    };
+
+    for source in &sources {
+        check_config_deprecations(source, &mut report);
+        check_duplicate_keys(source, &mut report);
+    }
+
+    let Some(config_file) = config_file else {
+        return report;
+    };
+
+    check_image_method(&config_file, &sources, &mut report);
+
+    let theme_path = theme_cli
+        .map(Path::to_path_buf)
+        .or_else(|| config_path.parent().and_then(|dir| config_file.theme_path(dir)));
+    let theme = match &theme_path {
+        Some(path) => match Source::read(path) {
+            Ok(source) => {
+                check_theme_deprecations(&source, &mut report);
+                let theme = deserialize::<UiConfigFile>(&source.content, Some(path), &mut report);
+                sources.push(source);
+                theme
+            }
+            Err(err) => {
+                report.error(Location::file(path), format!("Failed to read theme file: {err}"));
+                None
+            }
+        },
+        None => Some(UiConfigFile::default()),
+    };
+    if theme.is_none() {
+        return report;
+    }
+
+    let config =
+        match config_file.into_config(Some(config_path), theme_path.as_deref(), None, None, true) {
+            Ok(config) => config,
+            Err(err) => {
+                let location = missing_component(&err)
+                    .and_then(|name| find_in_sources(&sources, &format!("Component({name:?})")))
+                    .unwrap_or_default();
+                report.error(location, error_message(&err));
+                return report;
+            }
+        };
+
+    if let Err(err) = config.validate() {
+        report.error(Location::default(), format!("Invalid layout: {err:#}"));
+    }
+
+    for (key, action) in &config.keybinds.global {
+        for name in switched_tabs(action) {
+            if !config.tabs.names.contains(name) {
+                report.error(
+                    find_key(&sources, "global", key),
+                    format!(
+                        "Key '{key}' switches to tab '{name}' which does not exist. Available tabs: {}",
+                        config.tabs.names.iter().join(", ")
+                    ),
+                );
+            }
+        }
+    }
+
+    check_keybind_conflicts(&config.keybinds, &sources, &mut report);
+
+    report
+}
+
+/// Warns about keys bound both in global keybinds and in a more specific
+/// section, and about keybinds which start a longer one and
+/// thus only run once the sequence times out.
+fn check_keybind_conflicts(keybinds: &KeyConfig, sources: &[Source], report: &mut Report) {
+    fn section<'a, V: Display>(
+        name: &'static str,
+        keybinds: &'a HashMap<KeySequence, V>,
+    ) -> impl Iterator<Item = (&'static str, &'a KeySequence, String)> {
+        keybinds
+            .iter()
+            .map(move |(key, action)| (name, key, action.to_string()))
+            .sorted_by_key(|(_, key, _)| key.to_string())
+    }
+
+    let all = section("global", &keybinds.global)
+        .chain(section("navigation", &keybinds.navigation))
+        .chain(section("queue", &keybinds.queue))
+        .chain(section("albums", &keybinds.albums))
+        .chain(section("artists", &keybinds.artists))
+        .chain(section("directories", &keybinds.directories))
+        .chain(section("playlists", &keybinds.playlists))
+        .chain(section("search", &keybinds.search));
+    #[cfg(debug_assertions)]
+    let all = all.chain(section("logs", &keybinds.logs));
+    let all = all.collect_vec();
+
+    for (name, key, action) in &all {
+        if let Some(global) = keybinds.global.get(key).filter(|_| *name != "global") {
+            let scope = match *name {
+                "navigation" => "in panes",
+                "queue" => "in the queue pane",
+                "albums" => "in the albums pane",
+                "artists" => "in the artists and album artists panes",
+                "directories" => "in the directories pane",
+                "playlists" => "in the playlists pane",
+                "search" => "in the search pane",
+                _ => "in the logs pane",
+            };
+            report.warning(
+                find_key(sources, name, key),
+                format!(
+                    "Key '{key}' is bound to '{action}' in {name} and to '{global}' in global keybinds. The {name} keybind takes precedence {scope}."
+                ),
+            );
+        }
+
+        if keybinds.is_prefix(key) {
+            let longer = all
+                .iter()
+                .filter(|(_, other_key, _)| key.is_prefix_of(other_key))
+                .map(|(other_name, other_key, other)| {
+                    format!("'{other_key}' ({other} in {other_name})")
+                })
+                .unique()
+                .join(", ");
+            report.warning(
+                find_key(sources, name, key),
+                format!(
+                    "Key '{key}' bound to '{action}' in {name} is the start of {longer}. It only runs after key_sequence_timeout_ms passes without another key."
+                ),
+            );
+        }
+    }
+}
+
+/// Names of all tabs the action switches to, including the ones in chains
+fn switched_tabs(action: &GlobalAction) -> Vec<&TabName> {
+    match action {
+        GlobalAction::SwitchToTab(name) => vec![name],
+        GlobalAction::Chain(steps) => {
+            steps.iter().filter_map(|step| step.global.as_ref()).flat_map(switched_tabs).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(
+    content: &str,
+    path: Option<&Path>,
+    report: &mut Report,
+) -> Option<T> {
+    let location = |position| Location {
+        file: path.map(Path::to_path_buf),
+        position: path.and(Some(position)),
+    };
+    let in_merged = if path.is_none() { " in the merged config" } else { "" };
+
+    let mut deserializer = match ron::de::Deserializer::from_str(content) {
+        Ok(deserializer) => deserializer,
+        Err(err) => {
+            report.error(location(err.position), format!("{}{in_merged}", err.code));
+            return None;
+        }
+    };
+
+    match serde_path_to_error::deserialize::<_, T>(&mut deserializer) {
+        Ok(value) => match deserializer.end() {
+            Ok(()) => Some(value),
+            Err(err) => {
+                let err = deserializer.span_error(err);
+                report.error(location(err.position), format!("{}{in_merged}", err.code));
+                None
+            }
+        },
+        Err(err) => {
+            let path = err.path().to_string();
+            let err = deserializer.span_error(err.into_inner());
+            report.error(location(err.position), format!("{path}: {}{in_merged}", err.code));
+            None
+        }
+    }
+}
+
+fn check_config_deprecations(source: &Source, report: &mut Report) {
+    let Some(root) = &source.root else {
+        return;
+    };
+
+    for (field, replacement) in
+        [("image_method", "album_art.method"), ("album_art_max_size_px", "album_art.max_size_px")]
+    {
+        if root.field(field).is_some() {
+            let location = source
+                .find_field(&[field])
+                .map_or_else(|| Location::file(&source.path), |offset| source.location(offset));
+            report
+                .warning(location, format!("'{field}' is deprecated, use '{replacement}' instead"));
+        }
+    }
+
+    if let Some(Node::Raw(tabs)) = root.field("tabs") {
+        if tabs.contains("border_type") {
+            let location = source
+                .find_field(&["tabs", "border_type"])
+                .map_or_else(|| Location::file(&source.path), |offset| source.location(offset));
+            report.warning(
+                location,
+                "'border_type' in tabs is deprecated and has no effect, set 'borders' on the panes instead",
+            );
+        }
+    }
+}
+
+fn check_theme_deprecations(source: &Source, report: &mut Report) {
+    let enabled = source.root.as_ref().and_then(|root| root.field("tab_bar")?.field("enabled"));
+    if enabled.is_some() {
+        let location = source
+            .find_field(&["tab_bar", "enabled"])
+            .map_or_else(|| Location::file(&source.path), |offset| source.location(offset));
+        report.warning(
+            location,
+            "'tab_bar.enabled' is deprecated and has no effect, remove the 'Tabs' pane from the layout instead",
+        );
+    }
+}
+
+fn check_duplicate_keys(source: &Source, report: &mut Report) {
+    for section in KEYBIND_SECTIONS {
+        let entries = source.keybinds(section).collect_vec();
+        let duplicates = entries
+            .iter()
+            .enumerate()
+            .into_group_map_by(|(_, (_, key))| key.clone())
+            .into_iter()
+            .filter(|(_, bindings)| bindings.len() > 1)
+            .sorted_by_key(|(key, _)| key.to_string());
+
+        for (key, bindings) in duplicates {
+            // Report the second binding as it is the one that silently
+            // overrides the first
+            let (idx, (raw, _)) = bindings[1];
+            let nth = entries[..idx].iter().filter(|(r, _)| r == raw).count();
+            let location =
+                source.find_key(section, raw, nth).unwrap_or_else(|| Location::file(&source.path));
+            report.error(
+                location,
+                format!(
+                    "Key '{key}' is bound {} times in '{section}' keybinds, only one of them will be used",
+                    bindings.len()
+                ),
+            );
+        }
+    }
+}
+
+fn check_image_method(config: &ConfigFile, sources: &[Source], report: &mut Report) {
+    let (method, field) = match config.image_method {
+        Some(method) => (method, "image_method"),
+        None => (config.album_art.method, "method"),
+    };
+    let supported = match method {
+        ImageMethodFile::UeberzugWayland => image::is_ueberzug_wayland_supported(),
+        ImageMethodFile::UeberzugX11 => image::is_ueberzug_x11_supported(),
+        // Other methods depend on the terminal which is not known outside of
+        // the UI
+        ImageMethodFile::Kitty
+        | ImageMethodFile::Iterm2
+        | ImageMethodFile::Sixel
+        | ImageMethodFile::Block
+        | ImageMethodFile::None
+        | ImageMethodFile::Auto => true,
+    };
+
+    if !supported {
+        let location = sources
+            .iter()
+            .rev()
+            .find_map(|source| {
+                let fields: &[&str] =
+                    if field == "method" { &["album_art", field] } else { &[field] };
+                source.find_field(fields).map(|offset| source.location(offset))
+            })
+            .unwrap_or_default();
+        report.warning(
+            location,
+            format!(
+                "Image method '{method}' is not supported in the current environment, album art will be disabled"
+            ),
+        );
+    }
+}
+
+fn find_key(sources: &[Source], section: &str, key: &KeySequence) -> Location {
+    // Later files override the earlier ones
+    sources
+        .iter()
+        .rev()
+        .find_map(|source| {
+            let (raw, _) = source.keybinds(section).find(|(_, k)| k == key)?;
+            source.find_key(section, raw, 0)
+        })
+        .unwrap_or_default()
+}
+
+fn find_in_sources(sources: &[Source], needle: &str) -> Option<Location> {
+    sources
+        .iter()
+        .find_map(|source| source.content.find(needle).map(|offset| source.location(offset)))
+}
+
+fn missing_component(err: &DeserError) -> Option<&str> {
+    let DeserError::Generic(err) = err else {
+        return None;
+    };
+    err.chain().find_map(|err| match err.downcast_ref::<PaneConversionError>() {
+        Some(PaneConversionError::MissingComponent(name)) => Some(name.as_str()),
+        _ => None,
+    })
+}
+
+fn error_message(err: &DeserError) -> String {
+    match err {
+        DeserError::Generic(err) => format!("{err:#}"),
+        err => err.to_string(),
+    }
+}
+
+/// Finds identifier at word boundaries followed by a colon, ie. a field name.
+fn find_identifier(content: &str, start: usize, identifier: &str) -> Option<usize> {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut from = start;
+    while let Some(idx) = content[from..].find(identifier) {
+        let offset = from + idx;
+        let end = offset + identifier.len();
+        let boundary_before = !content[..offset].chars().next_back().is_some_and(is_ident_char);
+        let followed_by_colon = content[end..].trim_start().starts_with(':');
+        if boundary_before && followed_by_colon {
+            return Some(offset);
+        }
+        from = end;
+    }
+    None
+}
+
+fn position(content: &str, offset: usize) -> Position {
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    Position { line, col: before[line_start..].chars().count() + 1 }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{Severity, check};
+    use crate::tests::fixtures::temp_dir::TempDir;
+
+    fn messages(report: &super::Report) -> Vec<String> {
+        report.diagnostics.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn example_config_has_no_problems() {
+        let dir = TempDir::new("example");
+        let path = dir.write(
+            "config.ron",
+            include_str!("../../docs/src/content/docs/next/assets/example_config.ron"),
+        );
+
+        let report = check(&path, None);
+
+        assert!(report.diagnostics.is_empty(), "{:?}", messages(&report));
+    }
+
+    #[test]
+    fn reports_unknown_pane_with_position() {
+        let dir = TempDir::new("pane");
+        let path = dir.write(
+            "config.ron",
+            r#"#![enable(implicit_some)]
+#![enable(unwrap_newtypes)]
+#![enable(unwrap_variant_newtypes)]
+(
+    tabs: [
+        (
+            name: "Queue",
+            pane: Pane(Quuee),
+        ),
+    ],
+)"#,
+        );
+
+        let report = check(&path, None);
+
+        assert!(report.has_errors());
+        let diagnostic = &report.diagnostics[0];
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.file.as_deref(), Some(path.as_path()));
+        assert_eq!(diagnostic.position.map(|p| p.line), Some(8));
+        assert!(diagnostic.message.contains("Quuee"), "{}", diagnostic.message);
+    }
+
+    #[test]
+    fn reports_invalid_tab_reference_and_duplicate_keys() {
+        let dir = TempDir::new("keys");
+        let path = dir.write(
+            "config.ron",
+            r#"#![enable(implicit_some)]
+#![enable(unwrap_newtypes)]
+#![enable(unwrap_variant_newtypes)]
+(
+    keybinds: (
+        global: {
+            "q": Quit,
+            "1": SwitchToTab("Nope"),
+            "q": ShowHelp,
+        },
+    ),
+)"#,
+        );
+
+        let report = check(&path, None);
+        let messages = messages(&report);
+
+        assert!(report.has_errors());
+        assert!(
+            messages.iter().any(|m| m.contains(":8:13:") && m.contains("tab 'Nope'")),
+            "{messages:?}"
+        );
+        assert!(
+            messages.iter().any(|m| m.contains(":9:13:") && m.contains("Key 'q' is bound 2 times")),
+            "{messages:?}"
+        );
+    }
+
+    #[test]
+    fn reports_shadowed_keys_and_deprecated_fields_as_warnings() {
+        let dir = TempDir::new("warnings");
+        dir.write(
+            "themes/theme.ron",
+            &include_str!("../../docs/src/content/docs/next/assets/example_theme.ron")
+                .replace("tab_bar: (", "tab_bar: (\n        enabled: true,"),
+        );
+        let path = dir.write(
+            "config.ron",
+            r#"#![enable(implicit_some)]
+#![enable(unwrap_newtypes)]
+#![enable(unwrap_variant_newtypes)]
+(
+    theme: "theme",
+    image_method: Block,
+    keybinds: (
+        global: { "x": Quit },
+        navigation: { "x": Down },
+    ),
+)"#,
+        );
+
+        let report = check(&path, None);
+        let messages = messages(&report);
+
+        assert!(!report.has_errors(), "{messages:?}");
+        assert!(messages.iter().all(|m| m.starts_with("warning")), "{messages:?}");
+        assert!(
+            messages.iter().any(|m| m.contains(":6:5:") && m.contains("'image_method'")),
+            "{messages:?}"
+        );
+        assert!(
+            messages.iter().any(|m| m.contains(":9:23:") && m.contains("Key 'x'")),
+            "{messages:?}"
+        );
+        assert!(
+            messages.iter().any(|m| m.contains("theme.ron:18:9:") && m.contains("tab_bar.enabled")),
+            "{messages:?}"
+        );
+    }
+
+    #[test]
+    fn reports_pane_keybinds_shadowing_global_ones() {
+        let dir = TempDir::new("pane-conflict");
+        let path = dir.write(
+            "config.ron",
+            r#"#![enable(implicit_some)]
+#![enable(unwrap_newtypes)]
+#![enable(unwrap_variant_newtypes)]
+(
+    keybinds: (
+        global: { "e": Quit },
+        albums: { "e": ToggleReverseSort },
+    ),
+)"#,
+        );
+
+        let report = check(&path, None);
+        let messages = messages(&report);
+
+        assert!(!report.has_errors(), "{messages:?}");
+        assert!(
+            messages.iter().any(|m| m.contains(":7:19:")
+                && m.contains("Key 'e' is bound to 'ToggleReverseSort' in albums")
+                && m.contains("in the albums pane")),
+            "{messages:?}"
+        );
+    }
+
+    #[test]
+    fn reports_keybinds_which_start_a_longer_one() {
+        let dir = TempDir::new("prefix");
+        let path = dir.write(
+            "config.ron",
+            r#"#![enable(implicit_some)]
+#![enable(unwrap_newtypes)]
+#![enable(unwrap_variant_newtypes)]
+(
+    keybinds: (
+        global: { "ww": Quit },
+        navigation: { "w": Down },
+    ),
+)"#,
+        );
+
+        let report = check(&path, None);
+        let messages = messages(&report);
+
+        assert!(!report.has_errors(), "{messages:?}");
+        assert_eq!(messages.len(), 1, "{messages:?}");
+        assert!(
+            messages[0].contains(":7:23:")
+                && messages[0].contains(
+                    "Key 'w' bound to 'Down' in navigation is the start of 'ww' (Quit in global)"
+                ),
+            "{messages:?}"
+        );
+    }
+
+    #[test]
+    fn reports_missing_component() {
+        let dir = TempDir::new("component");
+        let path = dir.write(
+            "config.ron",
+            r#"#![enable(implicit_some)]
+#![enable(unwrap_newtypes)]
+#![enable(unwrap_variant_newtypes)]
+(
+    tabs: [
+        (
+            name: "Queue",
+            pane: Component("missing"),
+        ),
+    ],
+)"#,
+        );
+
+        let report = check(&path, None);
+        let messages = messages(&report);
+
+        assert!(report.has_errors());
+        assert!(
+            messages.iter().any(|m| m.contains(":8:19:") && m.contains("missing")),
+            "{messages:?}"
+        );
+    }
+}
@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use clap::{Parser, Subcommand, ValueEnum, ValueHint};
 use strum::IntoStaticStr;
 
-use crate::mpd::QueuePosition;
+use crate::mpd::{QueuePosition, SingleOrRange};
 
 #[derive(Parser, Debug)]
 pub struct Args {
@@ -24,6 +24,10 @@ pub struct Args {
     #[arg(short, long)]
     /// Override the MPD password
     pub password: Option<String>,
+    #[arg(long)]
+    /// Name of this instance. Remote commands can target the instance by it
+    /// with `rmpc remote --name`.
+    pub name: Option<String>,
 
     #[command(flatten)]
     pub partition: Partition,
@@ -64,6 +68,8 @@ pub enum Command {
         /// If provided, print the current config instead of the default one.
         #[arg(short, long, default_value = "false")]
         current: bool,
+        #[command(subcommand)]
+        command: Option<ConfigCommand>,
     },
     /// Prints the default theme. Can be used to bootstrap your theme file.
     Theme {
@@ -71,6 +77,10 @@ pub enum Command {
         #[arg(short, long, default_value = "false")]
         current: bool,
     },
+    /// Runs rmpc without the TUI. Follows changes in MPD and runs the
+    /// configured hooks, ie. `on_song_change`, keeps the lyrics index up to
+    /// date and serves remote commands until it is killed.
+    Daemon,
     /// Index the lyrics dir and display result, meant only for debugging
     /// purposes
     LyricsIndex,
@@ -225,7 +235,17 @@ pub enum Command {
     /// List MPD decoder plugins
     Decoders,
     /// Prints various information like the playback status
-    Status,
+    Status {
+        /// Print the status formatted by a list of properties like in the
+        /// theme's header instead of JSON, ie. `[(kind:
+        /// Property(Status(StateV2()))), (kind: Text(" ")), (kind:
+        /// Property(Song(Title)))]`
+        #[arg(short, long)]
+        format: Option<String>,
+        /// Keep running and print the status again whenever it changes
+        #[arg(long)]
+        follow: bool,
+    },
     /// Prints info about the current song.
     /// If --path specified, prints information about the song at the given path
     /// instead. If --path is specified multiple times, prints an array
@@ -233,6 +253,47 @@ pub enum Command {
     Song {
         #[arg(short, long)]
         path: Option<Vec<String>>,
+        /// Print the song formatted by a list of properties like in the
+        /// theme's header instead of JSON, one line per song
+        #[arg(short, long)]
+        format: Option<String>,
+        /// Keep running and print the current song again whenever it changes
+        #[arg(long, conflicts_with = "path")]
+        follow: bool,
+    },
+    /// Waits for a change in MPD and prints the changed subsystems as JSON
+    /// lines. Every line also contains the current status and song.
+    Idle {
+        /// Only wait for changes in these subsystems. Waits for changes in all
+        /// subsystems if omitted
+        #[clap(value_enum)]
+        subsystems: Vec<IdleSubsystem>,
+        /// Keep running and print every change instead of exiting after the
+        /// first one
+        #[arg(long)]
+        follow: bool,
+    },
+    /// Searches the database for songs matching all the given filters, case
+    /// insensitively. Prints the songs as a JSON array by default.
+    Search {
+        #[command(flatten)]
+        args: Box<SearchArgs>,
+    },
+    /// Finds songs in the database matching all the given filters, case
+    /// sensitively. Prints the songs as a JSON array by default.
+    Find {
+        #[command(flatten)]
+        args: Box<SearchArgs>,
+    },
+    /// List and manipulate the current queue
+    Queue {
+        #[command(subcommand)]
+        cmd: QueueCmd,
+    },
+    /// List and manipulate stored playlists
+    Playlist {
+        #[command(subcommand)]
+        cmd: PlaylistCmd,
     },
     /// Mounts supported storage to MPD
     Mount {
@@ -254,10 +315,15 @@ pub enum Command {
     },
     /// Send a remote command to running rmpc instance
     Remote {
-        /// PID of the rmpc instance to send the remote command to. If not
-        /// provided, rmpc will try to notify all the running instances.
+        /// PID of the rmpc instance to send the remote command to. If
+        /// neither PID nor name is provided, rmpc will try to notify all the
+        /// running instances.
         #[arg(long)]
         pid: Option<u32>,
+        /// Name of the rmpc instance to send the remote command to, as given
+        /// by its --name argument
+        #[arg(long, conflicts_with = "pid")]
+        name: Option<String>,
         #[command(subcommand)]
         command: RemoteCmd,
     },
@@ -286,11 +352,93 @@ pub enum RemoteCmd {
     },
     #[clap(hide = true)]
     Tmux { hook: String },
+    /// Runs an action in running rmpc instance as if its keybind was pressed.
+    /// Accepts the same action names as the keybinds config, ie.
+    /// `TogglePause`, `Delete` or `SwitchToTab Queue`
+    Action {
+        /// Name of the action
+        action: String,
+        /// Arguments of the action, ie. the tab name for `SwitchToTab`
+        args: Vec<String>,
+    },
+    /// Presses keys in running rmpc instance, ie. `<C-p>gg`. Uses the same
+    /// format as the keybinds config
+    Keys {
+        /// Keys to press
+        keys: String,
+    },
+    /// Lists running rmpc instances and removes sockets left behind by
+    /// instances which did not exit cleanly
+    List,
     /// Sets a value in running rmpc instance
     Set {
         #[command(subcommand)]
         command: SetCommand,
     },
+    /// Prints a value from a running rmpc instance as JSON. Requires --pid or
+    /// --name when more than one instance is running.
+    /// Exit codes:
+    ///   * 0: Success
+    ///   * 1: Error, ie. no instance was found or it failed to answer
+    Get {
+        /// The value to get
+        #[clap(value_enum)]
+        what: RemoteGet,
+    },
+    /// Keeps the connection open and prints events from a running rmpc
+    /// instance as JSON lines until it exits. Requires --pid or --name when
+    /// more than one instance is running.
+    Subscribe {
+        /// Only print events of this type. Can be specified multiple times,
+        /// prints all events if omitted
+        #[arg(short, long = "event")]
+        #[clap(value_enum)]
+        events: Vec<SubscribeEvent>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum RemoteGet {
+    /// Name of the active tab
+    Tab,
+    /// Name of the focused pane
+    Pane,
+    /// Item under the cursor in the focused pane
+    Selected,
+    /// Marked items in the focused pane
+    Marked,
+    /// Filter of the focused pane
+    Filter,
+    /// Path to the config file the instance loaded
+    ConfigPath,
+    /// Name of the theme the instance uses
+    Theme,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SubscribeEvent {
+    /// Currently playing song changed
+    SongChange,
+    /// Active tab changed
+    TabChange,
+    /// Focus moved to another pane
+    PaneFocus,
+    /// Status message was displayed
+    Status,
+    /// Current line in the lyrics pane changed
+    LyricsLine,
+    /// Config was reloaded
+    ConfigReload,
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+#[clap(rename_all = "lower")]
+pub enum ConfigCommand {
+    /// Validates the config and theme files and reports every problem found.
+    /// Exit codes:
+    ///   * 0: No errors, there might still be warnings
+    ///   * 1: At least one error was found
+    Check,
 }
 
 #[derive(Subcommand, Clone, Debug, PartialEq)]
@@ -312,6 +460,22 @@ pub enum SetCommand {
         #[arg(value_hint = ValueHint::AnyPath)]
         path: String,
     },
+    /// Switches a running rmpc instance to its `theme_light` or `theme_dark`
+    /// regardless of the terminal's background
+    ThemeMode {
+        #[clap(value_enum)]
+        mode: ThemeModeOrAuto,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ThemeModeOrAuto {
+    /// Use the theme from `theme_light`
+    Light,
+    /// Use the theme from `theme_dark`
+    Dark,
+    /// Follow the terminal's background again
+    Auto,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Parser, ValueEnum)]
@@ -321,6 +485,248 @@ pub enum Level {
     Warn,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum IdleSubsystem {
+    /// Playback was started, stopped or seeked or tags of the current song
+    /// changed
+    Player,
+    /// Volume changed
+    Mixer,
+    /// Queue changed
+    Playlist,
+    /// Options like repeat, random, crossfade or replay gain changed
+    Options,
+    /// Song database was modified after an update
+    Database,
+    /// Database update started or finished
+    Update,
+    /// Stored playlist was modified, renamed, created or deleted
+    StoredPlaylist,
+    /// Audio output was added, removed or modified
+    Output,
+    /// Partition was added, removed or changed
+    Partition,
+    /// Sticker database was modified
+    Sticker,
+    /// Client subscribed to or unsubscribed from a channel
+    Subscription,
+    /// Message was received on a subscribed channel
+    Message,
+    /// Neighbor was found or lost
+    Neighbor,
+    /// Mount list changed
+    Mount,
+}
+
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct SearchArgs {
+    /// Raw MPD filter expression, ie. `(artist == 'Foo')`. Cannot be combined
+    /// with the tag filters.
+    #[arg(conflicts_with_all = [
+        "artist", "album", "albumartist", "genre", "title", "composer", "performer", "date", "file",
+        "any", "tags", "kind",
+    ])]
+    pub expression: Option<String>,
+    /// Filter by artist
+    #[arg(long)]
+    pub artist: Option<String>,
+    /// Filter by album
+    #[arg(long)]
+    pub album: Option<String>,
+    /// Filter by album artist
+    #[arg(long)]
+    pub albumartist: Option<String>,
+    /// Filter by genre
+    #[arg(long)]
+    pub genre: Option<String>,
+    /// Filter by title
+    #[arg(long)]
+    pub title: Option<String>,
+    /// Filter by composer
+    #[arg(long)]
+    pub composer: Option<String>,
+    /// Filter by performer
+    #[arg(long)]
+    pub performer: Option<String>,
+    /// Filter by date
+    #[arg(long)]
+    pub date: Option<String>,
+    /// Filter by path of the file, relative to music directory root
+    #[arg(long)]
+    pub file: Option<String>,
+    /// Filter by any tag
+    #[arg(long)]
+    pub any: Option<String>,
+    /// Filter by any other tag, ie. `--tag label=Foo`. Can be specified
+    /// multiple times
+    #[arg(long = "tag", value_name = "TAG=VALUE", value_parser = parse_tag_filter)]
+    pub tags: Vec<(String, String)>,
+    /// How the tag filters are matched. Defaults to `exact` for find and
+    /// `contains` for search
+    #[arg(short, long)]
+    #[clap(value_enum)]
+    pub kind: Option<SearchKind>,
+    /// Print the songs formatted by a list of properties like in the theme's
+    /// header instead of JSON, one line per song
+    #[arg(short, long, conflicts_with = "add")]
+    pub format: Option<String>,
+    /// Add the songs to the queue instead of printing them
+    #[arg(long)]
+    pub add: bool,
+    /// If provided, queue the songs at this position instead of the end of the
+    /// queue. Allowed positions are <number> (absolute) and +<number> or
+    /// -<number> (relative)
+    #[arg(short, long, requires = "add", allow_negative_numbers = true)]
+    pub position: Option<QueuePosition>,
+}
+
+fn parse_tag_filter(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((tag, value)) if !tag.is_empty() => Ok((tag.to_owned(), value.to_owned())),
+        _ => Err(format!("Invalid tag filter '{value}', expected <TAG>=<VALUE>")),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SearchKind {
+    /// Value of the tag is equal to the given value
+    Exact,
+    /// Value of the tag starts with the given value
+    StartsWith,
+    /// Value of the tag contains the given value
+    Contains,
+    /// Value of the tag matches the given regular expression
+    Regex,
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+#[clap(rename_all = "lower")]
+pub enum QueueCmd {
+    /// Prints songs in the queue, one per line with their position, artist,
+    /// title and file separated by tabs
+    List {
+        /// Print the songs as a JSON array instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// Moves songs to a new position in the queue
+    Move {
+        /// Position of the song or a range of songs to move, ie. `5` or `2:6`.
+        /// End of the range is exclusive
+        from: SingleOrRange,
+        /// Position to move the songs to. Allowed positions are <number>
+        /// (absolute) and +<number> or -<number> (relative to the current
+        /// song)
+        #[arg(allow_negative_numbers = true)]
+        to: QueuePosition,
+    },
+    /// Removes songs from the queue
+    Delete {
+        /// Position of the song or a range of songs to remove, ie. `5` or
+        /// `2:6`. End of the range is exclusive
+        range: SingleOrRange,
+    },
+    /// Swaps positions of two songs in the queue
+    Swap {
+        /// Position of the first song. Allowed positions are <number>
+        /// (absolute) and +<number> or -<number> (relative to the current
+        /// song, +0 is the song after it and -0 the one before it)
+        #[arg(allow_negative_numbers = true)]
+        first: QueuePosition,
+        /// Position of the second song, same as the first one
+        #[arg(allow_negative_numbers = true)]
+        second: QueuePosition,
+    },
+    /// Shuffles the queue
+    Shuffle {
+        /// Range of songs to shuffle, ie. `2:6`. End of the range is
+        /// exclusive. Shuffles the whole queue if omitted
+        range: Option<SingleOrRange>,
+    },
+    /// Removes songs which are already in the queue, keeping their first
+    /// occurrence
+    Dedupe,
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+#[clap(rename_all = "lower")]
+pub enum PlaylistCmd {
+    /// Prints names of stored playlists, one per line
+    List {
+        /// Print the playlists with their last modification time as a JSON
+        /// array instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// Prints songs in a stored playlist, one per line with their position,
+    /// artist, title and file separated by tabs
+    Show {
+        /// Name of the playlist
+        playlist: String,
+        /// Print the songs as a JSON array instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// Adds songs of a stored playlist to the queue
+    Load {
+        /// Name of the playlist
+        playlist: String,
+        /// If provided, queue the songs at this position instead of the end
+        /// of the queue. Allowed positions are <number> (absolute) and
+        /// +<number> or -<number> (relative)
+        #[arg(short, long, allow_negative_numbers = true)]
+        position: Option<QueuePosition>,
+    },
+    /// Saves the current queue as a new stored playlist
+    Save {
+        /// Name of the playlist
+        playlist: String,
+        /// Replace the playlist if it already exists. Requires MPD 0.24 or
+        /// newer
+        #[arg(short, long)]
+        replace: bool,
+    },
+    /// Appends the current queue to a stored playlist. Requires MPD 0.24 or
+    /// newer
+    Append {
+        /// Name of the playlist
+        playlist: String,
+    },
+    /// Renames a stored playlist
+    Rename {
+        /// Current name of the playlist
+        playlist: String,
+        /// New name of the playlist
+        new_name: String,
+    },
+    /// Deletes a stored playlist
+    Delete {
+        /// Name of the playlist
+        playlist: String,
+    },
+    /// Adds songs to a stored playlist
+    Add {
+        /// Name of the playlist
+        playlist: String,
+        /// Songs to add, relative to music directory root
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// If provided, insert the songs at this position instead of the end
+        /// of the playlist
+        #[arg(short, long)]
+        position: Option<usize>,
+    },
+    /// Removes songs from a stored playlist
+    Remove {
+        /// Name of the playlist
+        playlist: String,
+        /// Position of the song or a range of songs to remove, ie. `5` or
+        /// `2:6`. End of the range is exclusive
+        range: SingleOrRange,
+    },
+}
+
 #[derive(Subcommand, Clone, Debug, PartialEq)]
 #[clap(rename_all = "lower")]
 pub enum StickerCmd {
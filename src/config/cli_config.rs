@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -59,10 +59,9 @@ impl From<&Config> for CliConfig {
 }
 
 impl CliConfigFile {
-    pub fn read(path: &PathBuf) -> Result<Self> {
-        let file = std::fs::File::open(path)?;
-        let read = std::io::BufReader::new(file);
-        let config: CliConfigFile = ron::de::from_reader(read)?;
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = ConfigFile::read_content(path)?;
+        let config: CliConfigFile = ron::de::from_str(&content)?;
 
         Ok(config)
     }
@@ -0,0 +1,670 @@
+//! Support for splitting the config into multiple files and overriding parts
+//! of it per host.
+//!
+//! The files are merged on the RON level before deserialization so that the
+//! values keep exactly the same syntax as if they were written in a single
+//! file. Anonymous structs and maps are merged key by key, every other value
+//! replaces the previous one as a whole.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use super::{DeserError, utils::tilde_expand};
+
+const INCLUDE_KEY: &str = "include";
+const HOSTS_KEY: &str = "hosts";
+
+#[derive(Debug)]
+pub struct ResolvedConfig {
+    /// Merged content of all of the files
+    pub content: String,
+    /// Canonical paths of all of the files the config was assembled from, the
+    /// main config file first
+    pub files: Vec<PathBuf>,
+}
+
+/// Reads config at the given path and merges in all of its includes and the
+/// overrides for the given host. If the config uses neither, its content is
+/// returned untouched so that error positions match the file.
+pub fn resolve(path: &Path, hostname: Option<&str>) -> Result<ResolvedConfig, DeserError> {
+    let content = std::fs::read_to_string(path)?;
+    let main_path = std::fs::canonicalize(path)?;
+
+    let Ok((attributes, root)) = Parser::new(&content).parse_document() else {
+        // Let ron report the error with proper position
+        return Ok(ResolvedConfig { content, files: vec![main_path] });
+    };
+    if !root.has_field(INCLUDE_KEY) && !root.has_field(HOSTS_KEY) {
+        return Ok(ResolvedConfig { content, files: vec![main_path] });
+    }
+
+    let mut resolver = Resolver {
+        hostname,
+        files: vec![main_path.clone()],
+        stack: vec![main_path.clone()],
+        attributes,
+        host_overrides: Vec::new(),
+    };
+    let mut merged = resolver.resolve_node(root, &main_path)?;
+    for host_override in std::mem::take(&mut resolver.host_overrides) {
+        merged = merged.merge(host_override);
+    }
+
+    let mut content = resolver.attributes.join("\n");
+    content.push('\n');
+    merged.write(&mut content, 0);
+
+    Ok(ResolvedConfig { content, files: resolver.files })
+}
+
+pub fn hostname() -> Option<String> {
+    sysinfo::System::host_name()
+}
+
+/// Parses the structure of a single RON document without resolving any of
+/// its includes.
+pub(super) fn parse(content: &str) -> Result<Node> {
+    Ok(Parser::new(content).parse_document()?.1)
+}
+
+struct Resolver<'a> {
+    hostname: Option<&'a str>,
+    files: Vec<PathBuf>,
+    /// Files currently being resolved, used to detect circular includes
+    stack: Vec<PathBuf>,
+    attributes: Vec<String>,
+    host_overrides: Vec<Node>,
+}
+
+impl Resolver<'_> {
+    fn load(&mut self, path: &Path) -> Result<Node> {
+        if self.stack.iter().any(|p| p == path) {
+            bail!("Circular include of config file '{}'", path.display());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read included config file '{}'", path.display()))?;
+        let (attributes, root) = Parser::new(&content).parse_document().with_context(|| {
+            format!("Failed to parse included config file '{}'", path.display())
+        })?;
+
+        for attribute in attributes {
+            if !self.attributes.contains(&attribute) {
+                self.attributes.push(attribute);
+            }
+        }
+        if !self.files.iter().any(|p| p == path) {
+            self.files.push(path.to_owned());
+        }
+
+        self.stack.push(path.to_owned());
+        let result = self.resolve_node(root, path);
+        self.stack.pop();
+
+        result
+    }
+
+    /// Replaces the root node with the result of merging all of its includes
+    /// and the node itself on top. Host overrides are collected to be applied
+    /// after everything else.
+    fn resolve_node(&mut self, mut root: Node, path: &Path) -> Result<Node> {
+        let includes = root.take_field(INCLUDE_KEY);
+        if let Some(hosts) = root.take_field(HOSTS_KEY) {
+            self.collect_host_overrides(hosts, path)?;
+        }
+
+        let Some(includes) = includes else {
+            return Ok(root);
+        };
+        let Node::Raw(includes) = includes else {
+            bail!("'{INCLUDE_KEY}' in '{}' must be a list of paths", path.display());
+        };
+        let includes: Vec<String> = ron::de::from_str(&includes).with_context(|| {
+            format!("'{INCLUDE_KEY}' in '{}' must be a list of paths", path.display())
+        })?;
+
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("/"));
+        let mut merged = Node::Struct(Vec::new());
+        for include in includes {
+            let include = base_dir.join(tilde_expand(&include).as_ref());
+            // The same file can be reached by different paths, ie. through
+            // "../" or a symlink
+            let include = std::fs::canonicalize(&include).with_context(|| {
+                format!("Failed to read included config file '{}'", include.display())
+            })?;
+            let node = self.load(&include)?;
+            merged = merged.merge(node);
+        }
+
+        Ok(merged.merge(root))
+    }
+
+    fn collect_host_overrides(&mut self, hosts: Node, path: &Path) -> Result<()> {
+        let Node::Map(hosts) = hosts else {
+            bail!("'{HOSTS_KEY}' in '{}' must be a map of hostname to config", path.display());
+        };
+        let Some(hostname) = self.hostname else {
+            log::warn!("Could not determine hostname, host specific config overrides are skipped");
+            return Ok(());
+        };
+
+        for (key, value) in hosts {
+            let name: String = ron::de::from_str(&key).with_context(|| {
+                format!("Invalid hostname '{key}' in '{HOSTS_KEY}' in '{}'", path.display())
+            })?;
+            if name == hostname {
+                log::debug!(hostname, path:?; "Applying host specific config override");
+                self.host_overrides.push(value);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Node {
+    /// Anonymous struct, ie. `(key: value)`, merged field by field
+    Struct(Vec<(String, Node)>),
+    /// Map, ie. `{"key": value}`, merged entry by entry
+    Map(Vec<(String, Node)>),
+    /// Any other value, replaced as a whole
+    Raw(String),
+}
+
+impl Node {
+    fn merge(self, other: Node) -> Node {
+        match (self, other) {
+            (Node::Struct(mut base), Node::Struct(other)) => {
+                merge_entries(&mut base, other);
+                Node::Struct(base)
+            }
+            (Node::Map(mut base), Node::Map(other)) => {
+                merge_entries(&mut base, other);
+                Node::Map(base)
+            }
+            (_, other) => other,
+        }
+    }
+
+    fn has_field(&self, key: &str) -> bool {
+        matches!(self, Node::Struct(fields) if fields.iter().any(|(k, _)| k == key))
+    }
+
+    pub(super) fn field(&self, key: &str) -> Option<&Node> {
+        let Node::Struct(fields) = self else {
+            return None;
+        };
+        fields.iter().find(|(k, _)| k == key).map(|(_, value)| value)
+    }
+
+    fn take_field(&mut self, key: &str) -> Option<Node> {
+        let Node::Struct(fields) = self else {
+            return None;
+        };
+        let idx = fields.iter().position(|(k, _)| k == key)?;
+        Some(fields.remove(idx).1)
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        let (open, close, entries) = match self {
+            Node::Raw(value) => {
+                out.push_str(value);
+                return;
+            }
+            Node::Struct(entries) => ('(', ')', entries),
+            Node::Map(entries) => ('{', '}', entries),
+        };
+
+        out.push(open);
+        out.push('\n');
+        for (key, value) in entries {
+            out.push_str(&" ".repeat(indent + 4));
+            out.push_str(key);
+            out.push_str(": ");
+            value.write(out, indent + 4);
+            out.push_str(",\n");
+        }
+        out.push_str(&" ".repeat(indent));
+        out.push(close);
+    }
+}
+
+fn merge_entries(base: &mut Vec<(String, Node)>, other: Vec<(String, Node)>) {
+    for (key, value) in other {
+        if let Some(idx) = base.iter().position(|(k, _)| *k == key) {
+            let (key, existing) = base.remove(idx);
+            base.insert(idx, (key, existing.merge(value)));
+        } else {
+            base.push((key, value));
+        }
+    }
+}
+
+/// Parses just enough of RON to find the boundaries of structs, maps and
+/// their values. Everything else is kept as raw text.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn parse_document(mut self) -> Result<(Vec<String>, Node)> {
+        let mut attributes = Vec::new();
+        self.skip_trivia();
+        while self.rest().starts_with("#![") {
+            let start = self.pos;
+            self.pos += 2;
+            self.skip_raw_value(&[])?;
+            attributes.push(self.src[start..self.pos].to_owned());
+            self.skip_trivia();
+        }
+
+        // Top level struct may optionally be named
+        let checkpoint = self.pos;
+        if self.identifier().is_some() {
+            self.skip_trivia();
+            if self.peek() != Some('(') {
+                self.pos = checkpoint;
+            }
+        }
+
+        let root = self.value()?;
+        self.skip_trivia();
+        if self.pos != self.src.len() {
+            bail!("Unexpected trailing characters at {}", self.pos);
+        }
+
+        Ok((attributes, root))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_trivia();
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            c => Err(anyhow!("Expected '{expected}' at {}, got {c:?}", self.pos)),
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                self.skip_block_comment();
+            } else if self.peek().is_some_and(char::is_whitespace) {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self) {
+        let mut depth = 0;
+        while !self.rest().is_empty() {
+            if self.rest().starts_with("/*") {
+                depth += 1;
+                self.pos += 2;
+            } else if self.rest().starts_with("*/") {
+                depth -= 1;
+                self.pos += 2;
+                if depth == 0 {
+                    return;
+                }
+            } else {
+                self.bump();
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        if self.rest().starts_with("r#") {
+            self.pos += 2;
+        }
+        if !self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') {
+            self.pos = start;
+            return None;
+        }
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.bump();
+        }
+        Some(&self.src[start..self.pos])
+    }
+
+    fn value(&mut self) -> Result<Node> {
+        self.skip_trivia();
+        match self.peek() {
+            Some('(') if self.is_struct_start() => self.structure(),
+            Some('{') => self.map(),
+            _ => {
+                let start = self.pos;
+                self.skip_raw_value(&[','])?;
+                let value = &self.src[start..self.pos];
+                if value.is_empty() {
+                    bail!("Expected a value at {start}");
+                }
+                Ok(Node::Raw(value.to_owned()))
+            }
+        }
+    }
+
+    fn is_struct_start(&mut self) -> bool {
+        let checkpoint = self.pos;
+        self.bump();
+        self.skip_trivia();
+        let result = self.peek() == Some(')') || {
+            self.identifier().is_some() && {
+                self.skip_trivia();
+                self.rest().starts_with(':') && !self.rest().starts_with("::")
+            }
+        };
+        self.pos = checkpoint;
+        result
+    }
+
+    fn structure(&mut self) -> Result<Node> {
+        self.expect('(')?;
+        let mut fields = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(')') {
+                self.bump();
+                break;
+            }
+            let key = self
+                .identifier()
+                .with_context(|| format!("Expected field name at {}", self.pos))?
+                .to_owned();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_trivia();
+            if self.peek() == Some(',') {
+                self.bump();
+            }
+        }
+        Ok(Node::Struct(fields))
+    }
+
+    fn map(&mut self) -> Result<Node> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some('}') {
+                self.bump();
+                break;
+            }
+            let start = self.pos;
+            self.skip_raw_value(&[':'])?;
+            let key = self.src[start..self.pos].to_owned();
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_trivia();
+            if self.peek() == Some(',') {
+                self.bump();
+            }
+        }
+        Ok(Node::Map(entries))
+    }
+
+    /// Skips a single value which is not further parsed. Stops before any of
+    /// the terminators or a closing bracket at the top level. Position is left
+    /// right after the last significant character so that trailing comments
+    /// are not part of the value.
+    fn skip_raw_value(&mut self, terminators: &[char]) -> Result<()> {
+        let mut depth = 0_usize;
+        let mut end = self.pos;
+        loop {
+            self.skip_trivia();
+            let Some(c) = self.peek() else {
+                break;
+            };
+            if depth == 0 && (terminators.contains(&c) || matches!(c, ')' | ']' | '}')) {
+                break;
+            }
+            match c {
+                '(' | '[' | '{' => {
+                    depth += 1;
+                    self.bump();
+                }
+                ')' | ']' | '}' => {
+                    depth -= 1;
+                    self.bump();
+                    if depth == 0 && terminators.is_empty() {
+                        end = self.pos;
+                        break;
+                    }
+                }
+                '"' => self.skip_string()?,
+                '\'' => self.skip_char()?,
+                'r' if self.is_raw_string_start() => self.skip_raw_string()?,
+                _ => {
+                    self.bump();
+                }
+            }
+            end = self.pos;
+        }
+        self.pos = end;
+        Ok(())
+    }
+
+    fn skip_string(&mut self) -> Result<()> {
+        self.bump();
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '"' => return Ok(()),
+                _ => {}
+            }
+        }
+        bail!("Unterminated string")
+    }
+
+    fn skip_char(&mut self) -> Result<()> {
+        self.bump();
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '\'' => return Ok(()),
+                _ => {}
+            }
+        }
+        bail!("Unterminated char")
+    }
+
+    fn is_raw_string_start(&self) -> bool {
+        let prev_is_ident = self.src[..self.pos]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        !prev_is_ident && self.rest()[1..].trim_start_matches('#').starts_with('"')
+    }
+
+    fn skip_raw_string(&mut self) -> Result<()> {
+        self.bump();
+        let hashes = self.rest().len() - self.rest().trim_start_matches('#').len();
+        self.pos += hashes + 1;
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let len = self.rest().find(&terminator).context("Unterminated raw string")?;
+        self.pos += len + terminator.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{Node, Parser, resolve};
+    use crate::{config::ConfigFile, tests::fixtures::temp_dir::TempDir};
+
+    fn parse(input: &str) -> Node {
+        Parser::new(input).parse_document().unwrap().1
+    }
+
+    #[test]
+    fn parses_nested_structs_and_raw_values() {
+        let result = parse(
+            r##"(
+                // comment
+                address: "127.0.0.1:6600", // trailing comment
+                album_art: (method: Kitty, max_size_px: (width: 1, height: 2)),
+                keybinds: (global: {":": CommandMode, "1": SwitchToTab("Queue")}),
+                list: ["a, b", r#"c)"#],
+                /* block */ tabs: [(name: "x")],
+            )"##,
+        );
+
+        assert_eq!(
+            result,
+            Node::Struct(vec![
+                ("address".into(), Node::Raw("\"127.0.0.1:6600\"".into())),
+                (
+                    "album_art".into(),
+                    Node::Struct(vec![
+                        ("method".into(), Node::Raw("Kitty".into())),
+                        (
+                            "max_size_px".into(),
+                            Node::Struct(vec![
+                                ("width".into(), Node::Raw("1".into())),
+                                ("height".into(), Node::Raw("2".into())),
+                            ])
+                        ),
+                    ])
+                ),
+                (
+                    "keybinds".into(),
+                    Node::Struct(vec![(
+                        "global".into(),
+                        Node::Map(vec![
+                            ("\":\"".into(), Node::Raw("CommandMode".into())),
+                            ("\"1\"".into(), Node::Raw("SwitchToTab(\"Queue\")".into())),
+                        ])
+                    )])
+                ),
+                ("list".into(), Node::Raw("[\"a, b\", r#\"c)\"#]".into())),
+                ("tabs".into(), Node::Raw("[(name: \"x\")]".into())),
+            ])
+        );
+    }
+
+    #[test]
+    fn merges_structs_and_maps_deeply() {
+        let base = parse(r#"(a: 1, b: (c: 2, d: 3), e: {"x": 1, "y": 2}, f: [1, 2])"#);
+        let other = parse(r#"(b: (d: 4), e: {"y": 3, "z": 4}, f: [3])"#);
+
+        assert_eq!(
+            base.merge(other),
+            parse(r#"(a: 1, b: (c: 2, d: 4), e: {"x": 1, "y": 3, "z": 4}, f: [3])"#)
+        );
+    }
+
+    #[test]
+    fn config_without_includes_is_untouched() {
+        let dir = TempDir::new("untouched");
+        let content = "#![enable(implicit_some)]\n(address: \"localhost:1234\")";
+        let path = dir.write("config.ron", content);
+
+        let result = resolve(&path, Some("host")).unwrap();
+
+        assert_eq!(result.content, content);
+        assert_eq!(result.files, vec![path]);
+    }
+
+    #[test]
+    fn merges_includes_and_host_overrides() {
+        let dir = TempDir::new("merge");
+        let base = dir.write(
+            "base.ron",
+            r#"#![enable(implicit_some)]
+            (
+                address: "base:6600",
+                volume_step: 10,
+                album_art: (method: Kitty, max_size_px: (width: 100, height: 100)),
+                hosts: {
+                    "laptop": (album_art: (method: Sixel)),
+                },
+            )"#,
+        );
+        let main = dir.write(
+            "config.ron",
+            r#"(
+                include: ["base.ron"],
+                address: "main:6600",
+                hosts: {
+                    "laptop": (cache_dir: "/tmp/laptop"),
+                    "desktop": (address: "desktop:6600"),
+                },
+            )"#,
+        );
+
+        let result = resolve(&main, Some("laptop")).unwrap();
+        let config: ConfigFile = ron::de::from_str(&result.content).unwrap();
+
+        assert_eq!(result.files, vec![main, base]);
+        assert_eq!(config.address, "main:6600");
+        assert_eq!(config.volume_step, 10);
+        assert_eq!(config.cache_dir, Some(PathBuf::from("/tmp/laptop")));
+        assert_eq!(config.album_art.method, crate::config::album_art::ImageMethodFile::Sixel);
+        assert_eq!(config.album_art.max_size_px, (100, 100).into());
+    }
+
+    #[test]
+    fn detects_circular_includes() {
+        let dir = TempDir::new("circular");
+        dir.write("a.ron", r#"(include: ["config.ron"])"#);
+        let main = dir.write("config.ron", r#"(include: ["a.ron"])"#);
+
+        let err = resolve(&main, None).unwrap_err();
+
+        assert!(err.to_string().contains("Circular include"), "{err}");
+    }
+
+    #[test]
+    fn detects_circular_includes_through_parent_dir() {
+        let dir = TempDir::new("circular-parent");
+        std::fs::create_dir_all(dir.0.join("dir")).unwrap();
+        dir.write("dir/a.ron", r#"(include: ["../dir/a.ron"])"#);
+        let main = dir.write("config.ron", r#"(include: ["dir/a.ron"])"#);
+
+        let err = resolve(&main, None).unwrap_err();
+
+        assert!(err.to_string().contains("Circular include"), "{err}");
+    }
+
+    #[test]
+    fn reports_missing_include() {
+        let dir = TempDir::new("missing");
+        let main = dir.write("config.ron", r#"(include: ["nope.ron"])"#);
+
+        let err = resolve(&main, None).unwrap_err();
+
+        assert!(err.to_string().contains(&Path::new("nope.ron").display().to_string()), "{err}");
+    }
+}
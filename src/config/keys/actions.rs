@@ -1,7 +1,8 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, fmt::Display, sync::Arc};
 
 use itertools::Itertools;
-use strum::{Display, EnumDiscriminants, VariantArray};
+use serde::de::{self, DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor};
+use strum::{Display, EnumDiscriminants, VariantArray, VariantNames};
 
 use super::ToDescription;
 use crate::config::{tabs::TabName, utils::tilde_expand};
@@ -35,6 +36,7 @@ pub enum GlobalAction {
     Update,
     Rescan,
     CommandMode,
+    CommandPalette,
     NextTab,
     PreviousTab,
     #[strum(to_string = "SwitchToTab({0})")]
@@ -47,10 +49,26 @@ pub enum GlobalAction {
         command: Arc<Vec<String>>,
         description: Option<String>,
     },
+    Script {
+        name: String,
+        description: Option<String>,
+    },
+    Chain(Arc<Vec<ChainStep>>),
+    RecordMacro,
+    PlayMacro,
 }
 
 #[derive(
-    Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Ord, PartialOrd,
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
 )]
 pub enum GlobalActionFile {
     Quit,
@@ -85,9 +103,14 @@ pub enum GlobalActionFile {
     PlaylistsTab,
     SearchTab,
     CommandMode,
+    CommandPalette,
     Command { command: String, description: Option<String> },
     ExternalCommand { command: Vec<String>, description: Option<String> },
+    Script { name: String, description: Option<String> },
     AddRandom,
+    Chain(Vec<ChainStepFile>),
+    RecordMacro,
+    PlayMacro,
 }
 
 impl From<GlobalActionFile> for GlobalAction {
@@ -98,6 +121,7 @@ impl From<GlobalActionFile> for GlobalAction {
             GlobalActionFile::ShowDecoders => GlobalAction::ShowDecoders,
             GlobalActionFile::ShowCurrentSongInfo => GlobalAction::ShowCurrentSongInfo,
             GlobalActionFile::CommandMode => GlobalAction::CommandMode,
+            GlobalActionFile::CommandPalette => GlobalAction::CommandPalette,
             GlobalActionFile::Command { command, description } => {
                 GlobalAction::Command { command, description }
             }
@@ -133,10 +157,18 @@ impl From<GlobalActionFile> for GlobalAction {
                     description,
                 }
             }
+            GlobalActionFile::Script { name, description } => {
+                GlobalAction::Script { name, description }
+            }
             GlobalActionFile::AddRandom => GlobalAction::AddRandom,
             GlobalActionFile::ToggleSingleOnOff => GlobalAction::ToggleSingleOnOff,
             GlobalActionFile::ToggleConsumeOnOff => GlobalAction::ToggleConsumeOnOff,
             GlobalActionFile::SwitchPartition => GlobalAction::SwitchPartition,
+            GlobalActionFile::Chain(steps) => {
+                GlobalAction::Chain(Arc::new(steps.into_iter().map(ChainStep::from).collect()))
+            }
+            GlobalActionFile::RecordMacro => GlobalAction::RecordMacro,
+            GlobalActionFile::PlayMacro => GlobalAction::PlayMacro,
         }
     }
 }
@@ -171,77 +203,174 @@ impl ToDescription for GlobalAction {
             GlobalAction::SwitchToTab(name) => Cow::Owned(format!("Switch directly to {name} tab")),
             GlobalAction::ShowHelp => "Show keybinds".into(),
             GlobalAction::CommandMode => "Enter command mode".into(),
+            GlobalAction::CommandPalette => "Search and run any action by its description".into(),
             GlobalAction::Command { description: None, .. } => "Execute a command".into(),
             GlobalAction::Command { description: Some(desc), .. } => Cow::Owned(desc.to_owned()),
             GlobalAction::ExternalCommand { description: None, .. } => {
                 "Execute an external command".into()
             }
             GlobalAction::ExternalCommand { description: Some(desc), .. } => Cow::Owned(desc.to_string()),
+            GlobalAction::Script { name, description: None } => Cow::Owned(format!("Run script '{name}'")),
+            GlobalAction::Script { description: Some(desc), .. } => Cow::Owned(desc.to_string()),
             GlobalAction::AddRandom => "Add random songs to the queue".into(),
             GlobalAction::ToggleSingleOnOff => "Toggle single mode on or off, skipping oneshot".into(),
             GlobalAction::ToggleConsumeOnOff => "Toggle consume mode on or off, skipping oneshot".into(),
             GlobalAction::SwitchPartition => "Switch to partition".into(),
+            GlobalAction::Chain(steps) => Cow::Owned(format!("Run {}", steps.iter().join(", "))),
+            GlobalAction::RecordMacro => {
+                "Start recording a macro to the register pressed next, or stop the recording".into()
+            }
+            GlobalAction::PlayMacro => "Replay the macro from the register pressed next".into(),
         }
     }
 }
 
 // Albums actions
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum AlbumsActionsFile {}
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
+)]
+pub enum AlbumsActionsFile {
+    ToggleReverseSort,
+    CycleAlbumSortMode,
+    CycleAlbumDisplayMode,
+}
 
-#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum AlbumsActions {}
+#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy, EnumDiscriminants)]
+#[strum_discriminants(derive(VariantArray))]
+pub enum AlbumsActions {
+    ToggleReverseSort,
+    CycleAlbumSortMode,
+    CycleAlbumDisplayMode,
+}
 
 impl From<AlbumsActionsFile> for AlbumsActions {
-    fn from(_value: AlbumsActionsFile) -> Self {
-        unreachable!()
+    fn from(value: AlbumsActionsFile) -> Self {
+        match value {
+            AlbumsActionsFile::ToggleReverseSort => AlbumsActions::ToggleReverseSort,
+            AlbumsActionsFile::CycleAlbumSortMode => AlbumsActions::CycleAlbumSortMode,
+            AlbumsActionsFile::CycleAlbumDisplayMode => AlbumsActions::CycleAlbumDisplayMode,
+        }
     }
 }
 
 impl ToDescription for AlbumsActions {
     fn to_description(&self) -> Cow<'static, str> {
-        "".into()
+        match self {
+            AlbumsActions::ToggleReverseSort => "Reverse the order of albums",
+            AlbumsActions::CycleAlbumSortMode => "Switch between sorting albums by name and date",
+            AlbumsActions::CycleAlbumDisplayMode => {
+                "Switch between splitting albums by date and showing only their names"
+            }
+        }
+        .into()
     }
 }
 
 // Artists actions
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum ArtistsActionsFile {}
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
+)]
+pub enum ArtistsActionsFile {
+    CycleAlbumSortMode,
+    CycleAlbumDisplayMode,
+}
 
-#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum ArtistsActions {}
+#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy, EnumDiscriminants)]
+#[strum_discriminants(derive(VariantArray))]
+pub enum ArtistsActions {
+    CycleAlbumSortMode,
+    CycleAlbumDisplayMode,
+}
 
 impl ToDescription for ArtistsActions {
     fn to_description(&self) -> Cow<'static, str> {
-        "".into()
+        match self {
+            ArtistsActions::CycleAlbumSortMode => "Switch between sorting albums by name and date",
+            ArtistsActions::CycleAlbumDisplayMode => {
+                "Switch between splitting albums by date and showing only their names"
+            }
+        }
+        .into()
     }
 }
 
 impl From<ArtistsActionsFile> for ArtistsActions {
-    fn from(_value: ArtistsActionsFile) -> Self {
-        unreachable!()
+    fn from(value: ArtistsActionsFile) -> Self {
+        match value {
+            ArtistsActionsFile::CycleAlbumSortMode => ArtistsActions::CycleAlbumSortMode,
+            ArtistsActionsFile::CycleAlbumDisplayMode => ArtistsActions::CycleAlbumDisplayMode,
+        }
     }
 }
 
 // Directories actions
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum DirectoriesActionsFile {}
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
+)]
+pub enum DirectoriesActionsFile {
+    CycleSortMode,
+    ToggleGroupByType,
+    ToggleReverseSort,
+}
 
-#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum DirectoriesActions {}
+#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy, EnumDiscriminants)]
+#[strum_discriminants(derive(VariantArray))]
+pub enum DirectoriesActions {
+    CycleSortMode,
+    ToggleGroupByType,
+    ToggleReverseSort,
+}
 
 impl ToDescription for DirectoriesActions {
     fn to_description(&self) -> Cow<'static, str> {
-        "".into()
+        match self {
+            DirectoriesActions::CycleSortMode => {
+                "Switch between sorting by the song format and by modification time"
+            }
+            DirectoriesActions::ToggleGroupByType => "Toggle listing directories before songs",
+            DirectoriesActions::ToggleReverseSort => "Reverse the sort order",
+        }
+        .into()
     }
 }
 
 impl From<DirectoriesActionsFile> for DirectoriesActions {
-    fn from(_value: DirectoriesActionsFile) -> Self {
-        unreachable!()
+    fn from(value: DirectoriesActionsFile) -> Self {
+        match value {
+            DirectoriesActionsFile::CycleSortMode => DirectoriesActions::CycleSortMode,
+            DirectoriesActionsFile::ToggleGroupByType => DirectoriesActions::ToggleGroupByType,
+            DirectoriesActionsFile::ToggleReverseSort => DirectoriesActions::ToggleReverseSort,
+        }
     }
 }
 
@@ -284,7 +413,18 @@ impl ToDescription for LogsActions {
 
 // Queue actions
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
+)]
 pub enum QueueActionsFile {
     Delete,
     DeleteAll,
@@ -345,7 +485,16 @@ impl ToDescription for QueueActions {
 // Common actions
 
 #[derive(
-    Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Ord, PartialOrd,
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
 )]
 pub enum CommonActionFile {
     Down,
@@ -513,38 +662,296 @@ impl From<CommonActionFile> for CommonAction {
 
 // Playlist actions
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum PlaylistsActionsFile {}
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
+)]
+pub enum PlaylistsActionsFile {
+    Duplicate,
+    Export,
+    Sort,
+}
 
-#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum PlaylistsActions {}
+#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy, EnumDiscriminants)]
+#[strum_discriminants(derive(VariantArray))]
+pub enum PlaylistsActions {
+    Duplicate,
+    Export,
+    Sort,
+}
 
 impl ToDescription for PlaylistsActions {
     fn to_description(&self) -> Cow<'static, str> {
-        "".into()
+        match self {
+            PlaylistsActions::Duplicate => {
+                "Save a copy of the playlist under cursor with a new name"
+            }
+            PlaylistsActions::Export => "Export the playlist under cursor to an m3u file",
+            PlaylistsActions::Sort => {
+                "Sort songs in the playlist under cursor according to browser_song_sort"
+            }
+        }
+        .into()
     }
 }
 
 impl From<PlaylistsActionsFile> for PlaylistsActions {
-    fn from(_value: PlaylistsActionsFile) -> Self {
-        unreachable!()
+    fn from(value: PlaylistsActionsFile) -> Self {
+        match value {
+            PlaylistsActionsFile::Duplicate => PlaylistsActions::Duplicate,
+            PlaylistsActionsFile::Export => PlaylistsActions::Export,
+            PlaylistsActionsFile::Sort => PlaylistsActions::Sort,
+        }
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum SearchActionsFile {}
+// Search actions
 
-#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum SearchActions {}
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Ord,
+    PartialOrd,
+    VariantNames,
+)]
+pub enum SearchActionsFile {
+    ResetQuery,
+    SaveQuery,
+}
+
+#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy, EnumDiscriminants)]
+#[strum_discriminants(derive(VariantArray))]
+pub enum SearchActions {
+    ResetQuery,
+    SaveQuery,
+}
 
 impl ToDescription for SearchActions {
     fn to_description(&self) -> Cow<'static, str> {
-        "".into()
+        match self {
+            SearchActions::ResetQuery => "Clear all search inputs and reset the search mode",
+            SearchActions::SaveQuery => "Save all songs matching the current query as a playlist",
+        }
+        .into()
     }
 }
 
 impl From<SearchActionsFile> for SearchActions {
-    fn from(_value: SearchActionsFile) -> Self {
-        unreachable!()
+    fn from(value: SearchActionsFile) -> Self {
+        match value {
+            SearchActionsFile::ResetQuery => SearchActions::ResetQuery,
+            SearchActionsFile::SaveQuery => SearchActions::SaveQuery,
+        }
+    }
+}
+
+// Chained actions
+
+/// Single action of a [`GlobalAction::Chain`]. Actions are written only by
+/// their name, so the same name can refer to an action in multiple sections,
+/// ie. `Delete` is both a navigation and a queue action. The step then behaves
+/// the same as a key bound to the action in all of those sections.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Ord, PartialOrd)]
+pub struct ChainStepFile {
+    pub global: Option<GlobalActionFile>,
+    pub navigation: Option<CommonActionFile>,
+    pub queue: Option<QueueActionsFile>,
+    pub albums: Option<AlbumsActionsFile>,
+    pub artists: Option<ArtistsActionsFile>,
+    pub directories: Option<DirectoriesActionsFile>,
+    pub playlists: Option<PlaylistsActionsFile>,
+    pub search: Option<SearchActionsFile>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
+pub struct ChainStep {
+    pub global: Option<GlobalAction>,
+    pub navigation: Option<CommonAction>,
+    pub queue: Option<QueueActions>,
+    pub albums: Option<AlbumsActions>,
+    pub artists: Option<ArtistsActions>,
+    pub directories: Option<DirectoriesActions>,
+    pub playlists: Option<PlaylistsActions>,
+    pub search: Option<SearchActions>,
+}
+
+impl From<ChainStepFile> for ChainStep {
+    fn from(value: ChainStepFile) -> Self {
+        Self {
+            global: value.global.map(Into::into),
+            navigation: value.navigation.map(Into::into),
+            queue: value.queue.map(Into::into),
+            albums: value.albums.map(Into::into),
+            artists: value.artists.map(Into::into),
+            directories: value.directories.map(Into::into),
+            playlists: value.playlists.map(Into::into),
+            search: value.search.map(Into::into),
+        }
+    }
+}
+
+impl Display for ChainStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self
+            .global
+            .as_ref()
+            .map(ToString::to_string)
+            .or_else(|| self.navigation.map(|a| a.to_string()))
+            .or_else(|| self.queue.map(|a| a.to_string()))
+            .or_else(|| self.albums.map(|a| a.to_string()))
+            .or_else(|| self.artists.map(|a| a.to_string()))
+            .or_else(|| self.directories.map(|a| a.to_string()))
+            .or_else(|| self.playlists.map(|a| a.to_string()))
+            .or_else(|| self.search.map(|a| a.to_string()))
+            .unwrap_or_default();
+        write!(f, "{name}")
+    }
+}
+
+impl serde::Serialize for ChainStepFile {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(action) = &self.global {
+            action.serialize(serializer)
+        } else if let Some(action) = &self.navigation {
+            action.serialize(serializer)
+        } else if let Some(action) = &self.queue {
+            action.serialize(serializer)
+        } else if let Some(action) = &self.albums {
+            action.serialize(serializer)
+        } else if let Some(action) = &self.artists {
+            action.serialize(serializer)
+        } else if let Some(action) = &self.directories {
+            action.serialize(serializer)
+        } else if let Some(action) = &self.playlists {
+            action.serialize(serializer)
+        } else if let Some(action) = &self.search {
+            action.serialize(serializer)
+        } else {
+            serializer.serialize_unit()
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ChainStepFile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_enum("ChainStep", &[], ChainStepVisitor)
+    }
+}
+
+struct ChainStepVisitor;
+
+impl<'de> Visitor<'de> for ChainStepVisitor {
+    type Value = ChainStepFile;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an action")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (VariantName(name), variant) = data.variant()?;
+
+        // Only global actions can have any data, the rest are matched by their
+        // name alone
+        let mut step = ChainStepFile::default();
+        if GlobalActionFile::VARIANTS.contains(&name.as_str()) {
+            step.global =
+                Some(<GlobalActionFile as serde::Deserialize>::deserialize(ReplayVariant {
+                    name: name.clone(),
+                    variant,
+                })?);
+        } else {
+            variant.unit_variant()?;
+        }
+        step.navigation = unit_variant_by_name(&name)?;
+        step.queue = unit_variant_by_name(&name)?;
+        step.albums = unit_variant_by_name(&name)?;
+        step.artists = unit_variant_by_name(&name)?;
+        step.directories = unit_variant_by_name(&name)?;
+        step.playlists = unit_variant_by_name(&name)?;
+        step.search = unit_variant_by_name(&name)?;
+
+        if step == ChainStepFile::default() {
+            return Err(de::Error::custom(format!("unknown action `{name}`")));
+        }
+        Ok(step)
+    }
+}
+
+fn unit_variant_by_name<'de, T, E>(name: &str) -> Result<Option<T>, E>
+where
+    T: serde::Deserialize<'de> + VariantNames,
+    E: de::Error,
+{
+    if T::VARIANTS.contains(&name) {
+        T::deserialize(name.into_deserializer()).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Name of an enum variant, ie. the action name in a chain
+struct VariantName(String);
+
+impl<'de> serde::Deserialize<'de> for VariantName {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl Visitor<'_> for NameVisitor {
+            type Value = VariantName;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an action name")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(VariantName(v.to_owned()))
+            }
+        }
+
+        deserializer.deserialize_identifier(NameVisitor)
+    }
+}
+
+/// Enum deserializer for a variant whose name was already read, used to pass
+/// the rest of the variant to the derived deserializer of the action.
+struct ReplayVariant<A> {
+    name: String,
+    variant: A,
+}
+
+impl<'de, A: VariantAccess<'de>> de::Deserializer<'de> for ReplayVariant<A> {
+    type Error = A::Error;
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+
+    fn deserialize_any<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, Self::Error> {
+        visitor.visit_enum(self)
+    }
+}
+
+impl<'de, A: VariantAccess<'de>> EnumAccess<'de> for ReplayVariant<A> {
+    type Error = A::Error;
+    type Variant = A;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, A), A::Error> {
+        let name = seed.deserialize(self.name.into_deserializer())?;
+        Ok((name, self.variant))
     }
 }
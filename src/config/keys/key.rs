@@ -4,6 +4,9 @@ use crossterm::event::{KeyCode, KeyModifiers};
 use itertools::Itertools;
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 
+/// Upper bound of the count which can be typed before a keybind
+pub const MAX_COUNT: usize = 9999;
+
 #[derive(Debug, SerializeDisplay, DeserializeFromStr, PartialEq, Eq, Hash, Clone)]
 pub struct Key {
     pub key: KeyCode,
@@ -183,6 +186,104 @@ impl FromStr for Key {
         Ok(Self { key, modifiers })
     }
 }
+/// One or more keys which have to be pressed in order to trigger a keybind,
+/// ie. `gg` or `<Space>pl`.
+#[derive(Debug, SerializeDisplay, DeserializeFromStr, PartialEq, Eq, Hash, Clone, Default)]
+pub struct KeySequence(pub Vec<Key>);
+
+impl KeySequence {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn last(&self) -> Option<&Key> {
+        self.0.last()
+    }
+
+    /// Whether this sequence is shorter than and the start of the other one
+    pub fn is_prefix_of(&self, other: &KeySequence) -> bool {
+        other.len() > self.len() && other.0.starts_with(&self.0)
+    }
+
+    /// Returns the rest of this sequence if it is longer than and starts with
+    /// the given prefix.
+    pub fn strip_prefix(&self, prefix: &KeySequence) -> Option<KeySequence> {
+        prefix.is_prefix_of(self).then(|| KeySequence(self.0[prefix.len()..].to_vec()))
+    }
+
+    /// Parses the sequence as a vim-style count, ie. "12" in "12j". Counts
+    /// cannot start with a zero and are capped at [`MAX_COUNT`].
+    pub fn as_count(&self) -> Option<usize> {
+        let mut count: usize = 0;
+        for (idx, key) in self.0.iter().enumerate() {
+            let KeyCode::Char(c) = key.key else {
+                return None;
+            };
+            let digit = c.to_digit(10)?;
+            if !key.modifiers.is_empty() || (idx == 0 && digit == 0) {
+                return None;
+            }
+            count = count.saturating_mul(10).saturating_add(digit as usize);
+        }
+
+        (count > 0).then_some(count.min(MAX_COUNT))
+    }
+}
+
+impl From<Key> for KeySequence {
+    fn from(value: Key) -> Self {
+        Self(vec![value])
+    }
+}
+
+impl Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in &self.0 {
+            write!(f, "{key}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Modifiers without angle brackets, ie. "C-a", are kept as a single
+        // key for backwards compatibility
+        let is_bare_modifier = |s: &str| ["C-", "A-", "S-"].iter().any(|m| s.starts_with(m));
+        if s.is_empty() || (!s.starts_with('<') && s.len() > 2 && is_bare_modifier(s)) {
+            return Ok(Self(vec![s.parse()?]));
+        }
+
+        let chars = s.chars().collect_vec();
+        let mut keys = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            // Try to find the end of a special key in angle brackets, ie.
+            // <C-a>, the closing bracket may be the key itself as in <C-S->>
+            let special = (chars[i] == '<')
+                .then(|| {
+                    (i + 2..chars.len()).filter(|end| chars[*end] == '>').find_map(|end| {
+                        let key: Key = chars[i..=end].iter().collect::<String>().parse().ok()?;
+                        Some((end, key))
+                    })
+                })
+                .flatten();
+
+            if let Some((end, key)) = special {
+                keys.push(key);
+                i = end + 1;
+            } else {
+                keys.push(chars[i].to_string().parse()?);
+                i += 1;
+            }
+        }
+
+        Ok(Self(keys))
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -256,4 +357,49 @@ mod tests {
         let deserialized: Key = input.parse().unwrap();
         assert_eq!(deserialized, expected);
     }
+
+    #[rstest]
+    #[case("gg",         vec![Key { key: KeyCode::Char('g'), modifiers: KeyModifiers::NONE }, Key { key: KeyCode::Char('g'), modifiers: KeyModifiers::NONE }])]
+    #[case("<Space>pl",  vec![Key { key: KeyCode::Char(' '), modifiers: KeyModifiers::NONE }, Key { key: KeyCode::Char('p'), modifiers: KeyModifiers::NONE }, Key { key: KeyCode::Char('l'), modifiers: KeyModifiers::NONE }])]
+    #[case("<C-w>J",     vec![Key { key: KeyCode::Char('w'), modifiers: KeyModifiers::CONTROL }, Key { key: KeyCode::Char('J'), modifiers: KeyModifiers::SHIFT }])]
+    #[case("<<",         vec![Key { key: KeyCode::Char('<'), modifiers: KeyModifiers::NONE }, Key { key: KeyCode::Char('<'), modifiers: KeyModifiers::NONE }])]
+    #[case("g<C-S->>",   vec![Key { key: KeyCode::Char('g'), modifiers: KeyModifiers::NONE }, Key { key: KeyCode::Char('>'), modifiers: KeyModifiers::CONTROL | KeyModifiers::SHIFT }])]
+    #[case("<CR>",       vec![Key { key: KeyCode::Enter,     modifiers: KeyModifiers::NONE }])]
+    #[case("C-a",        vec![Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL }])]
+    fn sequence_deserialization(#[case] input: &str, #[case] expected: Vec<Key>) {
+        let deserialized: KeySequence = input.parse().unwrap();
+        assert_eq!(deserialized, KeySequence(expected));
+    }
+
+    #[rstest]
+    #[case("gg")]
+    #[case("<Space>pl")]
+    #[case("<C-w>J")]
+    #[case("<Tab><S-Tab>")]
+    fn sequence_serialization_round_trip(#[case] input: &str) {
+        let deserialized: KeySequence = input.parse().unwrap();
+        assert_eq!(deserialized.to_string(), input);
+    }
+
+    #[test]
+    fn sequence_strip_prefix() {
+        let sequence: KeySequence = "<Space>pl".parse().unwrap();
+
+        assert_eq!(sequence.strip_prefix(&"<Space>".parse().unwrap()), Some("pl".parse().unwrap()));
+        assert_eq!(sequence.strip_prefix(&"<Space>pl".parse().unwrap()), None);
+        assert_eq!(sequence.strip_prefix(&"p".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn sequence_as_count() {
+        let count = |s: &str| s.parse::<KeySequence>().unwrap().as_count();
+
+        assert_eq!(count("5"), Some(5));
+        assert_eq!(count("10"), Some(10));
+        assert_eq!(count("123456"), Some(MAX_COUNT));
+        assert_eq!(count("0"), None);
+        assert_eq!(count("05"), None);
+        assert_eq!(count("5j"), None);
+        assert_eq!(count("<C-5>"), None);
+    }
 }
@@ -7,9 +7,11 @@ use actions::LogsActionsFile;
 pub use actions::{
     AlbumsActions,
     ArtistsActions,
+    ChainStep,
     CommonAction,
     DirectoriesActions,
     GlobalAction,
+    PlaylistsActions,
     QueueActions,
     SearchActions,
 };
@@ -21,9 +23,11 @@ use actions::{
     GlobalActionFile,
     PlaylistsActionsFile,
     QueueActionsFile,
+    SearchActionsFile,
 };
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-pub use key::Key;
+use itertools::Itertools;
+pub use key::{Key, KeySequence};
 use serde::{Deserialize, Serialize};
 
 pub(crate) mod actions;
@@ -31,33 +35,43 @@ mod key;
 
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct KeyConfig {
-    pub global: HashMap<Key, GlobalAction>,
-    pub navigation: HashMap<Key, CommonAction>,
-    pub albums: HashMap<Key, AlbumsActions>,
-    pub artists: HashMap<Key, ArtistsActions>,
-    pub directories: HashMap<Key, DirectoriesActions>,
-    pub search: HashMap<Key, SearchActions>,
+    pub global: HashMap<KeySequence, GlobalAction>,
+    pub navigation: HashMap<KeySequence, CommonAction>,
+    pub albums: HashMap<KeySequence, AlbumsActions>,
+    pub artists: HashMap<KeySequence, ArtistsActions>,
+    pub directories: HashMap<KeySequence, DirectoriesActions>,
+    pub playlists: HashMap<KeySequence, PlaylistsActions>,
+    pub search: HashMap<KeySequence, SearchActions>,
     #[cfg(debug_assertions)]
-    pub logs: HashMap<Key, LogsActions>,
-    pub queue: HashMap<Key, QueueActions>,
+    pub logs: HashMap<KeySequence, LogsActions>,
+    pub queue: HashMap<KeySequence, QueueActions>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct KeyConfigFile {
     #[serde(default)]
-    pub global: HashMap<Key, GlobalActionFile>,
+    pub global: HashMap<KeySequence, GlobalActionFile>,
     #[serde(default)]
-    pub navigation: HashMap<Key, CommonActionFile>,
+    pub navigation: HashMap<KeySequence, CommonActionFile>,
+    #[serde(default)]
+    pub albums: HashMap<KeySequence, AlbumsActionsFile>,
+    #[serde(default)]
+    pub artists: HashMap<KeySequence, ArtistsActionsFile>,
+    #[serde(default)]
+    pub directories: HashMap<KeySequence, DirectoriesActionsFile>,
+    #[serde(default)]
+    pub playlists: HashMap<KeySequence, PlaylistsActionsFile>,
+    #[serde(default)]
+    pub search: HashMap<KeySequence, SearchActionsFile>,
     #[cfg(debug_assertions)]
     #[serde(default)]
-    pub logs: HashMap<Key, LogsActionsFile>,
+    pub logs: HashMap<KeySequence, LogsActionsFile>,
     #[serde(default)]
-    pub queue: HashMap<Key, QueueActionsFile>,
+    pub queue: HashMap<KeySequence, QueueActionsFile>,
 }
 
 impl Default for KeyConfigFile {
     #[rustfmt::skip]
-    #[allow(unused_imports)]
     fn default() -> Self {
         use GlobalActionFile as G;
         use CommonActionFile as C;
@@ -65,15 +79,17 @@ impl Default for KeyConfigFile {
         use ArtistsActionsFile as Ar;
         use DirectoriesActionsFile  as D;
         use PlaylistsActionsFile as P;
+        use SearchActionsFile as S;
         use KeyCode as K;
         use KeyModifiers as M;
         #[cfg(debug_assertions)]
         use LogsActionsFile as L;
         use QueueActionsFile as Q;
         Self {
-            global: HashMap::from([
+            global: bindings([
                 (Key { key: K::Char('q'), modifiers: M::NONE  }, G::Quit),
                 (Key { key: K::Char(':'), modifiers: M::NONE  }, G::CommandMode),
+                (Key { key: K::Char('p'), modifiers: M::CONTROL }, G::CommandPalette),
                 (Key { key: K::Char('~'), modifiers: M::NONE  }, G::ShowHelp),
                 (Key { key: K::Char('I'), modifiers: M::SHIFT }, G::ShowCurrentSongInfo),
                 (Key { key: K::Char('O'), modifiers: M::SHIFT }, G::ShowOutputs),
@@ -95,6 +111,8 @@ impl Default for KeyConfigFile {
                 (Key { key: K::BackTab,   modifiers: M::SHIFT }, G::PreviousTab),
                 (Key { key: K::Tab,       modifiers: M::NONE  }, G::NextTab),
                 (Key { key: K::Char('R'), modifiers: M::SHIFT }, G::AddRandom),
+                (Key { key: K::Char('Q'), modifiers: M::SHIFT }, G::RecordMacro),
+                (Key { key: K::Char('@'), modifiers: M::NONE  }, G::PlayMacro),
                 (Key { key: K::Char('1'), modifiers: M::NONE  }, G::SwitchToTab("Queue".to_string())),
                 (Key { key: K::Char('2'), modifiers: M::NONE  }, G::SwitchToTab("Directories".to_string())),
                 (Key { key: K::Char('3'), modifiers: M::NONE  }, G::SwitchToTab("Artists".to_string())),
@@ -103,7 +121,7 @@ impl Default for KeyConfigFile {
                 (Key { key: K::Char('6'), modifiers: M::NONE  }, G::SwitchToTab("Playlists".to_string())),
                 (Key { key: K::Char('7'), modifiers: M::NONE  }, G::SwitchToTab("Search".to_string())),
             ]),
-            navigation: HashMap::from([
+            navigation: bindings([
                 (Key { key: K::Char('k'), modifiers: M::NONE    }, C::Up),
                 (Key { key: K::Char('j'), modifiers: M::NONE    }, C::Down),
                 (Key { key: K::Char('l'), modifiers: M::NONE    }, C::Right),
@@ -137,20 +155,35 @@ impl Default for KeyConfigFile {
                 (Key { key: K::Char('i'), modifiers: M::NONE    }, C::FocusInput),
                 (Key { key: K::Char('B'), modifiers: M::SHIFT   }, C::ShowInfo),
             ]),
-            // albums: HashMap::from([
-            // ]),
-            // artists: HashMap::from([
-            // ]),
-            // directories: HashMap::from([
-            // ]),
-            // playlists: HashMap::from([
-            // ]),
+            albums: bindings([
+                (Key { key: K::Char('o'), modifiers: M::NONE    }, Al::ToggleReverseSort),
+                (Key { key: K::Char('S'), modifiers: M::SHIFT   }, Al::CycleAlbumSortMode),
+                (Key { key: K::Char('M'), modifiers: M::SHIFT   }, Al::CycleAlbumDisplayMode),
+            ]),
+            artists: bindings([
+                (Key { key: K::Char('S'), modifiers: M::SHIFT   }, Ar::CycleAlbumSortMode),
+                (Key { key: K::Char('M'), modifiers: M::SHIFT   }, Ar::CycleAlbumDisplayMode),
+            ]),
+            directories: bindings([
+                (Key { key: K::Char('S'), modifiers: M::SHIFT   }, D::CycleSortMode),
+                (Key { key: K::Char('T'), modifiers: M::SHIFT   }, D::ToggleGroupByType),
+                (Key { key: K::Char('o'), modifiers: M::NONE    }, D::ToggleReverseSort),
+            ]),
+            playlists: bindings([
+                (Key { key: K::Char('y'), modifiers: M::NONE    }, P::Duplicate),
+                (Key { key: K::Char('E'), modifiers: M::SHIFT   }, P::Export),
+                (Key { key: K::Char('S'), modifiers: M::SHIFT   }, P::Sort),
+            ]),
+            search: bindings([
+                (Key { key: K::Char('r'), modifiers: M::CONTROL }, S::ResetQuery),
+                (Key { key: K::Char('s'), modifiers: M::CONTROL }, S::SaveQuery),
+            ]),
             #[cfg(debug_assertions)]
-            logs: HashMap::from([
+            logs: bindings([
                 (Key { key: K::Char('D'), modifiers: M::SHIFT   }, L::Clear),
                 (Key { key: K::Char('S'), modifiers: M::SHIFT   }, L::ToggleScroll),
             ]),
-            queue: HashMap::from([
+            queue: bindings([
                 (Key { key: K::Char('d'), modifiers: M::NONE    }, Q::Delete),
                 (Key { key: K::Char('D'), modifiers: M::SHIFT   }, Q::DeleteAll),
                 (Key { key: K::Enter,     modifiers: M::NONE    }, Q::Play),
@@ -163,15 +196,68 @@ impl Default for KeyConfigFile {
     }
 }
 
+impl KeyConfig {
+    /// Returns the remaining keys and descriptions of all keybinds which
+    /// continue the given prefix, sorted by the remaining keys.
+    pub fn continuations(&self, prefix: &KeySequence) -> Vec<(KeySequence, Cow<'static, str>)> {
+        fn collect<'a, V: ToDescription + 'a>(
+            prefix: &'a KeySequence,
+            keybinds: &'a HashMap<KeySequence, V>,
+        ) -> impl Iterator<Item = (KeySequence, Cow<'static, str>)> + 'a {
+            keybinds.iter().filter_map(|(sequence, action)| {
+                Some((sequence.strip_prefix(prefix)?, action.to_description()))
+            })
+        }
+
+        let result = collect(prefix, &self.global)
+            .chain(collect(prefix, &self.navigation))
+            .chain(collect(prefix, &self.queue))
+            .chain(collect(prefix, &self.albums))
+            .chain(collect(prefix, &self.artists))
+            .chain(collect(prefix, &self.directories))
+            .chain(collect(prefix, &self.playlists))
+            .chain(collect(prefix, &self.search));
+        #[cfg(debug_assertions)]
+        let result = result.chain(collect(prefix, &self.logs));
+
+        result
+            .sorted_by(|a, b| a.0.to_string().cmp(&b.0.to_string()).then_with(|| a.1.cmp(&b.1)))
+            .dedup()
+            .collect()
+    }
+
+    /// Whether the given sequence is a prefix of any longer keybind
+    pub fn is_prefix(&self, sequence: &KeySequence) -> bool {
+        fn any<V>(sequence: &KeySequence, keybinds: &HashMap<KeySequence, V>) -> bool {
+            keybinds.keys().any(|k| sequence.is_prefix_of(k))
+        }
+
+        #[cfg(debug_assertions)]
+        let logs = any(sequence, &self.logs);
+        #[cfg(not(debug_assertions))]
+        let logs = false;
+
+        logs || any(sequence, &self.global)
+            || any(sequence, &self.navigation)
+            || any(sequence, &self.queue)
+            || any(sequence, &self.albums)
+            || any(sequence, &self.artists)
+            || any(sequence, &self.directories)
+            || any(sequence, &self.playlists)
+            || any(sequence, &self.search)
+    }
+}
+
 impl From<KeyConfigFile> for KeyConfig {
     fn from(value: KeyConfigFile) -> Self {
         KeyConfig {
             global: value.global.into_iter().map(|(k, v)| (k, v.into())).collect(),
             navigation: value.navigation.into_iter().map(|(k, v)| (k, v.into())).collect(),
-            albums: HashMap::new(),
-            artists: HashMap::new(),
-            directories: HashMap::new(),
-            search: HashMap::new(),
+            albums: value.albums.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            artists: value.artists.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            directories: value.directories.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            playlists: value.playlists.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            search: value.search.into_iter().map(|(k, v)| (k, v.into())).collect(),
             #[cfg(debug_assertions)]
             logs: value.logs.into_iter().map(|(k, v)| (k, v.into())).collect(),
             queue: value.queue.into_iter().map(|(k, v)| (k, v.into())).collect(),
@@ -185,58 +271,77 @@ impl From<KeyEvent> for Key {
     }
 }
 
+fn bindings<V, const N: usize>(bindings: [(Key, V); N]) -> HashMap<KeySequence, V> {
+    bindings.into_iter().map(|(key, action)| (key.into(), action)).collect()
+}
+
 pub trait ToDescription {
     fn to_description(&self) -> Cow<'static, str>;
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
     use crossterm::event::{KeyCode, KeyModifiers};
 
-    use super::{Key, KeyConfig, KeyConfigFile};
+    use super::{Key, KeyConfig, KeyConfigFile, bindings};
     #[cfg(debug_assertions)]
     use crate::config::keys::LogsActions;
     #[cfg(debug_assertions)]
     use crate::config::keys::LogsActionsFile;
     use crate::config::keys::{
+        AlbumsActions,
+        ArtistsActions,
+        ChainStep,
         CommonAction,
+        DirectoriesActions,
         GlobalAction,
+        PlaylistsActions,
         QueueActions,
-        actions::{CommonActionFile, GlobalActionFile, QueueActionsFile},
+        SearchActions,
+        actions::{
+            AlbumsActionsFile,
+            ArtistsActionsFile,
+            CommonActionFile,
+            DirectoriesActionsFile,
+            GlobalActionFile,
+            PlaylistsActionsFile,
+            QueueActionsFile,
+            SearchActionsFile,
+        },
     };
 
     #[test]
     #[rustfmt::skip]
     fn converts() {
         let input = KeyConfigFile {
-            global: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, GlobalActionFile::Quit)]),
+            global: bindings([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, GlobalActionFile::Quit)]),
 
             #[cfg(debug_assertions)]
-            logs: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, LogsActionsFile::Clear)]),
-            queue: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, QueueActionsFile::Play),
+            logs: bindings([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, LogsActionsFile::Clear)]),
+            queue: bindings([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, QueueActionsFile::Play),
                                   (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT, }, QueueActionsFile::Save)]),
-            // albums: HashMap::from([]),
-            // artists: HashMap::from([]),
-            // directories: HashMap::from([]),
-            // playlists: HashMap::from([]),
-            navigation: HashMap::from([
+            albums: bindings([(Key { key: KeyCode::Char('o'), modifiers: KeyModifiers::NONE, }, AlbumsActionsFile::ToggleReverseSort)]),
+            artists: bindings([(Key { key: KeyCode::Char('S'), modifiers: KeyModifiers::SHIFT, }, ArtistsActionsFile::CycleAlbumSortMode)]),
+            directories: bindings([(Key { key: KeyCode::Char('T'), modifiers: KeyModifiers::SHIFT, }, DirectoriesActionsFile::ToggleGroupByType)]),
+            playlists: bindings([(Key { key: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, }, PlaylistsActionsFile::Duplicate)]),
+            search: bindings([(Key { key: KeyCode::Char('r'), modifiers: KeyModifiers::CONTROL, }, SearchActionsFile::ResetQuery)]),
+            navigation: bindings([
                 (Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, CommonActionFile::Up),
                 (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT }, CommonActionFile::Up)
             ])
         };
         let expected = KeyConfig {
-            global: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, GlobalAction::Quit)]),
+            global: bindings([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, GlobalAction::Quit)]),
             #[cfg(debug_assertions)]
-            logs: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, LogsActions::Clear)]),
-            queue: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, QueueActions::Play),
+            logs: bindings([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, LogsActions::Clear)]),
+            queue: bindings([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, QueueActions::Play),
                                   (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT, }, QueueActions::Save)]),
-            albums: HashMap::from([]),
-            artists: HashMap::from([]),
-            directories: HashMap::from([]),
-            search: HashMap::from([]),
-            navigation: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL }, CommonAction::Up),
+            albums: bindings([(Key { key: KeyCode::Char('o'), modifiers: KeyModifiers::NONE, }, AlbumsActions::ToggleReverseSort)]),
+            artists: bindings([(Key { key: KeyCode::Char('S'), modifiers: KeyModifiers::SHIFT, }, ArtistsActions::CycleAlbumSortMode)]),
+            directories: bindings([(Key { key: KeyCode::Char('T'), modifiers: KeyModifiers::SHIFT, }, DirectoriesActions::ToggleGroupByType)]),
+            playlists: bindings([(Key { key: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, }, PlaylistsActions::Duplicate)]),
+            search: bindings([(Key { key: KeyCode::Char('r'), modifiers: KeyModifiers::CONTROL, }, SearchActions::ResetQuery)]),
+            navigation: bindings([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL }, CommonAction::Up),
                                        (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT }, CommonAction::Up)]),
         };
 
@@ -245,4 +350,36 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn chain_resolves_actions_by_name() {
+        let input = r#"Chain([AddAllReplace, SwitchToTab("Queue"), JumpToCurrent, Delete])"#;
+
+        let result: GlobalAction = ron::from_str::<GlobalActionFile>(input).unwrap().into();
+
+        let GlobalAction::Chain(steps) = result else {
+            panic!("Expected a chain, got {result:?}");
+        };
+        assert_eq!(steps.as_slice(), [
+            ChainStep { navigation: Some(CommonAction::AddAllReplace), ..Default::default() },
+            ChainStep {
+                global: Some(GlobalAction::SwitchToTab("Queue".into())),
+                ..Default::default()
+            },
+            ChainStep { queue: Some(QueueActions::JumpToCurrent), ..Default::default() },
+            ChainStep {
+                navigation: Some(CommonAction::Delete),
+                queue: Some(QueueActions::Delete),
+                ..Default::default()
+            },
+        ]);
+    }
+
+    #[test]
+    fn chain_rejects_unknown_action() {
+        let result = ron::from_str::<GlobalActionFile>("Chain([Quit, NotAnAction])");
+
+        assert!(result.is_err_and(|err| err.to_string().contains("unknown action `NotAnAction`")));
+    }
 }
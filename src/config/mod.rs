@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     io::Read,
     path::{Path, PathBuf},
     str::FromStr,
@@ -14,25 +15,31 @@ use cava::{Cava, CavaFile};
 use clap::Parser;
 use cli::{Args, OnOff, OnOffOneshot};
 use itertools::Itertools;
+use notifications::{Notifications, NotificationsFile};
 use search::SearchFile;
 use serde::{Deserialize, Serialize};
 use sort_mode::{SortMode, SortModeFile, SortOptions};
 use tabs::{PaneType, Tabs, TabsFile, validate_tabs};
 use theme::properties::{SongProperty, SongPropertyFile};
+use theme_mode::{ThemeMode, ThemeVariant, ThemeVariants};
 use utils::tilde_expand;
 
 pub mod address;
 pub mod album_art;
 pub mod artists;
 pub mod cava;
+pub mod check;
 pub mod cli;
 pub mod cli_config;
 mod defaults;
+mod include;
 pub mod keys;
+pub mod notifications;
 mod search;
 pub mod sort_mode;
 pub mod tabs;
 pub mod theme;
+pub mod theme_mode;
 
 pub use address::MpdAddress;
 pub use search::Search;
@@ -42,7 +49,13 @@ use self::{
     theme::{ConfigColor, UiConfig, UiConfigFile},
 };
 use crate::{
-    shared::{image, image::ImageProtocol, macros::status_warn},
+    shared::{
+        command_line::split_args,
+        image,
+        image::ImageProtocol,
+        macros::status_warn,
+        scripting,
+    },
     tmux,
 };
 
@@ -60,6 +73,8 @@ pub struct Config {
     pub keybinds: KeyConfig,
     pub enable_mouse: bool,
     pub enable_config_hot_reload: bool,
+    pub enable_mpris: bool,
+    pub notifications: Notifications,
     pub status_update_interval_ms: Option<u64>,
     pub select_current_song_on_change: bool,
     pub center_current_song_on_change: bool,
@@ -68,11 +83,22 @@ pub struct Config {
     pub mpd_read_timeout: Duration,
     pub mpd_write_timeout: Duration,
     pub mpd_idle_read_timeout_ms: Option<Duration>,
+    pub key_sequence_timeout: Duration,
     pub theme: UiConfig,
     pub theme_name: Option<String>,
+    pub theme_variants: ThemeVariants,
     pub album_art: AlbumArtConfig,
     pub on_song_change: Option<Arc<Vec<String>>>,
     pub on_resize: Option<Arc<Vec<String>>>,
+    pub on_state_change: Option<Arc<Vec<String>>>,
+    pub on_volume_change: Option<Arc<Vec<String>>>,
+    pub on_queue_change: Option<Arc<Vec<String>>>,
+    pub on_options_change: Option<Arc<Vec<String>>>,
+    pub on_db_update_finished: Option<Arc<Vec<String>>>,
+    pub on_connect: Option<Arc<Vec<String>>>,
+    pub on_disconnect: Option<Arc<Vec<String>>>,
+    pub on_startup: Option<Arc<Vec<String>>>,
+    pub on_exit: Option<Arc<Vec<String>>>,
     pub search: Search,
     pub artists: Artists,
     pub tabs: Tabs,
@@ -81,6 +107,7 @@ pub struct Config {
     pub show_playlists_in_browser: ShowPlaylistsMode,
     pub directories_sort: Arc<SortOptions>,
     pub cava: Cava,
+    pub scripts: HashMap<String, Arc<rhai::AST>>,
 }
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -104,6 +131,10 @@ pub struct ConfigFile {
     lyrics_dir: Option<String>,
     #[serde(default)]
     pub theme: Option<String>,
+    #[serde(default)]
+    pub theme_light: Option<String>,
+    #[serde(default)]
+    pub theme_dark: Option<String>,
     #[serde(default = "defaults::u8::<5>")]
     volume_step: u8,
     #[serde(default = "defaults::u32::<30>")]
@@ -133,7 +164,13 @@ pub struct ConfigFile {
     #[serde(default = "defaults::bool::<true>")]
     pub enable_config_hot_reload: bool,
     #[serde(default)]
+    enable_mpris: bool,
+    #[serde(default)]
+    pub notifications: NotificationsFile,
+    #[serde(default)]
     keybinds: KeyConfigFile,
+    #[serde(default = "defaults::u64::<1000>")]
+    key_sequence_timeout_ms: u64,
     #[serde(default)]
     image_method: Option<ImageMethodFile>,
     #[serde(default)]
@@ -145,6 +182,24 @@ pub struct ConfigFile {
     #[serde(default)]
     on_resize: Option<Vec<String>>,
     #[serde(default)]
+    on_state_change: Option<Vec<String>>,
+    #[serde(default)]
+    on_volume_change: Option<Vec<String>>,
+    #[serde(default)]
+    on_queue_change: Option<Vec<String>>,
+    #[serde(default)]
+    on_options_change: Option<Vec<String>>,
+    #[serde(default)]
+    on_db_update_finished: Option<Vec<String>>,
+    #[serde(default)]
+    on_connect: Option<Vec<String>>,
+    #[serde(default)]
+    on_disconnect: Option<Vec<String>>,
+    #[serde(default)]
+    on_startup: Option<Vec<String>>,
+    #[serde(default)]
+    on_exit: Option<Vec<String>>,
+    #[serde(default)]
     search: SearchFile,
     #[serde(default)]
     artists: ArtistsFile,
@@ -158,6 +213,8 @@ pub struct ConfigFile {
     pub directories_sort: SortModeFile,
     #[serde(default)]
     pub cava: CavaFile,
+    #[serde(default)]
+    pub scripts: HashMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
@@ -189,8 +246,11 @@ impl Default for ConfigFile {
             mpd_write_timeout_ms: 5_000,
             mpd_read_timeout_ms: 10_000,
             mpd_idle_read_timeout_ms: None,
+            key_sequence_timeout_ms: 1000,
             max_fps: 30,
             theme: None,
+            theme_light: None,
+            theme_dark: None,
             cache_dir: None,
             lyrics_dir: None,
             image_method: None,
@@ -203,10 +263,21 @@ impl Default for ConfigFile {
             },
             on_song_change: None,
             on_resize: None,
+            on_state_change: None,
+            on_volume_change: None,
+            on_queue_change: None,
+            on_options_change: None,
+            on_db_update_finished: None,
+            on_connect: None,
+            on_disconnect: None,
+            on_startup: None,
+            on_exit: None,
             search: SearchFile::default(),
             tabs: TabsFile::default(),
             enable_mouse: true,
             enable_config_hot_reload: true,
+            enable_mpris: false,
+            notifications: NotificationsFile::default(),
             wrap_navigation: false,
             password: None,
             artists: ArtistsFile::default(),
@@ -215,6 +286,7 @@ impl Default for ConfigFile {
             rewind_to_start_sec: None,
             reflect_changes_to_playlist: false,
             cava: CavaFile::default(),
+            scripts: HashMap::new(),
             show_playlists_in_browser: ShowPlaylistsMode::default(),
         }
     }
@@ -222,7 +294,25 @@ impl Default for ConfigFile {
 
 impl Config {
     pub fn validate(&self) -> Result<()> {
-        validate_tabs(&self.theme.layout, &self.tabs)
+        validate_tabs(&self.theme.layout, &self.tabs)?;
+        for variant in [&self.theme_variants.light, &self.theme_variants.dark].into_iter().flatten()
+        {
+            validate_tabs(&variant.theme.layout, &self.tabs)
+                .with_context(|| format!("Invalid theme '{}'", variant.name))?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the theme with the one configured for the mode, returns
+    /// whether there was one
+    pub fn apply_theme_mode(&mut self, mode: ThemeMode) -> bool {
+        let Some(variant) = self.theme_variants.get(mode) else {
+            return false;
+        };
+
+        self.theme = variant.theme.as_ref().clone();
+        self.theme_name = Some(variant.name.clone());
+        true
     }
 }
 
@@ -286,38 +376,72 @@ impl From<anyhow::Error> for DeserError {
 }
 
 impl ConfigFile {
-    pub fn read(path: &PathBuf) -> Result<Self, DeserError> {
+    pub fn read(path: &Path) -> Result<Self, DeserError> {
+        Ok(Self::read_with_includes(path)?.0)
+    }
+
+    /// Reads the config file together with all of the files it includes and
+    /// applies overrides for the current host. Returns the config and paths
+    /// of all of the files it was assembled from.
+    pub fn read_with_includes(path: &Path) -> Result<(Self, Vec<PathBuf>), DeserError> {
+        let hostname = include::hostname();
+        let resolved = include::resolve(path, hostname.as_deref())?;
+        let result: Result<ConfigFile, _> = serde_path_to_error::deserialize(
+            &mut ron::de::Deserializer::from_str(&resolved.content)?,
+        );
+
+        Ok((result?, resolved.files))
+    }
+
+    /// Returns the merged content of the config file and all of its includes.
+    pub fn read_content(path: &Path) -> Result<String, DeserError> {
+        let hostname = include::hostname();
+        Ok(include::resolve(path, hostname.as_deref())?.content)
+    }
+
+    pub fn theme_path(&self, config_dir: &Path) -> Option<PathBuf> {
+        self.theme.as_deref().map(|theme_name| Self::named_theme_path(config_dir, theme_name))
+    }
+
+    fn named_theme_path(config_dir: &Path, theme_name: &str) -> PathBuf {
+        PathBuf::from(config_dir).join("themes").join(format!("{theme_name}.ron"))
+    }
+
+    fn read_theme(&self, config_dir: &Path) -> Result<UiConfigFile, DeserError> {
+        self.theme_path(config_dir)
+            .map_or_else(|| Ok(UiConfigFile::default()), |path| Self::read_theme_file(&path))
+    }
+
+    fn read_theme_file(path: &Path) -> Result<UiConfigFile, DeserError> {
         let file = std::fs::File::open(path)?;
         let mut read = std::io::BufReader::new(file);
         let mut buf = Vec::new();
         read.read_to_end(&mut buf)?;
-        let result: Result<ConfigFile, _> =
-            serde_path_to_error::deserialize(&mut ron::de::Deserializer::from_bytes(&buf)?);
+        let theme: UiConfigFile =
+            serde_path_to_error::deserialize(&mut ron::de::Deserializer::from_bytes(&buf)?)?;
 
-        Ok(result?)
+        Ok(theme)
     }
 
-    pub fn theme_path(&self, config_dir: &Path) -> Option<PathBuf> {
-        self.theme.as_ref().map(|theme_name| {
-            PathBuf::from(config_dir).join("themes").join(format!("{theme_name}.ron"))
-        })
-    }
+    fn read_theme_variant(
+        config_dir: Option<&Path>,
+        name: Option<String>,
+    ) -> Result<Option<ThemeVariant>, DeserError> {
+        let (Some(config_dir), Some(name)) = (config_dir, name) else {
+            return Ok(None);
+        };
 
-    fn read_theme(&self, config_dir: &Path) -> Result<UiConfigFile, DeserError> {
-        self.theme_path(config_dir).map_or_else(
-            || Ok(UiConfigFile::default()),
-            |path| {
-                let file = std::fs::File::open(&path)?;
-                let mut read = std::io::BufReader::new(file);
-                let mut buf = Vec::new();
-                read.read_to_end(&mut buf)?;
-                let theme: UiConfigFile = serde_path_to_error::deserialize(
-                    &mut ron::de::Deserializer::from_bytes(&buf)?,
-                )?;
-
-                Ok(theme)
-            },
-        )
+        let path = Self::named_theme_path(config_dir, &name);
+        let theme = Self::read_theme_file(&path)
+            .and_then(|theme| Ok(UiConfig::try_from(theme)?))
+            .map_err(|err| {
+            DeserError::Generic(anyhow::anyhow!(
+                "Failed to load theme '{name}' from '{}': {err}",
+                path.display()
+            ))
+        })?;
+
+        Ok(Some(ThemeVariant { name, theme: Arc::new(theme) }))
     }
 
     pub fn into_config(
@@ -357,9 +481,30 @@ impl ConfigFile {
 
         let (address, password) =
             MpdAddress::resolve(address_cli, password_cli, self.address, self.password);
+        let config_dir = config_path.and_then(Path::parent);
+        let theme_variants = ThemeVariants {
+            light: Self::read_theme_variant(config_dir, self.theme_light)?,
+            dark: Self::read_theme_variant(config_dir, self.theme_dark)?,
+        };
+        let scripts = self
+            .scripts
+            .into_iter()
+            .map(|(name, path)| -> Result<_> {
+                let path = tilde_expand(&path);
+                // Relative paths are resolved against the config's directory, joining an
+                // absolute path keeps it as is
+                let path = config_dir.map_or_else(|| PathBuf::from(&*path), |dir| dir.join(&*path));
+                let ast = scripting::compile(&path).with_context(|| {
+                    format!("Failed to load script '{name}' from '{}'", path.display())
+                })?;
+                Ok((name, Arc::new(ast)))
+            })
+            .try_collect()?;
+
         let album_art_method = self.album_art.method;
         let mut config = Config {
             theme_name: self.theme,
+            theme_variants,
             cache_dir: self.cache_dir,
             lyrics_dir: self.lyrics_dir.map(|v| {
                 let v = tilde_expand(&v);
@@ -377,20 +522,28 @@ impl ConfigFile {
             mpd_read_timeout: Duration::from_millis(self.mpd_read_timeout_ms),
             mpd_write_timeout: Duration::from_millis(self.mpd_write_timeout_ms),
             mpd_idle_read_timeout_ms: self.mpd_idle_read_timeout_ms.map(Duration::from_millis),
+            key_sequence_timeout: Duration::from_millis(self.key_sequence_timeout_ms),
             enable_mouse: self.enable_mouse,
             enable_config_hot_reload: self.enable_config_hot_reload,
+            enable_mpris: self.enable_mpris,
+            notifications: self.notifications.try_into()?,
             keybinds: self.keybinds.into(),
             select_current_song_on_change: self.select_current_song_on_change,
             center_current_song_on_change: self.center_current_song_on_change,
             search: self.search.into(),
             artists: self.artists.into(),
             album_art: self.album_art.into(),
-            on_song_change: self.on_song_change.map(|arr| {
-                Arc::new(arr.into_iter().map(|v| tilde_expand(&v).into_owned()).collect_vec())
-            }),
-            on_resize: self.on_resize.map(|arr| {
-                Arc::new(arr.into_iter().map(|v| tilde_expand(&v).into_owned()).collect_vec())
-            }),
+            on_song_change: self.on_song_change.map(hook_command),
+            on_resize: self.on_resize.map(hook_command),
+            on_state_change: self.on_state_change.map(hook_command),
+            on_volume_change: self.on_volume_change.map(hook_command),
+            on_queue_change: self.on_queue_change.map(hook_command),
+            on_options_change: self.on_options_change.map(hook_command),
+            on_db_update_finished: self.on_db_update_finished.map(hook_command),
+            on_connect: self.on_connect.map(hook_command),
+            on_disconnect: self.on_disconnect.map(hook_command),
+            on_startup: self.on_startup.map(hook_command),
+            on_exit: self.on_exit.map(hook_command),
             browser_song_sort: Arc::new(SortOptions {
                 mode: SortMode::Format(
                     self.browser_song_sort.iter().cloned().map(SongProperty::from).collect_vec(),
@@ -427,6 +580,7 @@ impl ConfigFile {
             rewind_to_start_sec: self.rewind_to_start_sec,
             reflect_changes_to_playlist: self.reflect_changes_to_playlist,
             cava: self.cava.into(),
+            scripts,
         };
 
         if skip_album_art_check {
@@ -484,11 +638,18 @@ impl ConfigFile {
     }
 }
 
+/// Expands tilde in every part of a hook's command
+fn hook_command(command: Vec<String>) -> Arc<Vec<String>> {
+    Arc::new(command.into_iter().map(|v| tilde_expand(&v).into_owned()).collect_vec())
+}
+
 impl FromStr for Args {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Args::try_parse_from(std::iter::once("").chain(s.split_whitespace()))?)
+        Ok(Args::try_parse_from(
+            std::iter::once(String::new()).chain(split_args(s).into_iter().map(|arg| arg.value)),
+        )?)
     }
 }
 
@@ -579,12 +740,19 @@ pub mod utils {
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
+    use std::sync::Arc;
 
     use walkdir::WalkDir;
 
     #[cfg(debug_assertions)]
     use crate::config::keys::KeyConfigFile;
-    use crate::config::{ConfigFile, theme::UiConfigFile};
+    use crate::config::{
+        Config,
+        ConfigFile,
+        album_art::{ImageMethod, ImageMethodFile},
+        theme::{UiConfig, UiConfigFile},
+        theme_mode::{ThemeMode, ThemeVariant, ThemeVariants},
+    };
 
     #[test]
     #[cfg(debug_assertions)]
@@ -645,4 +813,36 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn daemon_config_does_not_query_terminal() {
+        // The image method is left unresolved, resolving auto would query the terminal
+        for method in [ImageMethodFile::Auto, ImageMethodFile::Kitty] {
+            let file = ConfigFile { image_method: Some(method), ..Default::default() };
+
+            let config = file.into_config(None, None, None, None, true).unwrap();
+
+            assert_eq!(config.album_art.method, ImageMethod::Unsupported);
+        }
+    }
+
+    #[test]
+    fn theme_mode_replaces_theme_with_its_variant() {
+        let theme = UiConfig { format_tag_separator: "light".to_owned(), ..Default::default() };
+        let mut config = Config {
+            theme_name: Some("base".to_owned()),
+            theme_variants: ThemeVariants {
+                light: Some(ThemeVariant { name: "light".to_owned(), theme: Arc::new(theme) }),
+                dark: None,
+            },
+            ..Default::default()
+        };
+
+        assert!(!config.apply_theme_mode(ThemeMode::Dark));
+        assert_eq!(config.theme_name.as_deref(), Some("base"));
+
+        assert!(config.apply_theme_mode(ThemeMode::Light));
+        assert_eq!(config.theme_name.as_deref(), Some("light"));
+        assert_eq!(config.theme.format_tag_separator, "light");
+    }
 }
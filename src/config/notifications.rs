@@ -0,0 +1,127 @@
+use anyhow::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::theme::properties::{
+    Property,
+    PropertyFile,
+    PropertyKind,
+    PropertyKindFile,
+    PropertyKindFileOrText,
+    SongPropertyFile,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NotificationsFile {
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default = "NotificationFile::default_song_change")]
+    song_change: Option<NotificationFile>,
+    #[serde(default)]
+    state_change: Option<NotificationFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NotificationFile {
+    title: Vec<PropertyFile<PropertyKindFile>>,
+    #[serde(default)]
+    body: Vec<PropertyFile<PropertyKindFile>>,
+    #[serde(default)]
+    urgency: Urgency,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Notifications {
+    pub enabled: bool,
+    pub song_change: Option<Notification>,
+    pub state_change: Option<Notification>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: Vec<Property<PropertyKind>>,
+    pub body: Vec<Property<PropertyKind>>,
+    pub urgency: Urgency,
+}
+
+/// Urgency levels defined by the desktop notifications specification
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    pub fn to_hint(self) -> u8 {
+        match self {
+            Urgency::Low => 0,
+            Urgency::Normal => 1,
+            Urgency::Critical => 2,
+        }
+    }
+}
+
+impl Default for NotificationsFile {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            song_change: NotificationFile::default_song_change(),
+            state_change: None,
+        }
+    }
+}
+
+impl NotificationFile {
+    #[allow(clippy::unnecessary_wraps)]
+    fn default_song_change() -> Option<Self> {
+        let property = |property: SongPropertyFile, default: &str| PropertyFile {
+            kind: PropertyKindFileOrText::Property(PropertyKindFile::Song(property)),
+            style: None,
+            default: Some(Box::new(PropertyFile {
+                kind: PropertyKindFileOrText::Text(default.to_owned()),
+                style: None,
+                default: None,
+            })),
+        };
+
+        Some(Self {
+            title: vec![property(SongPropertyFile::Title, "No Title")],
+            body: vec![
+                property(SongPropertyFile::Artist, "Unknown Artist"),
+                PropertyFile {
+                    kind: PropertyKindFileOrText::Text(" - ".to_owned()),
+                    style: None,
+                    default: None,
+                },
+                property(SongPropertyFile::Album, "Unknown Album"),
+            ],
+            urgency: Urgency::Low,
+        })
+    }
+}
+
+impl TryFrom<NotificationFile> for Notification {
+    type Error = anyhow::Error;
+
+    fn try_from(value: NotificationFile) -> Result<Self> {
+        Ok(Self {
+            title: value.title.into_iter().map(Property::try_from).try_collect()?,
+            body: value.body.into_iter().map(Property::try_from).try_collect()?,
+            urgency: value.urgency,
+        })
+    }
+}
+
+impl TryFrom<NotificationsFile> for Notifications {
+    type Error = anyhow::Error;
+
+    fn try_from(value: NotificationsFile) -> Result<Self> {
+        Ok(Self {
+            enabled: value.enabled,
+            song_change: value.song_change.map(Notification::try_from).transpose()?,
+            state_change: value.state_change.map(Notification::try_from).transpose()?,
+        })
+    }
+}
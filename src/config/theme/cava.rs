@@ -27,6 +27,43 @@ pub struct CavaThemeFile {
     pub bar_spacing: u16,
     #[serde(default = "defaults::u16::<1>")]
     pub bar_width: u16,
+    #[serde(default)]
+    pub mode: CavaModeFile,
+}
+
+/// How the visualiser is drawn. Every mode except `Braille` uses `bar_width`
+/// and `bar_spacing` from the parent config.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CavaModeFile {
+    /// Bars growing from the bottom of the pane up.
+    #[default]
+    Bars,
+    /// Bars growing from the middle of the pane both up and down.
+    Mirrored {
+        /// Color of the bottom half, `bar_color` is used if not provided
+        #[serde(default)]
+        reflection_color: Option<CavaColorFile>,
+    },
+    /// Bars growing from the left to the right, one bar per `bar_width` rows.
+    Horizontal {
+        #[serde(default = "defaults::default_horizontal_bar_symbols")]
+        bar_symbols: Vec<char>,
+        /// Grow the bars from the right edge instead
+        #[serde(default)]
+        reverse: bool,
+    },
+    /// A single line drawn with braille dots, two points per column.
+    Braille {
+        /// Fill the area below the line
+        #[serde(default)]
+        fill: bool,
+    },
+    /// Left channel grows up from the middle, right channel grows down.
+    Stereo {
+        /// Color of the right channel, `bar_color` is used if not provided
+        #[serde(default)]
+        right_color: Option<CavaColorFile>,
+    },
 }
 
 impl Default for CavaThemeFile {
@@ -37,6 +74,7 @@ impl Default for CavaThemeFile {
             bar_color: CavaColorFile::Single("blue".into()),
             bar_spacing: 1,
             bar_width: 1,
+            mode: CavaModeFile::Bars,
         }
     }
 }
@@ -44,22 +82,54 @@ impl Default for CavaThemeFile {
 #[derive(Debug, Clone)]
 pub struct CavaTheme {
     pub bar_symbols: Vec<String>,
-    pub bar_symbols_count: usize,
     pub bg_color: CrosstermColor,
     pub bar_color: CavaColor,
     pub bar_spacing: u16,
     pub bar_width: u16,
+    pub mode: CavaMode,
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum CavaMode {
+    #[default]
+    Bars,
+    Mirrored {
+        reflection_color: Option<CavaColor>,
+    },
+    Horizontal {
+        bar_symbols: Vec<String>,
+        reverse: bool,
+    },
+    Braille {
+        fill: bool,
+    },
+    Stereo {
+        right_color: Option<CavaColor>,
+    },
+}
+
+impl CavaMode {
+    /// Number of audio channels the mode needs the data for.
+    pub fn channels(&self) -> usize {
+        match self {
+            CavaMode::Stereo { .. } => 2,
+            CavaMode::Bars
+            | CavaMode::Mirrored { .. }
+            | CavaMode::Horizontal { .. }
+            | CavaMode::Braille { .. } => 1,
+        }
+    }
 }
 
 impl Default for CavaTheme {
     fn default() -> Self {
         Self {
-            bar_symbols_count: 8,
             bar_symbols: "▁▂▃▄▅▆▇█".chars().map(|c| c.to_string()).collect(),
             bg_color: CrosstermColor::Black,
             bar_color: CavaColor::Single(CrosstermColor::Blue),
             bar_spacing: 1,
             bar_width: 1,
+            mode: CavaMode::Bars,
         }
     }
 }
@@ -98,7 +168,6 @@ fn lerp_u8(a: u8, b: u8, t: f64) -> u8 {
 impl CavaThemeFile {
     pub fn into_config(self, default_bg_color: Option<RatatuiColor>) -> Result<CavaTheme> {
         Ok(CavaTheme {
-            bar_symbols_count: self.bar_symbols.len(),
             bar_symbols: self
                 .bar_symbols
                 .into_iter()
@@ -114,107 +183,140 @@ impl CavaThemeFile {
                 .transpose()?
                 .or(default_bg_color)
                 .map_or(CrosstermColor::Reset, CrosstermColor::from),
-            bar_color: match self.bar_color {
-                CavaColorFile::Single(c) => CavaColor::Single(
-                    RatatuiColor::from(ConfigColor::try_from(c.as_bytes())?).into(),
-                ),
-                CavaColorFile::Rows(cs) => CavaColor::Rows(
-                    cs.into_iter()
-                        .map(|c| -> Result<CrosstermColor> {
-                            Ok(CrosstermColor::from(RatatuiColor::from(ConfigColor::try_from(
-                                c.as_bytes(),
-                            )?)))
-                        })
-                        .try_collect()?,
-                ),
-                CavaColorFile::Gradient(mut cs) => {
-                    let first_entry = cs
-                        .iter()
-                        .sorted_by_key(|x| x.0)
-                        .next()
-                        .context("at least 2 elements should be guaranteed by deserialization")?
-                        .1
-                        .clone();
-                    let last_entry = cs
-                        .iter()
-                        .sorted_by_key(|x| x.0)
-                        .next_back()
-                        .context("at least 2 elements should be guaranteed by deserialization")?
-                        .1
-                        .clone();
+            bar_color: self.bar_color.try_into()?,
+            mode: self.mode.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<CavaModeFile> for CavaMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: CavaModeFile) -> Result<Self> {
+        Ok(match value {
+            CavaModeFile::Bars => CavaMode::Bars,
+            CavaModeFile::Mirrored { reflection_color } => CavaMode::Mirrored {
+                reflection_color: reflection_color.map(CavaColor::try_from).transpose()?,
+            },
+            CavaModeFile::Horizontal { bar_symbols, reverse } => {
+                ensure!(!bar_symbols.is_empty(), "Horizontal bar_symbols must not be empty");
+                CavaMode::Horizontal {
+                    bar_symbols: bar_symbols.into_iter().map(|c| c.to_string()).collect(),
+                    reverse,
+                }
+            }
+            CavaModeFile::Braille { fill } => CavaMode::Braille { fill },
+            CavaModeFile::Stereo { right_color } => {
+                CavaMode::Stereo { right_color: right_color.map(CavaColor::try_from).transpose()? }
+            }
+        })
+    }
+}
 
-                    cs.entry(0).or_insert(first_entry);
-                    cs.entry(100).or_insert(last_entry);
-                    ensure!(
-                        !cs.keys().any(|k| *k > 100),
-                        "Gradient keys must be in the range 0-100, got: {:?}",
-                        cs.keys()
-                    );
+impl TryFrom<CavaColorFile> for CavaColor {
+    type Error = anyhow::Error;
 
-                    let cs: HashMap<u8, (u8, u8, u8)> = cs
-                        .into_iter()
-                        .map(|(k, v)| -> Result<_> {
-                            match ConfigColor::try_from(v.as_bytes())? {
-                                ConfigColor::Rgb(r, g, b) => Ok((k, (r, g, b))),
-                                ConfigColor::Hex(u) => {
-                                    let r = (u >> 16) as u8;
-                                    let g = (u >> 8) as u8;
-                                    let b = u as u8;
-                                    Ok((k, (r, g, b)))
-                                }
-                                result => Err(anyhow::anyhow!(
-                                    "Gradient colors must be hex or RGB colors, got {:?}",
-                                    result
-                                )),
+    fn try_from(value: CavaColorFile) -> Result<Self> {
+        Ok(match value {
+            CavaColorFile::Single(c) => {
+                CavaColor::Single(RatatuiColor::from(ConfigColor::try_from(c.as_bytes())?).into())
+            }
+            CavaColorFile::Rows(cs) => CavaColor::Rows(
+                cs.into_iter()
+                    .map(|c| -> Result<CrosstermColor> {
+                        Ok(CrosstermColor::from(RatatuiColor::from(ConfigColor::try_from(
+                            c.as_bytes(),
+                        )?)))
+                    })
+                    .try_collect()?,
+            ),
+            CavaColorFile::Gradient(mut cs) => {
+                let first_entry = cs
+                    .iter()
+                    .sorted_by_key(|x| x.0)
+                    .next()
+                    .context("at least 2 elements should be guaranteed by deserialization")?
+                    .1
+                    .clone();
+                let last_entry = cs
+                    .iter()
+                    .sorted_by_key(|x| x.0)
+                    .next_back()
+                    .context("at least 2 elements should be guaranteed by deserialization")?
+                    .1
+                    .clone();
+
+                cs.entry(0).or_insert(first_entry);
+                cs.entry(100).or_insert(last_entry);
+                ensure!(
+                    !cs.keys().any(|k| *k > 100),
+                    "Gradient keys must be in the range 0-100, got: {:?}",
+                    cs.keys()
+                );
+
+                let cs: HashMap<u8, (u8, u8, u8)> = cs
+                    .into_iter()
+                    .map(|(k, v)| -> Result<_> {
+                        match ConfigColor::try_from(v.as_bytes())? {
+                            ConfigColor::Rgb(r, g, b) => Ok((k, (r, g, b))),
+                            ConfigColor::Hex(u) => {
+                                let r = (u >> 16) as u8;
+                                let g = (u >> 8) as u8;
+                                let b = u as u8;
+                                Ok((k, (r, g, b)))
                             }
-                        })
-                        .try_collect()?;
+                            result => Err(anyhow::anyhow!(
+                                "Gradient colors must be hex or RGB colors, got {:?}",
+                                result
+                            )),
+                        }
+                    })
+                    .try_collect()?;
 
-                    let cs = cs
-                        .into_iter()
-                        .sorted_by_key(|x| x.0)
-                        .tuple_windows()
-                        .fold(HashMap::new(), |mut acc, ((a_key, a_val), (b_key, b_val))| {
-                            if b_key - a_key == 0 {
-                                // range only includes start and end, simply include them in the map
-                                acc.insert(a_key, CrosstermColor::Rgb {
-                                    r: a_val.0,
-                                    g: a_val.1,
-                                    b: a_val.2,
+                let cs = cs
+                    .into_iter()
+                    .sorted_by_key(|x| x.0)
+                    .tuple_windows()
+                    .fold(HashMap::new(), |mut acc, ((a_key, a_val), (b_key, b_val))| {
+                        if b_key - a_key == 0 {
+                            // range only includes start and end, simply include them in the map
+                            acc.insert(a_key, CrosstermColor::Rgb {
+                                r: a_val.0,
+                                g: a_val.1,
+                                b: a_val.2,
+                            });
+                            acc.insert(b_key, CrosstermColor::Rgb {
+                                r: b_val.0,
+                                g: b_val.1,
+                                b: b_val.2,
+                            });
+                        } else {
+                            // interpolate values between start and end
+                            let total = f64::from(b_key - a_key);
+                            for i in a_key..=b_key {
+                                let progress = f64::from(i - a_key) / total;
+                                acc.insert(i, CrosstermColor::Rgb {
+                                    r: lerp_u8(a_val.0, b_val.0, progress),
+                                    g: lerp_u8(a_val.1, b_val.1, progress),
+                                    b: lerp_u8(a_val.2, b_val.2, progress),
                                 });
-                                acc.insert(b_key, CrosstermColor::Rgb {
-                                    r: b_val.0,
-                                    g: b_val.1,
-                                    b: b_val.2,
-                                });
-                            } else {
-                                // interpolate values between start and end
-                                let total = f64::from(b_key - a_key);
-                                for i in a_key..=b_key {
-                                    let progress = f64::from(i - a_key) / total;
-                                    acc.insert(i, CrosstermColor::Rgb {
-                                        r: lerp_u8(a_val.0, b_val.0, progress),
-                                        g: lerp_u8(a_val.1, b_val.1, progress),
-                                        b: lerp_u8(a_val.2, b_val.2, progress),
-                                    });
-                                }
                             }
-                            acc
-                        })
-                        .iter()
-                        .sorted_by_key(|x| x.0)
-                        .map(|v| v.1)
-                        .copied()
-                        .collect_vec();
+                        }
+                        acc
+                    })
+                    .iter()
+                    .sorted_by_key(|x| x.0)
+                    .map(|v| v.1)
+                    .copied()
+                    .collect_vec();
 
-                    ensure!(
-                        cs.len() >= 100,
-                        "Something went wrong when precalculating gradient, expected at least 100 colors, got {}. Please report this issue along with your config.",
-                        cs.len()
-                    );
-                    CavaColor::Gradient(cs)
-                }
-            },
+                ensure!(
+                    cs.len() >= 100,
+                    "Something went wrong when precalculating gradient, expected at least 100 colors, got {}. Please report this issue along with your config.",
+                    cs.len()
+                );
+                CavaColor::Gradient(cs)
+            }
         })
     }
 }
@@ -266,3 +368,33 @@ where
     }
     Ok(v)
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{CavaColorFile, CavaModeFile, CavaThemeFile};
+
+    #[test]
+    fn parses_render_modes() {
+        let theme: CavaThemeFile = ron::de::from_str("(mode: Mirrored())").unwrap();
+        assert_eq!(theme.mode, CavaModeFile::Mirrored { reflection_color: None });
+
+        let theme: CavaThemeFile = ron::de::from_str(
+            r#"#![enable(implicit_some)]
+            (mode: Stereo(right_color: Single("red")))"#,
+        )
+        .unwrap();
+        assert_eq!(theme.mode, CavaModeFile::Stereo {
+            right_color: Some(CavaColorFile::Single("red".to_owned()))
+        });
+
+        let theme: CavaThemeFile = ron::de::from_str("(mode: Horizontal(reverse: true))").unwrap();
+        assert_eq!(theme.mode, CavaModeFile::Horizontal {
+            bar_symbols: "▏▎▍▌▋▊▉█".chars().collect(),
+            reverse: true
+        });
+
+        let theme: CavaThemeFile = ron::de::from_str("()").unwrap();
+        assert_eq!(theme.mode, CavaModeFile::Bars);
+    }
+}
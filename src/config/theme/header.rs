@@ -106,6 +106,17 @@ impl Default for HeaderConfigFile {
                         }),
                     }],
                     right: vec![
+                        PropertyFile {
+                            kind: PropertyKindFileOrText::Property(PropertyKindFile::Widget(
+                                WidgetPropertyFile::PendingKeys,
+                            )),
+                            style: Some(StyleFile {
+                                fg: Some("yellow".to_string()),
+                                bg: None,
+                                modifiers: Some(Modifiers::Bold),
+                            }),
+                            default: None,
+                        },
                         PropertyFile {
                             kind: PropertyKindFileOrText::Property(PropertyKindFile::Widget(
                                 WidgetPropertyFile::ScanStatus,
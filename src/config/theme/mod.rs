@@ -157,7 +157,7 @@ impl Default for UiConfigFile {
                 modifiers: None,
             }),
             tab_bar: TabBarFile {
-                enabled: Some(true),
+                enabled: None,
                 active_style: Some(StyleFile {
                     fg: Some("black".to_string()),
                     bg: Some("blue".to_string()),
@@ -185,6 +185,7 @@ pub enum PropertyKindFile {
     Song(SongPropertyFile),
     Status(StatusPropertyFile),
     Widget(WidgetPropertyFile),
+    Script(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -248,6 +249,7 @@ pub enum PropertyKind {
     Song(SongProperty),
     Status(StatusProperty),
     Widget(WidgetProperty),
+    Script(String),
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -260,6 +262,7 @@ pub struct Property<T> {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum WidgetPropertyFile {
     ScanStatus,
+    PendingKeys,
     States { active_style: Option<StyleFile>, separator_style: Option<StyleFile> },
     Volume,
 }
@@ -267,6 +270,7 @@ pub enum WidgetPropertyFile {
 #[derive(Debug, Display, Clone, Copy, Hash, Eq, PartialEq)]
 pub enum WidgetProperty {
     ScanStatus,
+    PendingKeys,
     States { active_style: Style, separator_style: Style },
     Volume,
 }
@@ -479,6 +483,10 @@ impl TryFrom<PropertyFile<PropertyKindFile>> for Property<PropertyKind> {
                         PropertyKindFile::Widget(WidgetPropertyFile::ScanStatus) => {
                             PropertyKind::Widget(WidgetProperty::ScanStatus)
                         }
+                        PropertyKindFile::Widget(WidgetPropertyFile::PendingKeys) => {
+                            PropertyKind::Widget(WidgetProperty::PendingKeys)
+                        }
+                        PropertyKindFile::Script(name) => PropertyKind::Script(name),
                     })
                 }
                 PropertyKindFileOrText::Group(group) => {
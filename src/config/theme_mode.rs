@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use super::theme::UiConfig;
+
+/// Whether the terminal has a light or a dark background
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeMode {
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThemeVariant {
+    pub name: String,
+    pub theme: Arc<UiConfig>,
+}
+
+/// Themes from `theme_light` and `theme_dark` which replace the theme when the
+/// mode changes
+#[derive(Debug, Default, Clone)]
+pub struct ThemeVariants {
+    pub light: Option<ThemeVariant>,
+    pub dark: Option<ThemeVariant>,
+}
+
+impl ThemeVariants {
+    pub fn is_empty(&self) -> bool {
+        self.light.is_none() && self.dark.is_none()
+    }
+
+    pub fn get(&self, mode: ThemeMode) -> Option<&ThemeVariant> {
+        match mode {
+            ThemeMode::Light => self.light.as_ref(),
+            ThemeMode::Dark => self.dark.as_ref(),
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::{cell::Cell, collections::HashSet, ops::AddAssign, time::Instant};
 
 use anyhow::{Context, Result, bail};
 use bon::bon;
-use crossbeam::channel::{SendError, Sender, bounded};
+use crossbeam::channel::{SendError, Sender};
 
 use crate::{
     AppEvent,
@@ -14,19 +14,22 @@ use crate::{
         Config,
         album_art::ImageMethod,
         tabs::{PaneType, TabName},
+        theme_mode::ThemeMode,
     },
     core::scheduler::{Scheduler, time_provider::DefaultTimeProvider},
     mpd::{
         client::Client,
-        commands::{Song, State, Status},
+        commands::{Song, Status},
         mpd_client::MpdClient,
     },
     shared::{
         events::ClientRequest,
+        key_event::{MacroRecorder, PendingKeys},
         lrc::{Lrc, LrcIndex, get_lrc_path},
         macros::status_warn,
-        mpd_query::MpdQuerySync,
+        mpd_query::query_sync,
         ring_vec::RingVec,
+        scripting::Scripts,
     },
     ui::StatusMessage,
 };
@@ -53,6 +56,12 @@ pub struct AppContext {
     #[debug(skip)]
     pub(crate) scheduler: Scheduler<(Sender<AppEvent>, Sender<ClientRequest>), DefaultTimeProvider>,
     pub(crate) messages: RingVec<10, StatusMessage>,
+    pub(crate) pending_keys: Option<PendingKeys>,
+    pub(crate) macros: MacroRecorder,
+    pub(crate) scripts: Scripts,
+    /// Mode of the terminal's background, if it could be detected
+    pub(crate) detected_theme_mode: Option<ThemeMode>,
+    pub(crate) forced_theme_mode: Option<ThemeMode>,
 }
 
 #[bon]
@@ -103,6 +112,11 @@ impl AppContext {
             should_fetch_stickers: sticker_support_needed,
             rendered_frames: 0,
             messages: RingVec::default(),
+            pending_keys: None,
+            macros: MacroRecorder::default(),
+            scripts: Scripts::default(),
+            detected_theme_mode: None,
+            forced_theme_mode: None,
         })
     }
 
@@ -123,29 +137,16 @@ impl AppContext {
         self.rendered_frames.add_assign(1);
     }
 
+    /// Mode whose theme variant should be used
+    pub(crate) fn theme_mode(&self) -> Option<ThemeMode> {
+        self.forced_theme_mode.or(self.detected_theme_mode)
+    }
+
     pub(crate) fn query_sync<T: Send + Sync + 'static>(
         &self,
         on_done: impl FnOnce(&mut Client<'_>) -> Result<T> + Send + 'static,
     ) -> Result<T> {
-        let (tx, rx) = bounded(1);
-        let query = MpdQuerySync {
-            callback: Box::new(|client| Ok(MpdQueryResult::Any(Box::new((on_done)(client)?)))),
-            tx,
-        };
-
-        if let Err(err) = self.client_request_sender.send(ClientRequest::QuerySync(query)) {
-            log::error!(error:? = err; "Failed to send query request");
-            bail!("Failed to send sync query request");
-        }
-
-        if let MpdQueryResult::Any(any) = rx.recv()? {
-            if let Ok(val) = any.downcast::<T>() {
-                return Ok(*val);
-            }
-            bail!("Received unknown type answer for sync query request",);
-        }
-
-        bail!("Received unknown MpdQueryResult for sync query request");
+        query_sync(&self.client_request_sender, on_done)
     }
 
     #[builder(finish_fn(name = query))]
@@ -177,13 +178,7 @@ impl AppContext {
     }
 
     pub(crate) fn find_current_song_in_queue(&self) -> Option<(usize, &Song)> {
-        if self.status.state == State::Stop {
-            return None;
-        }
-
-        self.status
-            .songid
-            .and_then(|id| self.queue.iter().enumerate().find(|(_, song)| song.id == id))
+        self.status.find_current_song_in(&self.queue)
     }
 
     pub(crate) fn find_lrc(&self) -> Result<Option<Lrc>> {
@@ -1,19 +1,40 @@
-use std::{io::Write, path::PathBuf, sync::Arc};
+use std::{collections::HashSet, io::Write, path::PathBuf, sync::Arc};
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use itertools::Itertools;
+use serde::Serialize;
 
 use crate::{
     config::{
-        cli::{AddRandom, Command, StickerCmd},
+        cli::{
+            AddRandom,
+            Command,
+            IdleSubsystem,
+            PlaylistCmd,
+            QueueCmd,
+            SearchArgs,
+            SearchKind,
+            StickerCmd,
+        },
         cli_config::CliConfig,
+        theme::{
+            TagResolutionStrategy,
+            properties::{
+                Property,
+                PropertyFile,
+                PropertyKind,
+                PropertyKindFile,
+                PropertyKindOrText,
+                StatusProperty,
+            },
+        },
     },
     context::AppContext,
     mpd::{
         QueuePosition,
         client::Client,
-        commands::{IdleEvent, State, mpd_config::MpdConfig, volume::Bound},
-        mpd_client::{Filter, MpdClient, Tag, ValueChange},
+        commands::{IdleEvent, Song, State, Status, mpd_config::MpdConfig, volume::Bound},
+        mpd_client::{Filter, FilterKind, MpdClient, SaveMode, Tag, ValueChange},
         version::Version,
     },
     shared::{
@@ -22,6 +43,7 @@ use crate::{
         macros::{status_error, status_info},
         ytdlp::YtDlp,
     },
+    ui::panes::PropertySource,
 };
 
 impl Command {
@@ -35,6 +57,7 @@ impl Command {
             Command::Version => bail!("Cannot use version command here."),
             Command::DebugInfo => bail!("Cannot use debuginfo command here."),
             Command::Remote { .. } => bail!("Cannot use remote command here."),
+            Command::Daemon => bail!("Cannot use daemon command here."),
             Command::AddRandom { tag, count } => Ok(Box::new(move |client| {
                 match tag {
                     AddRandom::Song => {
@@ -67,7 +90,7 @@ impl Command {
 
                     if wait {
                         loop {
-                            client.idle(Some(IdleEvent::Update))?;
+                            client.idle(&[IdleEvent::Update])?;
                             log::trace!("issuing update");
                             let crate::mpd::commands::Status { updating_db, .. } =
                                 client.get_status()?;
@@ -277,11 +300,38 @@ impl Command {
             Command::DisableOutput { id } => {
                 Ok(Box::new(move |client| Ok(client.disable_output(id)?)))
             }
-            Command::Status => Ok(Box::new(|client| {
-                println!("{}", serde_json::ser::to_string(&client.get_status()?)?);
-                Ok(())
-            })),
-            Command::Song { path: Some(paths) } if paths.len() == 1 => {
+            Command::Status { format, follow } => {
+                let template = format.as_deref().map(FormatTemplate::parse).transpose()?;
+                Ok(Box::new(move |client| {
+                    print_output(client, follow, |client| {
+                        let status = client.get_status()?;
+                        match &template {
+                            Some(template) => {
+                                let song = client.get_current_song()?;
+                                template.render(client, song, &status)
+                            }
+                            None => Ok(serde_json::ser::to_string(&status)?),
+                        }
+                    })
+                }))
+            }
+            Command::Song { path: Some(paths), format: Some(format), .. } => {
+                let template = FormatTemplate::parse(&format)?;
+                Ok(Box::new(move |client| {
+                    let status = client.get_status()?;
+                    for path in &paths {
+                        let Some(song) =
+                            client.find_one(&[Filter::new(Tag::File, path.as_str())])?
+                        else {
+                            println!("Song with path '{path}' not found.");
+                            std::process::exit(1);
+                        };
+                        println!("{}", template.render(client, Some(song), &status)?);
+                    }
+                    Ok(())
+                }))
+            }
+            Command::Song { path: Some(paths), .. } if paths.len() == 1 => {
                 Ok(Box::new(move |client| {
                     let path = &paths[0];
                     if let Some(song) = client.find_one(&[Filter::new(Tag::File, path.as_str())])? {
@@ -293,7 +343,7 @@ impl Command {
                     }
                 }))
             }
-            Command::Song { path: Some(paths) } => Ok(Box::new(move |client| {
+            Command::Song { path: Some(paths), .. } => Ok(Box::new(move |client| {
                 let mut songs = Vec::new();
                 for path in &paths {
                     if let Some(song) = client.find_one(&[Filter::new(Tag::File, path.as_str())])? {
@@ -306,15 +356,24 @@ impl Command {
                 println!("{}", serde_json::ser::to_string(&songs)?);
                 Ok(())
             })),
-            Command::Song { path: None } => Ok(Box::new(|client| {
-                let current_song = client.get_current_song()?;
-                if let Some(song) = current_song {
-                    println!("{}", serde_json::ser::to_string(&song)?);
-                    Ok(())
-                } else {
-                    std::process::exit(1);
-                }
-            })),
+            Command::Song { path: None, format, follow } => {
+                let template = format.as_deref().map(FormatTemplate::parse).transpose()?;
+                Ok(Box::new(move |client| {
+                    print_output(client, follow, |client| {
+                        let current_song = client.get_current_song()?;
+                        match (&template, current_song) {
+                            (Some(template), song) => {
+                                let status = client.get_status()?;
+                                template.render(client, song, &status)
+                            }
+                            (None, Some(song)) => Ok(serde_json::ser::to_string(&song)?),
+                            // Keep following until a song starts playing
+                            (None, None) if follow => Ok("null".to_owned()),
+                            (None, None) => std::process::exit(1),
+                        }
+                    })
+                }))
+            }
             Command::Mount { name, path } => {
                 Ok(Box::new(move |client| Ok(client.mount(&name, &path)?)))
             }
@@ -393,6 +452,113 @@ impl Command {
                     Ok(())
                 }))
             }
+            Command::Idle { subsystems, follow } => Ok(Box::new(move |client| {
+                let subsystems = subsystems.into_iter().map(IdleEvent::from).collect_vec();
+                loop {
+                    let events = client.idle(&subsystems)?;
+                    let status = client.get_status()?;
+                    let song = client.get_current_song()?;
+
+                    let mut stdout = std::io::stdout().lock();
+                    for event in events {
+                        let output = IdleOutput { event, status: &status, song: song.as_ref() };
+                        writeln!(stdout, "{}", serde_json::ser::to_string(&output)?)?;
+                    }
+                    stdout.flush()?;
+
+                    if !follow {
+                        return Ok(());
+                    }
+                }
+            })),
+            Command::Search { args } => search(*args, false),
+            Command::Find { args } => search(*args, true),
+            Command::Queue { cmd: QueueCmd::List { json } } => Ok(Box::new(move |client| {
+                let queue = client.playlist_info(false)?.unwrap_or_default();
+                print_songs(&queue, json)
+            })),
+            Command::Queue { cmd: QueueCmd::Move { from, to } } => {
+                Ok(Box::new(move |client| Ok(client.move_in_queue(from, to)?)))
+            }
+            Command::Queue { cmd: QueueCmd::Delete { range } } => {
+                Ok(Box::new(move |client| Ok(client.delete_from_queue(range)?)))
+            }
+            Command::Queue { cmd: QueueCmd::Swap { first, second } } => {
+                Ok(Box::new(move |client| {
+                    // MPD's swap takes only absolute positions
+                    let current = client.get_status()?.song.map(|song| song as usize);
+                    let resolve = |position: QueuePosition| {
+                        position.resolve(current).with_context(|| {
+                            format!("Position '{}' is not in the queue", position.as_mpd_str())
+                        })
+                    };
+                    Ok(client.swap(resolve(first)?, resolve(second)?)?)
+                }))
+            }
+            Command::Queue { cmd: QueueCmd::Shuffle { range } } => {
+                Ok(Box::new(move |client| Ok(client.shuffle(range)?)))
+            }
+            Command::Queue { cmd: QueueCmd::Dedupe } => Ok(Box::new(|client| {
+                let queue = client.playlist_info(false)?.unwrap_or_default();
+                for id in duplicate_ids(&queue) {
+                    client.delete_id(id)?;
+                }
+                Ok(())
+            })),
+            Command::Playlist { cmd: PlaylistCmd::List { json } } => Ok(Box::new(move |client| {
+                let playlists = client.list_playlists()?;
+                if json {
+                    println!("{}", serde_json::ser::to_string(&playlists)?);
+                } else {
+                    let mut stdout = std::io::stdout().lock();
+                    for playlist in playlists {
+                        writeln!(stdout, "{}", playlist.name)?;
+                    }
+                }
+                Ok(())
+            })),
+            Command::Playlist { cmd: PlaylistCmd::Show { playlist, json } } => {
+                Ok(Box::new(move |client| {
+                    let songs = client.list_playlist_info(&playlist, None)?;
+                    print_songs(&songs, json)
+                }))
+            }
+            Command::Playlist { cmd: PlaylistCmd::Load { playlist, position } } => {
+                Ok(Box::new(move |client| Ok(client.load_playlist(&playlist, position)?)))
+            }
+            Command::Playlist { cmd: PlaylistCmd::Save { playlist, replace } } => {
+                Ok(Box::new(move |client| {
+                    let mode = replace.then_some(SaveMode::Replace);
+                    Ok(client.save_queue_as_playlist(&playlist, mode)?)
+                }))
+            }
+            Command::Playlist { cmd: PlaylistCmd::Append { playlist } } => {
+                Ok(Box::new(move |client| {
+                    Ok(client.save_queue_as_playlist(&playlist, Some(SaveMode::Append))?)
+                }))
+            }
+            Command::Playlist { cmd: PlaylistCmd::Rename { playlist, new_name } } => {
+                Ok(Box::new(move |client| Ok(client.rename_playlist(&playlist, &new_name)?)))
+            }
+            Command::Playlist { cmd: PlaylistCmd::Delete { playlist } } => {
+                Ok(Box::new(move |client| Ok(client.delete_playlist(&playlist)?)))
+            }
+            Command::Playlist { cmd: PlaylistCmd::Add { playlist, mut files, position } } => {
+                Ok(Box::new(move |client| {
+                    // Every song is inserted at the same position so they have to be added in
+                    // reverse to keep their order
+                    if position.is_some() {
+                        files.reverse();
+                    }
+                    for file in files {
+                        client.add_to_playlist(&playlist, &file, position)?;
+                    }
+                    Ok(())
+                }))
+            }
+            Command::Playlist { cmd: PlaylistCmd::Remove { playlist, range } } => {
+                Ok(Box::new(move |client| Ok(client.delete_from_playlist(&playlist, &range)?)))
+            }
         }
     }
 }
@@ -495,3 +661,344 @@ pub fn create_env<'a>(
 
     result
 }
+
+/// Line printed by `idle` for every changed subsystem
+#[derive(Serialize)]
+struct IdleOutput<'a> {
+    event: IdleEvent,
+    status: &'a Status,
+    song: Option<&'a Song>,
+}
+
+impl From<IdleSubsystem> for IdleEvent {
+    fn from(value: IdleSubsystem) -> Self {
+        match value {
+            IdleSubsystem::Player => IdleEvent::Player,
+            IdleSubsystem::Mixer => IdleEvent::Mixer,
+            IdleSubsystem::Playlist => IdleEvent::Playlist,
+            IdleSubsystem::Options => IdleEvent::Options,
+            IdleSubsystem::Database => IdleEvent::Database,
+            IdleSubsystem::Update => IdleEvent::Update,
+            IdleSubsystem::StoredPlaylist => IdleEvent::StoredPlaylist,
+            IdleSubsystem::Output => IdleEvent::Output,
+            IdleSubsystem::Partition => IdleEvent::Partition,
+            IdleSubsystem::Sticker => IdleEvent::Sticker,
+            IdleSubsystem::Subscription => IdleEvent::Subscription,
+            IdleSubsystem::Message => IdleEvent::Message,
+            IdleSubsystem::Neighbor => IdleEvent::Neighbor,
+            IdleSubsystem::Mount => IdleEvent::Mount,
+        }
+    }
+}
+
+/// Runs `search` or `find`. Find is case sensitive and matches tag values
+/// exactly by default.
+fn search(
+    args: SearchArgs,
+    case_sensitive: bool,
+) -> Result<Box<dyn FnOnce(&mut Client<'_>) -> Result<()> + Send + 'static>> {
+    let template = args.format.as_deref().map(FormatTemplate::parse).transpose()?;
+    let kind = match args.kind {
+        Some(kind) => kind.into(),
+        None if case_sensitive => FilterKind::Exact,
+        None => FilterKind::Contains,
+    };
+    let filters = search_filters(&args, kind);
+    let SearchArgs { expression, add, position, .. } = args;
+    if expression.is_none() && filters.is_empty() {
+        bail!("At least one tag filter or a filter expression is required");
+    }
+
+    Ok(Box::new(move |client| {
+        if add {
+            match (&expression, case_sensitive) {
+                (Some(expression), true) => client.find_add_expression(expression, position)?,
+                (Some(expression), false) => client.search_add_expression(expression, position)?,
+                (None, true) => client.find_add(&filters, position)?,
+                (None, false) => client.search_add(&filters, position)?,
+            }
+            return Ok(());
+        }
+
+        let songs = match (&expression, case_sensitive) {
+            (Some(expression), true) => client.find_expression(expression)?,
+            (Some(expression), false) => client.search_expression(expression)?,
+            (None, true) => client.find(&filters)?,
+            (None, false) => client.search(&filters)?,
+        };
+
+        let Some(template) = template else {
+            println!("{}", serde_json::ser::to_string(&songs)?);
+            return Ok(());
+        };
+        let status = client.get_status()?;
+        let mut stdout = std::io::stdout().lock();
+        for song in songs {
+            writeln!(stdout, "{}", template.render(client, Some(song), &status)?)?;
+        }
+        Ok(())
+    }))
+}
+
+/// Filters given by the tag arguments of `search` and `find`
+fn search_filters(args: &SearchArgs, kind: FilterKind) -> Vec<Filter<'static>> {
+    [
+        (Tag::Artist, &args.artist),
+        (Tag::Album, &args.album),
+        (Tag::AlbumArtist, &args.albumartist),
+        (Tag::Genre, &args.genre),
+        (Tag::Title, &args.title),
+        (Tag::Custom("Composer".to_owned()), &args.composer),
+        (Tag::Custom("Performer".to_owned()), &args.performer),
+        (Tag::Custom("Date".to_owned()), &args.date),
+        (Tag::File, &args.file),
+        (Tag::Any, &args.any),
+    ]
+    .into_iter()
+    .filter_map(|(tag, value)| value.clone().map(|value| (tag, value)))
+    .chain(args.tags.iter().map(|(tag, value)| (Tag::Custom(tag.clone()), value.clone())))
+    .map(|(tag, value)| Filter::new_with_kind(tag, value, kind))
+    .collect()
+}
+
+impl From<SearchKind> for FilterKind {
+    fn from(value: SearchKind) -> Self {
+        match value {
+            SearchKind::Exact => FilterKind::Exact,
+            SearchKind::StartsWith => FilterKind::StartsWith,
+            SearchKind::Contains => FilterKind::Contains,
+            SearchKind::Regex => FilterKind::Regex,
+        }
+    }
+}
+
+/// Prints the songs either as a JSON array or one per line with their position,
+/// artist, title and file separated by tabs
+fn print_songs(songs: &[Song], json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::ser::to_string(songs)?);
+        return Ok(());
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    for (idx, song) in songs.iter().enumerate() {
+        writeln!(
+            stdout,
+            "{idx}\t{} - {}\t{}",
+            song.artist_str(FORMAT_TAG_SEPARATOR),
+            song.title_str(FORMAT_TAG_SEPARATOR),
+            song.file
+        )?;
+    }
+    Ok(())
+}
+
+/// Ids of songs whose file is already in the queue at a lower position
+fn duplicate_ids(queue: &[Song]) -> Vec<u32> {
+    let mut seen = HashSet::new();
+    queue.iter().filter(|song| !seen.insert(song.file.as_str())).map(|song| song.id).collect()
+}
+
+/// Tag separator of the default theme, the CLI does not read the theme
+const FORMAT_TAG_SEPARATOR: &str = " | ";
+
+/// Template given to `--format`. Uses the same properties as the header in the
+/// theme.
+struct FormatTemplate {
+    properties: Vec<Property<PropertyKind>>,
+    needs_queue: bool,
+    needs_stickers: bool,
+}
+
+impl FormatTemplate {
+    fn parse(input: &str) -> Result<Self> {
+        // Same as in the config files which usually enable implicit_some
+        let properties: Vec<PropertyFile<PropertyKindFile>> = ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_str(input)
+            .with_context(|| format!("Invalid format '{input}'"))?;
+        let properties: Vec<Property<PropertyKind>> =
+            properties.into_iter().map(Property::try_from).try_collect()?;
+
+        let needs_queue = any_property(&properties, &|kind| {
+            matches!(
+                kind,
+                PropertyKindOrText::Property(PropertyKind::Status(
+                    StatusProperty::QueueLength { .. }
+                        | StatusProperty::QueueTimeTotal { .. }
+                        | StatusProperty::QueueTimeRemaining { .. }
+                ))
+            )
+        });
+        let needs_stickers =
+            any_property(&properties, &|kind| matches!(kind, PropertyKindOrText::Sticker(_)));
+
+        Ok(Self { properties, needs_queue, needs_stickers })
+    }
+
+    fn render(
+        &self,
+        client: &mut Client<'_>,
+        mut song: Option<Song>,
+        status: &Status,
+    ) -> Result<String> {
+        let queue = if self.needs_queue {
+            client.playlist_info(false)?.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        if let Some(song) = song.as_mut().filter(|_| self.needs_stickers) {
+            song.stickers = Some(client.list_stickers(&song.file)?.0);
+        }
+
+        let source = PropertySource::new(status, &queue);
+        Ok(self
+            .properties
+            .iter()
+            .filter_map(|property| {
+                property.as_string(
+                    song.as_ref(),
+                    &source,
+                    FORMAT_TAG_SEPARATOR,
+                    TagResolutionStrategy::default(),
+                )
+            })
+            .collect())
+    }
+}
+
+/// Whether any of the properties, including the ones in groups and defaults,
+/// matches the predicate
+fn any_property(
+    properties: &[Property<PropertyKind>],
+    pred: &dyn Fn(&PropertyKindOrText<PropertyKind>) -> bool,
+) -> bool {
+    properties.iter().any(|property| {
+        pred(&property.kind)
+            || matches!(&property.kind, PropertyKindOrText::Group(group) if any_property(group, pred))
+            || property.default.as_deref().is_some_and(|default| {
+                any_property(std::slice::from_ref(default), pred)
+            })
+    })
+}
+
+/// Prints the rendered output. When following, waits for any change in MPD and
+/// prints the output again if it differs from the last one.
+fn print_output(
+    client: &mut Client<'_>,
+    follow: bool,
+    mut render: impl FnMut(&mut Client<'_>) -> Result<String>,
+) -> Result<()> {
+    let mut last_output = None;
+    loop {
+        let output = render(client)?;
+        if last_output.as_ref() != Some(&output) {
+            let mut stdout = std::io::stdout().lock();
+            writeln!(stdout, "{output}")?;
+            stdout.flush()?;
+            last_output = Some(output);
+        }
+
+        if !follow {
+            return Ok(());
+        }
+        client.idle(&[])?;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use clap::Parser;
+
+    use super::{FormatTemplate, IdleOutput, duplicate_ids, search_filters};
+    use crate::{
+        config::cli::{Args, Command},
+        mpd::{
+            commands::{IdleEvent, Song, Status},
+            mpd_client::{Filter, FilterKind},
+        },
+    };
+
+    #[test]
+    fn format_template_detects_required_data() {
+        let template = FormatTemplate::parse(
+            r#"[
+                (kind: Property(Status(StateV2(playing_label: "Playing")))),
+                (kind: Text(" ")),
+                (
+                    kind: Group([(kind: Property(Song(Artist))), (kind: Text(" - ")), (kind: Property(Song(Title)))]),
+                    default: (kind: Property(Status(QueueLength()))),
+                ),
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(template.properties.len(), 3);
+        assert!(template.needs_queue);
+        assert!(!template.needs_stickers);
+    }
+
+    #[test]
+    fn format_template_rejects_invalid_format() {
+        assert!(FormatTemplate::parse("[Property(Song(Title))]").is_err());
+    }
+
+    #[test]
+    fn search_filters_are_built_from_tag_arguments() {
+        let Args { command: Some(Command::Find { args }), .. } = Args::parse_from([
+            "rmpc",
+            "find",
+            "--title",
+            "Song",
+            "--artist",
+            "Foo",
+            "--tag",
+            "label=Bar=Baz",
+        ]) else {
+            panic!("Expected find command");
+        };
+
+        let filters = search_filters(&args, FilterKind::StartsWith);
+
+        assert_eq!(filters.iter().map(Filter::to_query_str).collect::<Vec<_>>(), [
+            "Artist =~ '^Foo'",
+            "Title =~ '^Song'",
+            "label =~ '^Bar=Baz'"
+        ]);
+    }
+
+    #[test]
+    fn search_expression_conflicts_with_tag_arguments() {
+        assert!(Args::try_parse_from(["rmpc", "search", "(artist == 'Foo')"]).is_ok());
+        assert!(
+            Args::try_parse_from(["rmpc", "search", "(artist == 'Foo')", "--artist", "Foo"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn idle_output_contains_event_status_and_song() {
+        let Args { command: Some(Command::Idle { subsystems, follow: false }), .. } =
+            Args::parse_from(["rmpc", "idle", "stored_playlist"])
+        else {
+            panic!("Expected idle command");
+        };
+        let status = Status::default();
+
+        let output =
+            IdleOutput { event: IdleEvent::from(subsystems[0]), status: &status, song: None };
+        let output = serde_json::to_string(&output).unwrap();
+
+        assert!(output.starts_with(r#"{"event":"stored_playlist","status":{"#));
+        assert!(output.ends_with(r#","song":null}"#));
+    }
+
+    #[test]
+    fn duplicate_ids_keep_first_occurrence() {
+        let queue = [(1, "a.mp3"), (2, "b.mp3"), (3, "a.mp3"), (4, "c.mp3"), (5, "b.mp3")]
+            .map(|(id, file)| Song { id, file: file.to_owned(), ..Default::default() });
+
+        assert_eq!(duplicate_ids(&queue), [3, 5]);
+    }
+}
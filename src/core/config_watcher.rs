@@ -1,7 +1,12 @@
-use std::{path::PathBuf, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+    time::Duration,
+};
 
 use anyhow::{Context, Result, bail};
 use crossbeam::channel::Sender;
+use itertools::Itertools;
 use notify_debouncer_full::{
     DebounceEventResult,
     Debouncer,
@@ -14,15 +19,23 @@ use notify_debouncer_full::{
         event::{AccessKind, AccessMode},
     },
 };
+use parking_lot::Mutex;
 
 use crate::{AppEvent, config::ConfigFile, shared::macros::try_skip};
 
+type ConfigDebouncer = Debouncer<RecommendedWatcher, RecommendedCache>;
+
+/// Drop guard for the config watcher. The watcher stops once this is dropped.
+pub(crate) struct ConfigWatcherGuard {
+    _debouncer: Arc<Mutex<Option<ConfigDebouncer>>>,
+}
+
 #[must_use = "Returns a drop guard for the config directory watcher"]
 pub(crate) fn init(
     config_path: PathBuf,
     theme_name: Option<String>,
     event_tx: Sender<AppEvent>,
-) -> Result<Debouncer<RecommendedWatcher, RecommendedCache>> {
+) -> Result<ConfigWatcherGuard> {
     if !config_path.exists() {
         bail!("Config path {} does not exist", config_path.display());
     }
@@ -31,11 +44,21 @@ pub(crate) fn init(
         .file_name()
         .with_context(|| format!("Invalid config path {}", config_path.display()))?
         .to_owned();
-    let config_directory = config_path
-        .parent()
-        .with_context(|| format!("Invalid config directory {}", config_path.display()))?
-        .to_owned();
+    let config_directory = std::path::absolute(
+        config_path
+            .parent()
+            .with_context(|| format!("Invalid config directory {}", config_path.display()))?,
+    )?;
 
+    // Included files can live outside of the config directory so their
+    // directories have to be watched as well
+    let mut config_files =
+        ConfigFile::read_with_includes(&config_path).map(|(_, files)| files).unwrap_or_default();
+    let initial_files = config_files.clone();
+    let closure_config_directory = config_directory.clone();
+
+    let debouncer: Arc<Mutex<Option<ConfigDebouncer>>> = Arc::new(Mutex::new(None));
+    let weak_debouncer = Arc::downgrade(&debouncer);
     let mut theme_name = theme_name;
     let mut watcher = new_debouncer(
         Duration::from_millis(500),
@@ -52,6 +75,7 @@ pub(crate) fn init(
             for event in events {
                 if !event.paths.iter().any(|path| {
                     path.ends_with(&config_file_name)
+                        || config_files.iter().any(|file| path == file)
                         || theme_name.as_ref().is_some_and(|theme| path.ends_with(theme))
                 }) {
                     continue;
@@ -62,20 +86,25 @@ pub(crate) fn init(
 
                 log::debug!(event:?; "File event");
 
-                let Ok(config) = ConfigFile::read(&config_path).inspect_err(|err| {
-                    try_skip!(
-                        event_tx.send(AppEvent::InfoModal {
-                            message: vec![err.to_string()],
-                            id: Some("config_error_modal".into()),
-                            title: None,
-                            size: None,
-                        }),
-                        "Failed to send info modal request"
-                    );
-                }) else {
+                let Ok((config, files)) =
+                    ConfigFile::read_with_includes(&config_path).inspect_err(|err| {
+                        try_skip!(
+                            event_tx.send(AppEvent::InfoModal {
+                                message: vec![err.to_string()],
+                                id: Some("config_error_modal".into()),
+                                title: None,
+                                size: None,
+                            }),
+                            "Failed to send info modal request"
+                        );
+                    })
+                else {
                     continue;
                 };
 
+                watch_directories(&weak_debouncer, &closure_config_directory, &files);
+                config_files = files;
+
                 let Ok(config) = config
                     .into_config(Some(&config_path), None, None, None, true)
                     .inspect_err(|err| {
@@ -112,6 +141,37 @@ pub(crate) fn init(
 
     watcher.watch(&config_directory, RecursiveMode::Recursive)?;
     log::info!(config_directory:? = config_directory.to_str(); "Watching for changes");
+    *debouncer.lock() = Some(watcher);
+    watch_directories(&Arc::downgrade(&debouncer), &config_directory, &initial_files);
+
+    Ok(ConfigWatcherGuard { _debouncer: debouncer })
+}
 
-    Ok(watcher)
+/// Watches directories of the included config files which are not already
+/// covered by the recursive watch of the config directory.
+fn watch_directories(
+    debouncer: &Weak<Mutex<Option<ConfigDebouncer>>>,
+    config_directory: &Path,
+    files: &[PathBuf],
+) {
+    let Some(debouncer) = debouncer.upgrade() else {
+        return;
+    };
+    let mut debouncer = debouncer.lock();
+    let Some(debouncer) = debouncer.as_mut() else {
+        return;
+    };
+
+    for dir in files
+        .iter()
+        .filter_map(|file| file.parent())
+        .filter(|dir| !dir.starts_with(config_directory))
+        .unique()
+    {
+        if let Err(err) = debouncer.watch(dir, RecursiveMode::NonRecursive) {
+            log::warn!(err:?, dir:?; "Failed to watch directory of an included config file");
+        } else {
+            log::debug!(dir:?; "Watching directory of an included config file");
+        }
+    }
 }
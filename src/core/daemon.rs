@@ -0,0 +1,266 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::{Result, bail};
+use crossbeam::channel::Receiver;
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+};
+
+use super::{
+    command::{create_env, run_external},
+    event_loop::{handle_idle_event, reflect_changes_to_playlist},
+    hooks::{self, Hook},
+    mpris,
+    notifications::{self, NotificationEvent},
+};
+use crate::{
+    config::keys::{ChainStep, GlobalAction},
+    context::AppContext,
+    mpd::commands::{IdleEvent, State},
+    shared::{
+        events::{AppEvent, WorkDone},
+        ipc::{GetIpcCommand, RemoteEvent, Subscribers},
+        macros::{status_error, status_warn, try_skip},
+        mpd_query::{
+            EXTERNAL_COMMAND,
+            GLOBAL_QUEUE_UPDATE,
+            GLOBAL_STATUS_UPDATE,
+            GLOBAL_VOLUME_UPDATE,
+            MpdQueryResult,
+        },
+    },
+};
+
+/// Main loop of `rmpc daemon`. Follows MPD's changes, runs hooks and serves
+/// remote commands the same way as the TUI's event loop, but skips everything
+/// which needs the UI.
+pub fn init(
+    context: AppContext,
+    event_rx: Receiver<AppEvent>,
+) -> std::io::Result<std::thread::JoinHandle<()>> {
+    // Shut down through the main loop so the exit hook runs and the socket
+    // gets cleaned up
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+    let tx = context.app_event_sender.clone();
+    std::thread::Builder::new().name("signals".to_owned()).spawn(move || {
+        if signals.forever().next().is_some() {
+            try_skip!(tx.send(AppEvent::Terminate), "Failed to send terminate event");
+        }
+    })?;
+
+    std::thread::Builder::new().name("main".to_owned()).spawn(move || main_task(context, event_rx))
+}
+
+fn main_task(mut context: AppContext, event_rx: Receiver<AppEvent>) {
+    let mut subscribers = Subscribers::default();
+    // Daemon has no UI to notify, these are only required by the shared idle
+    // event handling
+    let mut ui_events = HashSet::new();
+    let mut connected = true;
+
+    let mpris = mpris::init(&context);
+    let notifier = notifications::init(&context);
+    hooks::run(&context, Hook::Startup, Vec::new());
+
+    for event in event_rx {
+        match event {
+            AppEvent::IdleEvent(event) => {
+                handle_idle_event(event, &context, &mut ui_events);
+                ui_events.clear();
+            }
+            AppEvent::WorkDone(Ok(WorkDone::LyricsIndexed { index })) => {
+                context.lrc_index = index;
+            }
+            AppEvent::WorkDone(Ok(WorkDone::SingleLrcIndexed { lrc_entry })) => {
+                if let Some(lrc_entry) = lrc_entry {
+                    context.lrc_index.add(lrc_entry);
+                }
+            }
+            AppEvent::WorkDone(Ok(WorkDone::MpdCommandFinished { id, target: None, data })) => {
+                match (id, data) {
+                    (
+                        GLOBAL_STATUS_UPDATE,
+                        MpdQueryResult::Status { data: status, source_event },
+                    ) => {
+                        let previous_song_id =
+                            context.find_current_song_in_queue().map(|(_, song)| song.id);
+                        let previous_playlist = context.status.lastloadedplaylist.take();
+                        let previous_status = std::mem::replace(&mut context.status, status);
+
+                        reflect_changes_to_playlist(&context, previous_playlist, source_event);
+                        hooks::on_status_change(&context, &previous_status);
+                        if let Some(notifier) = notifier
+                            .as_ref()
+                            .filter(|_| previous_status.state != context.status.state)
+                        {
+                            notifier.notify(&context, NotificationEvent::StateChange);
+                        }
+                        if let Some(mpris) = &mpris {
+                            mpris.update(&context);
+                        }
+
+                        let song = context.find_current_song_in_queue().map(|(_, song)| song);
+                        if song.map(|song| song.id) != previous_song_id {
+                            if song.is_some() {
+                                hooks::run(&context, Hook::SongChange, Vec::new());
+                                if let Some(notifier) = &notifier {
+                                    notifier.notify(&context, NotificationEvent::SongChange);
+                                }
+                            }
+                            subscribers.emit(&RemoteEvent::SongChange { song: song.cloned() });
+                        }
+                    }
+                    (GLOBAL_VOLUME_UPDATE, MpdQueryResult::Volume(volume)) => {
+                        let previous = std::mem::replace(&mut context.status.volume, volume);
+                        hooks::on_volume_change(&context, previous);
+                        if let Some(mpris) = &mpris {
+                            mpris.update(&context);
+                        }
+                    }
+                    (GLOBAL_QUEUE_UPDATE, MpdQueryResult::Queue(queue)) => {
+                        let previous =
+                            std::mem::replace(&mut context.queue, queue.unwrap_or_default());
+                        hooks::on_queue_change(&context, &previous);
+                        if let Some(mpris) = &mpris {
+                            mpris.update(&context);
+                        }
+                    }
+                    (EXTERNAL_COMMAND, MpdQueryResult::ExternalCommand(command, songs)) => {
+                        let songs = songs.iter().map(|s| s.file.as_str());
+                        run_external(command, create_env(&context, songs));
+                    }
+                    (id, _) => {
+                        log::debug!(id; "Ignoring result of a command meant for the UI");
+                    }
+                }
+            }
+            AppEvent::WorkDone(Ok(_)) => {}
+            AppEvent::WorkDone(Err(err)) => {
+                status_error!("{}", err);
+            }
+            AppEvent::Status(message, level, _) => {
+                subscribers.emit(&RemoteEvent::Status { message, level });
+            }
+            AppEvent::InfoModal { message, .. } => {
+                log::error!(message:?; "{}", message.join("\n"));
+            }
+            AppEvent::ConfigChanged { config: mut new_config, keep_old_theme } => {
+                if keep_old_theme {
+                    new_config.theme = context.config.theme.clone();
+                }
+
+                if let Err(err) = new_config.validate() {
+                    status_error!(error:? = err; "Cannot change config, invalid value: '{err}'");
+                    continue;
+                }
+
+                context.config = Arc::new(*new_config);
+                subscribers.emit(&RemoteEvent::ConfigReload);
+            }
+            AppEvent::ThemeChanged { theme } => {
+                let mut config = context.config.as_ref().clone();
+                config.theme = *theme;
+                if let Err(err) = config.validate() {
+                    status_error!(error:? = err; "Cannot change theme, invalid config: '{err}'");
+                    continue;
+                }
+                context.config = Arc::new(config);
+            }
+            AppEvent::RemoteQuery { query, tx } => {
+                if tx.send(remote_query(query, &context)).is_err() {
+                    log::error!(query:?; "Failed to answer remote query");
+                }
+            }
+            AppEvent::RemoteSubscribe(subscriber) => {
+                subscribers.add(subscriber);
+            }
+            AppEvent::RunAction(step) => {
+                run_action(&step, &context);
+            }
+            AppEvent::UserKeyInput(_) => {
+                status_warn!("Keys cannot be pressed in daemon mode");
+            }
+            AppEvent::Reconnected => {
+                for ev in [IdleEvent::Player, IdleEvent::Playlist, IdleEvent::Options] {
+                    handle_idle_event(ev, &context, &mut ui_events);
+                }
+                ui_events.clear();
+                status_warn!("rmpc reconnected to MPD and will reinitialize");
+                hooks::run(&context, Hook::Connect, Vec::new());
+                connected = true;
+            }
+            AppEvent::LostConnection => {
+                context.status.state = State::Stop;
+                if connected {
+                    status_error!("rmpc lost connection to MPD and will try to reconnect");
+                    hooks::run(&context, Hook::Disconnect, Vec::new());
+                }
+                connected = false;
+            }
+            AppEvent::Terminate => {
+                hooks::run_blocking(&context, Hook::Exit, Vec::new());
+                break;
+            }
+            AppEvent::KeySequenceTimeout
+            | AppEvent::UserMouseInput(_)
+            | AppEvent::Log(_)
+            | AppEvent::RequestRender
+            | AppEvent::Resized { .. }
+            | AppEvent::ResizedDebounced { .. }
+            | AppEvent::UiEvent(_)
+            | AppEvent::TmuxHook { .. }
+            | AppEvent::TerminalBackgroundDetected(_)
+            | AppEvent::ThemeModeForced(_)
+            | AppEvent::LyricsLineChanged { .. } => {}
+        }
+    }
+}
+
+/// Only external commands and scripts can run without the UI, external
+/// commands get the same environment as when run from the TUI without any
+/// selected songs
+fn run_action(step: &ChainStep, context: &AppContext) {
+    match &step.global {
+        Some(GlobalAction::ExternalCommand { command, .. }) => {
+            run_external(command.clone(), create_env(context, std::iter::empty::<&str>()));
+        }
+        Some(GlobalAction::Script { name, .. }) => {
+            if let Err(err) = context.scripts.run_action(name, context) {
+                status_error!("{err:#}");
+            }
+        }
+        _ => status_warn!("Action '{step}' is not available in daemon mode"),
+    }
+}
+
+fn remote_query(query: GetIpcCommand, context: &AppContext) -> Result<serde_json::Value> {
+    match query {
+        GetIpcCommand::Theme => Ok(serde_json::to_value(&context.config.theme_name)?),
+        GetIpcCommand::ConfigPath => bail!("Config path is answered by the socket listener"),
+        GetIpcCommand::Tab
+        | GetIpcCommand::Pane
+        | GetIpcCommand::Selected
+        | GetIpcCommand::Marked
+        | GetIpcCommand::Filter => bail!("{query:?} is not available in daemon mode"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use rstest::rstest;
+
+    use super::remote_query;
+    use crate::{context::AppContext, shared::ipc::GetIpcCommand, tests::fixtures::app_context};
+
+    #[rstest]
+    fn answers_only_queries_which_do_not_need_ui(app_context: AppContext) {
+        assert_eq!(
+            remote_query(GetIpcCommand::Theme, &app_context).unwrap(),
+            serde_json::Value::Null
+        );
+        assert!(remote_query(GetIpcCommand::Tab, &app_context).is_err());
+        assert!(remote_query(GetIpcCommand::Selected, &app_context).is_err());
+    }
+}
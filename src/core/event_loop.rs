@@ -12,8 +12,14 @@ use ratatui::{
     prelude::{Backend, CrosstermBackend},
 };
 
-use super::command::{create_env, run_external};
+use super::{
+    command::{create_env, run_external},
+    hooks::{self, Hook},
+    mpris,
+    notifications::{self, NotificationEvent},
+};
 use crate::{
+    config::theme_mode::ThemeMode,
     context::AppContext,
     mpd::{
         commands::{IdleEvent, State},
@@ -23,6 +29,7 @@ use crate::{
         events::{AppEvent, WorkDone},
         ext::error::ErrorExt,
         id::{self, Id},
+        ipc::{RemoteEvent, Subscribers},
         macros::{status_error, status_warn},
         mpd_query::{
             EXTERNAL_COMMAND,
@@ -67,6 +74,9 @@ fn main_task<B: Backend + std::io::Write>(
     ui.before_show(area, &mut context).expect("Initial render init to succeed");
     let mut _update_loop_guard = None;
     let mut _update_db_loop_guard = None;
+    let mut subscribers = Subscribers::default();
+    let mut last_tab = context.active_tab.clone();
+    let mut last_pane = ui.focused_pane(&context);
 
     // Tmux hooks have to be initialized after ui, because ueberzugpp replaces all
     // hooks on its init instead of simply appending and might break rmpc's hooks
@@ -79,6 +89,10 @@ fn main_task<B: Backend + std::io::Write>(
         }
     };
 
+    let mpris = mpris::init(&context);
+    let notifier = notifications::init(&context);
+    hooks::run(&context, Hook::Startup, Vec::new());
+
     // Check the playback status and start the periodic status update if needed
     if context.status.state == State::Play {
         _update_loop_guard = context
@@ -91,6 +105,19 @@ fn main_task<B: Backend + std::io::Write>(
     loop {
         let now = std::time::Instant::now();
 
+        // Checked at the start of the loop because most event handlers skip to the next
+        // iteration early
+        if context.active_tab != last_tab {
+            last_tab = context.active_tab.clone();
+            subscribers.emit(&RemoteEvent::TabChange { tab: last_tab.to_string() });
+        }
+        let pane = ui.focused_pane(&context);
+        if pane != last_pane {
+            subscribers
+                .emit(&RemoteEvent::PaneFocus { pane: pane.as_ref().map(ToString::to_string) });
+            last_pane = pane;
+        }
+
         let event = if render_wanted {
             match event_receiver.recv_timeout(
                 min_frame_duration.checked_sub(now - last_render).unwrap_or(Duration::ZERO),
@@ -112,6 +139,9 @@ fn main_task<B: Backend + std::io::Write>(
                     new_config.album_art.method = context.config.album_art.method;
                     if keep_old_theme {
                         new_config.theme = context.config.theme.clone();
+                        new_config.theme_variants = context.config.theme_variants.clone();
+                    } else if let Some(mode) = context.theme_mode() {
+                        new_config.apply_theme_mode(mode);
                     }
 
                     if let Err(err) = new_config.validate() {
@@ -127,6 +157,7 @@ fn main_task<B: Backend + std::io::Write>(
                         log::error!(error:? = err; "UI failed to handle config changed event");
                         continue;
                     }
+                    subscribers.emit(&RemoteEvent::ConfigReload);
 
                     // Need to clear the terminal to avoid artifacts from album art and other
                     // elements
@@ -158,19 +189,52 @@ fn main_task<B: Backend + std::io::Write>(
                     }
                     render_wanted = true;
                 }
-                AppEvent::UserKeyInput(key) => match ui.handle_key(&mut key.into(), &mut context) {
-                    Ok(KeyHandleResult::None) => continue,
-                    Ok(KeyHandleResult::Quit) => {
-                        if let Err(err) = ui.on_event(UiEvent::Exit, &mut context) {
-                            log::error!(error:? = err, event:?; "UI failed to handle quit event");
-                        }
-                        break;
+                AppEvent::TerminalBackgroundDetected(mode) => {
+                    let previous = context.theme_mode();
+                    context.detected_theme_mode = Some(mode);
+                    switch_theme_mode(&context, previous);
+                }
+                AppEvent::ThemeModeForced(mode) => {
+                    let previous = context.theme_mode();
+                    context.forced_theme_mode = mode;
+                    switch_theme_mode(&context, previous);
+                }
+                AppEvent::RemoteQuery { query, tx } => {
+                    if tx.send(ui.remote_query(query, &context)).is_err() {
+                        log::error!(query:?; "Failed to answer remote query");
                     }
-                    Err(err) => {
-                        status_error!(err:?; "Error: {}", err.to_status());
-                        render_wanted = true;
+                }
+                AppEvent::RemoteSubscribe(subscriber) => {
+                    subscribers.add(subscriber);
+                }
+                AppEvent::LyricsLineChanged { index, time, line } => {
+                    subscribers.emit(&RemoteEvent::lyrics_line(index, time, line));
+                }
+                // Signals are only listened to in daemon mode
+                AppEvent::Terminate => {}
+                AppEvent::UserKeyInput(_)
+                | AppEvent::KeySequenceTimeout
+                | AppEvent::RunAction(_) => {
+                    let result = match event {
+                        AppEvent::UserKeyInput(key) => ui.handle_key(&mut key.into(), &mut context),
+                        AppEvent::RunAction(ref step) => ui.run_action(step.clone(), &mut context),
+                        _ => ui.on_key_sequence_timeout(&mut context),
+                    };
+                    match result {
+                        Ok(KeyHandleResult::None) => continue,
+                        Ok(KeyHandleResult::Quit) => {
+                            if let Err(err) = ui.on_event(UiEvent::Exit, &mut context) {
+                                log::error!(error:? = err, event:?; "UI failed to handle quit event");
+                            }
+                            hooks::run_blocking(&context, Hook::Exit, Vec::new());
+                            break;
+                        }
+                        Err(err) => {
+                            status_error!(err:?; "Error: {}", err.to_status());
+                            render_wanted = true;
+                        }
                     }
-                },
+                }
                 AppEvent::UserMouseInput(ev) => match ui.handle_mouse_event(ev, &mut context) {
                     Ok(()) => {}
                     Err(err) => {
@@ -179,6 +243,9 @@ fn main_task<B: Backend + std::io::Write>(
                     }
                 },
                 AppEvent::Status(mut message, level, timeout) => {
+                    if !subscribers.is_empty() {
+                        subscribers.emit(&RemoteEvent::Status { message: message.clone(), level });
+                    }
                     context.messages.push(StatusMessage {
                         level,
                         timeout,
@@ -252,29 +319,19 @@ fn main_task<B: Backend + std::io::Write>(
                             let previous_state = context.status.state;
                             let current_updating_db = context.status.updating_db;
                             let current_playlist = context.status.lastloadedplaylist.take();
-                            context.status = status;
-                            let new_playlist = context.status.lastloadedplaylist.as_ref();
+                            let previous_status = std::mem::replace(&mut context.status, status);
                             let mut song_changed = false;
 
-                            if context.config.reflect_changes_to_playlist
-                                && matches!(source_event, Some(IdleEvent::Playlist))
+                            reflect_changes_to_playlist(&context, current_playlist, source_event);
+                            hooks::on_status_change(&context, &previous_status);
+                            if let Some(notifier) = notifier
+                                .as_ref()
+                                .filter(|_| previous_status.state != context.status.state)
                             {
-                                // Try to reflect changes to saved playlist if any was loaded both
-                                // before and after the update
-                                if let (Some(current_playlist), Some(new_playlist)) =
-                                    (current_playlist, new_playlist)
-                                {
-                                    if &current_playlist == new_playlist {
-                                        let playlist_name = current_playlist.clone();
-                                        context.command(move |client| {
-                                            client.save_queue_as_playlist(
-                                                &playlist_name,
-                                                Some(SaveMode::Replace),
-                                            )?;
-                                            Ok(())
-                                        });
-                                    }
-                                }
+                                notifier.notify(&context, NotificationEvent::StateChange);
+                            }
+                            if let Some(mpris) = &mpris {
+                                mpris.update(&context);
                             }
 
                             let mut start_render_loop = || {
@@ -339,15 +396,19 @@ fn main_task<B: Backend + std::io::Write>(
 
                             if let Some((_, song)) = context.find_current_song_in_queue() {
                                 if Some(song.id) != current_song_id {
-                                    if let Some(command) = &context.config.on_song_change {
-                                        let env = create_env(&context, std::iter::empty());
-
-                                        run_external(command.clone(), env);
+                                    hooks::run(&context, Hook::SongChange, Vec::new());
+                                    if let Some(notifier) = &notifier {
+                                        notifier.notify(&context, NotificationEvent::SongChange);
                                     }
                                     song_changed = true;
                                 }
                             }
                             if song_changed {
+                                subscribers.emit(&RemoteEvent::SongChange {
+                                    song: context
+                                        .find_current_song_in_queue()
+                                        .map(|(_, song)| song.clone()),
+                                });
                                 if let Err(err) = ui.on_event(UiEvent::SongChanged, &mut context) {
                                     status_error!(error:? = err; "UI failed to handle idle event, error: '{}'", err.to_status());
                                 }
@@ -355,11 +416,20 @@ fn main_task<B: Backend + std::io::Write>(
                             render_wanted = true;
                         }
                         ("global_volume_update", None, MpdQueryResult::Volume(volume)) => {
-                            context.status.volume = volume;
+                            let previous = std::mem::replace(&mut context.status.volume, volume);
+                            hooks::on_volume_change(&context, previous);
+                            if let Some(mpris) = &mpris {
+                                mpris.update(&context);
+                            }
                             render_wanted = true;
                         }
                         ("global_queue_update", None, MpdQueryResult::Queue(queue)) => {
-                            context.queue = queue.unwrap_or_default();
+                            let previous =
+                                std::mem::replace(&mut context.queue, queue.unwrap_or_default());
+                            hooks::on_queue_change(&context, &previous);
+                            if let Some(mpris) = &mpris {
+                                mpris.update(&context);
+                            }
                             render_wanted = true;
                         }
                         (
@@ -425,6 +495,7 @@ fn main_task<B: Backend + std::io::Write>(
                         log::error!(error:? = err, event:?; "UI failed to handle resize event");
                     }
                     status_warn!("rmpc reconnected to MPD and will reinitialize");
+                    hooks::run(&context, Hook::Connect, Vec::new());
                     connected = true;
                 }
                 AppEvent::LostConnection => {
@@ -434,6 +505,7 @@ fn main_task<B: Backend + std::io::Write>(
                     }
                     if connected {
                         status_error!("rmpc lost connection to MPD and will try to reconnect");
+                        hooks::run(&context, Hook::Disconnect, Vec::new());
                     }
                     connected = false;
                 }
@@ -485,7 +557,57 @@ fn main_task<B: Backend + std::io::Write>(
     terminal
 }
 
-fn handle_idle_event(event: IdleEvent, context: &AppContext, result_ui_evs: &mut HashSet<UiEvent>) {
+/// Swaps the theme for the one configured for the current mode through
+/// [`AppEvent::ThemeChanged`] when the mode changed
+fn switch_theme_mode(context: &AppContext, previous: Option<ThemeMode>) {
+    let Some(mode) = context.theme_mode().filter(|mode| previous != Some(*mode)) else {
+        return;
+    };
+    let Some(variant) = context.config.theme_variants.get(mode) else {
+        return;
+    };
+
+    log::debug!(mode:?, theme = variant.name.as_str(); "Switching theme for the theme mode");
+    if let Err(err) = context
+        .app_event_sender
+        .send(AppEvent::ThemeChanged { theme: Box::new(variant.theme.as_ref().clone()) })
+    {
+        log::error!(error:? = err; "Failed to send theme changed event");
+    }
+}
+
+/// Saves the queue to the stored playlist it was loaded from when the queue
+/// changed and the playlist is still loaded
+pub(super) fn reflect_changes_to_playlist(
+    context: &AppContext,
+    previous_playlist: Option<String>,
+    source_event: Option<IdleEvent>,
+) {
+    if !context.config.reflect_changes_to_playlist
+        || !matches!(source_event, Some(IdleEvent::Playlist))
+    {
+        return;
+    }
+
+    // Try to reflect changes to saved playlist if any was loaded both before and
+    // after the update
+    if let (Some(previous_playlist), Some(new_playlist)) =
+        (previous_playlist, context.status.lastloadedplaylist.as_ref())
+    {
+        if &previous_playlist == new_playlist {
+            context.command(move |client| {
+                client.save_queue_as_playlist(&previous_playlist, Some(SaveMode::Replace))?;
+                Ok(())
+            });
+        }
+    }
+}
+
+pub(super) fn handle_idle_event(
+    event: IdleEvent,
+    context: &AppContext,
+    result_ui_evs: &mut HashSet<UiEvent>,
+) {
     match event {
         IdleEvent::Mixer if context.supported_commands.contains("getvol") => {
             context
@@ -550,7 +672,14 @@ fn handle_idle_event(event: IdleEvent, context: &AppContext, result_ui_evs: &mut
                 })
             });
         }
-        IdleEvent::Update => {}
+        IdleEvent::Update => {
+            context.query().id(GLOBAL_STATUS_UPDATE).replace_id("status").query(move |client| {
+                Ok(MpdQueryResult::Status {
+                    data: client.get_status()?,
+                    source_event: Some(IdleEvent::Update),
+                })
+            });
+        }
         IdleEvent::Output
         | IdleEvent::Partition
         | IdleEvent::Subscription
@@ -565,3 +694,38 @@ fn handle_idle_event(event: IdleEvent, context: &AppContext, result_ui_evs: &mut
         result_ui_evs.insert(ev);
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{collections::HashSet, time::Duration};
+
+    use crossbeam::channel::{Receiver, Sender};
+    use rstest::rstest;
+
+    use super::handle_idle_event;
+    use crate::{
+        mpd::commands::IdleEvent,
+        shared::{
+            events::{ClientRequest, WorkRequest},
+            mpd_query::{GLOBAL_STATUS_UPDATE, MpdQuery},
+        },
+        tests::fixtures::{app_context, client_request_channel, work_request_channel},
+    };
+
+    #[rstest]
+    fn update_idle_event_queries_status(
+        work_request_channel: (Sender<WorkRequest>, Receiver<WorkRequest>),
+        client_request_channel: (Sender<ClientRequest>, Receiver<ClientRequest>),
+    ) {
+        let rx = client_request_channel.1.clone();
+        let app_context = app_context(work_request_channel, client_request_channel);
+
+        handle_idle_event(IdleEvent::Update, &app_context, &mut HashSet::new());
+
+        assert!(matches!(
+            rx.recv_timeout(Duration::from_millis(100)).unwrap(),
+            ClientRequest::Query(MpdQuery { id: GLOBAL_STATUS_UPDATE, .. })
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use super::command::{create_env, run_external, run_external_blocking};
+use crate::{
+    config::Config,
+    context::AppContext,
+    mpd::commands::{Song, Status, Volume, volume::Bound},
+};
+
+/// External commands configured to run on changes in MPD or in rmpc's
+/// lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Hook {
+    SongChange,
+    StateChange,
+    VolumeChange,
+    QueueChange,
+    OptionsChange,
+    DbUpdateFinished,
+    Connect,
+    Disconnect,
+    Startup,
+    Exit,
+}
+
+type HookEnv = Vec<(&'static str, String)>;
+
+impl Hook {
+    fn command(self, config: &Config) -> Option<&Arc<Vec<String>>> {
+        match self {
+            Hook::SongChange => config.on_song_change.as_ref(),
+            Hook::StateChange => config.on_state_change.as_ref(),
+            Hook::VolumeChange => config.on_volume_change.as_ref(),
+            Hook::QueueChange => config.on_queue_change.as_ref(),
+            Hook::OptionsChange => config.on_options_change.as_ref(),
+            Hook::DbUpdateFinished => config.on_db_update_finished.as_ref(),
+            Hook::Connect => config.on_connect.as_ref(),
+            Hook::Disconnect => config.on_disconnect.as_ref(),
+            Hook::Startup => config.on_startup.as_ref(),
+            Hook::Exit => config.on_exit.as_ref(),
+        }
+    }
+}
+
+/// Runs the hook in the background with the default environment extended by
+/// the hook's own variables
+pub(super) fn run(context: &AppContext, hook: Hook, env: HookEnv) {
+    let Some(command) = hook.command(&context.config) else {
+        return;
+    };
+
+    log::debug!(hook:?; "Running hook");
+    run_external(Arc::clone(command), full_env(context, env));
+}
+
+/// Runs the hook and waits for it to finish, used when rmpc is about to exit
+/// and a background thread would be killed with it
+pub(super) fn run_blocking(context: &AppContext, hook: Hook, env: HookEnv) {
+    let Some(command) = hook.command(&context.config) else {
+        return;
+    };
+
+    log::debug!(hook:?; "Running hook");
+    let env = full_env(context, env);
+    if let Err(err) =
+        run_external_blocking(command, env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+    {
+        log::error!(err:?, hook:?; "Hook failed");
+    }
+}
+
+fn full_env(context: &AppContext, env: HookEnv) -> Vec<(String, String)> {
+    let mut result = create_env(context, std::iter::empty::<&str>());
+    result.extend(env.into_iter().map(|(k, v)| (k.to_owned(), v)));
+    result
+}
+
+/// Runs hooks for every difference between the previous and the current
+/// status
+pub(super) fn on_status_change(context: &AppContext, previous: &Status) {
+    for (hook, env) in status_changes(previous, &context.status) {
+        run(context, hook, env);
+    }
+}
+
+/// Volume is also updated on its own by the mixer idle event, without the rest
+/// of the status
+pub(super) fn on_volume_change(context: &AppContext, previous: Volume) {
+    if previous != context.status.volume {
+        run(context, Hook::VolumeChange, volume_env(previous, context.status.volume));
+    }
+}
+
+pub(super) fn on_queue_change(context: &AppContext, previous: &[Song]) {
+    if previous.iter().map(|song| song.id).eq(context.queue.iter().map(|song| song.id)) {
+        return;
+    }
+
+    run(context, Hook::QueueChange, vec![
+        ("PREV_QUEUE_LENGTH", previous.len().to_string()),
+        ("QUEUE_LENGTH", context.queue.len().to_string()),
+    ]);
+}
+
+fn status_changes(previous: &Status, status: &Status) -> Vec<(Hook, HookEnv)> {
+    let mut result = Vec::new();
+
+    if previous.state != status.state {
+        result.push((Hook::StateChange, vec![("PREV_STATE", previous.state.to_string())]));
+    }
+
+    if previous.volume != status.volume {
+        result.push((Hook::VolumeChange, volume_env(previous.volume, status.volume)));
+    }
+
+    if previous.repeat != status.repeat
+        || previous.random != status.random
+        || previous.single != status.single
+        || previous.consume != status.consume
+        || previous.xfade != status.xfade
+    {
+        result.push((Hook::OptionsChange, vec![
+            ("REPEAT", status.repeat.to_string()),
+            ("RANDOM", status.random.to_string()),
+            ("SINGLE", status.single.to_mpd_value().to_owned()),
+            ("CONSUME", status.consume.to_mpd_value().to_owned()),
+            ("CROSSFADE", status.xfade.unwrap_or_default().to_string()),
+        ]));
+    }
+
+    if let (Some(id), None) = (previous.updating_db, status.updating_db) {
+        result.push((Hook::DbUpdateFinished, vec![("UPDATE_ID", id.to_string())]));
+    }
+
+    result
+}
+
+fn volume_env(previous: Volume, volume: Volume) -> HookEnv {
+    vec![("PREV_VOLUME", previous.value().to_string()), ("VOLUME", volume.value().to_string())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hook, status_changes};
+    use crate::mpd::commands::{State, Status, Volume, status::OnOffOneshot};
+
+    #[test]
+    fn unchanged_status_runs_no_hooks() {
+        let status = Status { state: State::Play, updating_db: Some(1), ..Default::default() };
+
+        assert!(status_changes(&status, &status.clone()).is_empty());
+    }
+
+    #[test]
+    fn each_change_runs_its_hook() {
+        let previous = Status { updating_db: Some(3), ..Default::default() };
+        let status = Status {
+            state: State::Pause,
+            volume: Volume::new(40),
+            consume: OnOffOneshot::Oneshot,
+            ..Default::default()
+        };
+
+        assert_eq!(status_changes(&previous, &status), vec![
+            (Hook::StateChange, vec![("PREV_STATE", "Stopped".to_owned())]),
+            (Hook::VolumeChange, vec![
+                ("PREV_VOLUME", "0".to_owned()),
+                ("VOLUME", "40".to_owned())
+            ]),
+            (Hook::OptionsChange, vec![
+                ("REPEAT", "false".to_owned()),
+                ("RANDOM", "false".to_owned()),
+                ("SINGLE", "0".to_owned()),
+                ("CONSUME", "oneshot".to_owned()),
+                ("CROSSFADE", "0".to_owned()),
+            ]),
+            (Hook::DbUpdateFinished, vec![("UPDATE_ID", "3".to_owned())]),
+        ]);
+    }
+}
@@ -3,7 +3,7 @@ use std::time::Duration;
 use crossbeam::channel::Sender;
 use crossterm::event::Event;
 
-use crate::shared::{events::AppEvent, mouse_event::MouseEventTracker};
+use crate::shared::{events::AppEvent, mouse_event::MouseEventTracker, terminal};
 
 pub fn init(event_tx: Sender<AppEvent>) -> std::io::Result<std::thread::JoinHandle<()>> {
     std::thread::Builder::new().name("input".to_owned()).spawn(move || input_poll_task(&event_tx))
@@ -26,6 +26,19 @@ fn input_poll_task(event_tx: &Sender<AppEvent>) {
                         log::error!(error:? = err; "Failed to send user input");
                     }
                 }
+                // Only reported when theme variants are configured. Input is read on this thread
+                // so it is safe to read the response here.
+                Ok(Event::FocusGained) => match terminal::query_background_mode() {
+                    Ok(Some(mode)) => {
+                        if let Err(err) = event_tx.send(AppEvent::TerminalBackgroundDetected(mode))
+                        {
+                            log::error!(error:? = err; "Failed to send terminal background");
+                        }
+                    }
+                    Ok(None) => log::debug!("Terminal did not report its background color"),
+                    Err(err) => log::warn!(error:? = err; "Failed to query terminal background"),
+                },
+                Ok(Event::FocusLost) => {}
                 Ok(Event::Resize(columns, rows)) => {
                     if let Err(err) = event_tx.send(AppEvent::Resized { columns, rows }) {
                         log::error!(error:? = err; "Failed to render request after resize");
@@ -1,8 +1,12 @@
 pub mod client;
 pub mod command;
 pub mod config_watcher;
+pub mod daemon;
 pub mod event_loop;
+pub mod hooks;
 pub mod input;
+pub mod mpris;
+pub mod notifications;
 pub mod scheduler;
 pub mod socket;
 pub mod work;
@@ -0,0 +1,638 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use crossbeam::channel::Sender;
+use itertools::Itertools;
+use zbus::{
+    block_on,
+    blocking::{Connection, connection},
+    fdo,
+    interface,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, Value},
+};
+
+use crate::{
+    context::AppContext,
+    mpd::{
+        client::Client,
+        commands::{Song, State, Status, Volume, status::OnOffOneshot, volume::Bound},
+        mpd_client::{MpdClient, ValueChange},
+    },
+    shared::{events::ClientRequest, image::write_album_art_file, mpd_query::MpdCommand},
+};
+
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+/// Difference between the expected and the reported position of the current
+/// song which is considered a seek rather than an imprecise status update
+const SEEK_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// MPRIS2 server publishing the playback state on the session bus. Method
+/// calls from other applications are forwarded to MPD and their effect comes
+/// back through the usual idle events and [`Mpris::update`].
+pub struct Mpris {
+    connection: Connection,
+}
+
+/// Starts the MPRIS2 server if it is enabled in the config. Failure to start it
+/// is not fatal, rmpc works the same without it.
+pub fn init(context: &AppContext) -> Option<Mpris> {
+    if !context.config.enable_mpris {
+        return None;
+    }
+
+    let name = format!("org.mpris.MediaPlayer2.rmpc.instance{}", std::process::id());
+    let result = connection::Builder::session()
+        .and_then(|builder| builder.name(name))
+        .map_err(anyhow::Error::from)
+        .and_then(|builder| Mpris::new(builder, Player::new(context)));
+
+    match result {
+        Ok(mpris) => Some(mpris),
+        Err(err) => {
+            log::error!(err:?; "Failed to start MPRIS server");
+            None
+        }
+    }
+}
+
+impl Mpris {
+    fn new(builder: connection::Builder<'_>, player: Player) -> Result<Self> {
+        let connection =
+            builder.serve_at(OBJECT_PATH, Root)?.serve_at(OBJECT_PATH, player)?.build()?;
+        Ok(Self { connection })
+    }
+
+    /// Publishes changes of the status and the current song since the last
+    /// update
+    pub fn update(&self, context: &AppContext) {
+        if let Err(err) = self.update_inner(context) {
+            log::error!(err:?; "Failed to update MPRIS state");
+        }
+    }
+
+    fn update_inner(&self, context: &AppContext) -> Result<()> {
+        let player = self.connection.object_server().interface::<_, Player>(OBJECT_PATH)?;
+        let emitter = player.signal_emitter();
+        let mut iface = player.get_mut();
+
+        let new = PlayerState::new(&context.status, context.find_current_song_in_queue());
+        let old = std::mem::replace(&mut iface.state, new);
+
+        let song_changed =
+            old.song.as_ref().map(|s| s.id) != iface.state.song.as_ref().map(|s| s.id);
+        if song_changed {
+            iface.art_url = None;
+            if let Some(song) = &iface.state.song {
+                self.fetch_album_art(context, song);
+            }
+        }
+
+        let new = &iface.state;
+        if old.state != new.state {
+            block_on(iface.playback_status_changed(emitter))?;
+        }
+        if old.repeat != new.repeat || old.single != new.single {
+            block_on(iface.loop_status_changed(emitter))?;
+        }
+        if old.random != new.random {
+            block_on(iface.shuffle_changed(emitter))?;
+        }
+        if old.volume != new.volume {
+            block_on(iface.volume_changed(emitter))?;
+        }
+        if old.song != new.song {
+            block_on(iface.metadata_changed(emitter))?;
+        }
+        if !song_changed && old.seeked_to(new) {
+            block_on(Player::seeked(emitter, micros(new.elapsed)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the song's album art to the cache directory in the background
+    /// and publishes its URL once done
+    fn fetch_album_art(&self, context: &AppContext, song: &Song) {
+        let Some(cache_dir) = context.config.cache_dir.clone() else {
+            return;
+        };
+        let connection = self.connection.clone();
+        let (id, file) = (song.id, song.file.clone());
+
+        context.command(move |client| {
+            let url = match client.find_album_art(&file)? {
+                Some(data) => Some(write_album_art_file(
+                    &cache_dir.join("mpris"),
+                    &format!("cover_{id}"),
+                    &data,
+                )?),
+                None => None,
+            };
+
+            let player = connection.object_server().interface::<_, Player>(OBJECT_PATH)?;
+            let mut iface = player.get_mut();
+            if iface.state.song.as_ref().is_some_and(|song| song.id == id) {
+                iface.art_url = url;
+                block_on(iface.metadata_changed(player.signal_emitter()))?;
+            }
+            Ok(())
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PlayerState {
+    state: State,
+    repeat: bool,
+    single: OnOffOneshot,
+    random: bool,
+    volume: Volume,
+    elapsed: Duration,
+    updated_at: Instant,
+    song: Option<Song>,
+}
+
+impl PlayerState {
+    fn new(status: &Status, song: Option<(usize, &Song)>) -> Self {
+        Self {
+            state: status.state,
+            repeat: status.repeat,
+            single: status.single,
+            random: status.random,
+            volume: status.volume,
+            elapsed: status.elapsed,
+            updated_at: Instant::now(),
+            song: song.map(|(_, song)| song.clone()),
+        }
+    }
+
+    /// MPRIS clients interpolate the position themselves, so it is only
+    /// estimated from the last status while playing
+    fn position(&self) -> Duration {
+        match self.state {
+            State::Play => self.elapsed + self.updated_at.elapsed(),
+            State::Pause | State::Stop => self.elapsed,
+        }
+    }
+
+    fn seeked_to(&self, new: &PlayerState) -> bool {
+        let expected = match self.state {
+            State::Play => self.elapsed + new.updated_at.duration_since(self.updated_at),
+            State::Pause | State::Stop => self.elapsed,
+        };
+        new.state != State::Stop && expected.abs_diff(new.elapsed) > SEEK_THRESHOLD
+    }
+
+    fn loop_status(&self) -> &'static str {
+        match (self.repeat, self.single) {
+            (false, _) => "None",
+            (true, OnOffOneshot::On | OnOffOneshot::Oneshot) => "Track",
+            (true, OnOffOneshot::Off) => "Playlist",
+        }
+    }
+}
+
+fn micros(duration: Duration) -> i64 {
+    i64::try_from(duration.as_micros()).unwrap_or(i64::MAX)
+}
+
+fn track_id(song: &Song) -> ObjectPath<'static> {
+    ObjectPath::from_string_unchecked(format!("/org/rmpc/track/{}", song.id))
+}
+
+fn metadata(song: Option<&Song>, art_url: Option<&str>) -> HashMap<String, Value<'static>> {
+    let mut result = HashMap::new();
+    let Some(song) = song else {
+        result.insert("mpris:trackid".to_owned(), ObjectPath::from_str_unchecked(NO_TRACK).into());
+        return result;
+    };
+
+    result.insert("mpris:trackid".to_owned(), track_id(song).into());
+    if let Some(duration) = song.duration {
+        result.insert("mpris:length".to_owned(), micros(duration).into());
+    }
+    if let Some(url) = art_url {
+        result.insert("mpris:artUrl".to_owned(), url.to_owned().into());
+    }
+    if song.file.contains("://") {
+        result.insert("xesam:url".to_owned(), song.file.clone().into());
+    }
+    for (key, tag) in [("xesam:title", "title"), ("xesam:album", "album")] {
+        if let Some(value) = song.metadata.get(tag) {
+            result.insert(key.to_owned(), value.first().to_owned().into());
+        }
+    }
+    for (key, tag) in [
+        ("xesam:artist", "artist"),
+        ("xesam:albumArtist", "albumartist"),
+        ("xesam:composer", "composer"),
+        ("xesam:genre", "genre"),
+    ] {
+        if let Some(value) = song.metadata.get(tag) {
+            result.insert(key.to_owned(), value.iter().map(ToOwned::to_owned).collect_vec().into());
+        }
+    }
+    // Track and disc are often in the `number/total` format
+    for (key, tag) in [("xesam:trackNumber", "track"), ("xesam:discNumber", "disc")] {
+        if let Some(number) = song
+            .metadata
+            .get(tag)
+            .and_then(|value| value.first().split('/').next()?.trim().parse::<i32>().ok())
+        {
+            result.insert(key.to_owned(), number.into());
+        }
+    }
+
+    result
+}
+
+struct Root;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &'static str {
+        "rmpc"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct Player {
+    client_request_sender: Sender<ClientRequest>,
+    state: PlayerState,
+    art_url: Option<String>,
+}
+
+impl Player {
+    fn new(context: &AppContext) -> Self {
+        Self {
+            client_request_sender: context.client_request_sender.clone(),
+            state: PlayerState::new(&context.status, context.find_current_song_in_queue()),
+            art_url: None,
+        }
+    }
+
+    fn command(&self, callback: impl FnOnce(&mut Client<'_>) -> Result<()> + Send + 'static) {
+        if let Err(err) = self
+            .client_request_sender
+            .send(ClientRequest::Command(MpdCommand { callback: Box::new(callback) }))
+        {
+            log::error!(error:? = err; "Failed to send command request");
+        }
+    }
+}
+
+// Method arguments are owned as the interface macro deserializes them
+#[allow(clippy::needless_pass_by_value)]
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {
+        self.command(|client| Ok(client.next()?));
+    }
+
+    fn previous(&self) {
+        self.command(|client| Ok(client.prev()?));
+    }
+
+    fn pause(&self) {
+        self.command(|client| Ok(client.pause()?));
+    }
+
+    fn play_pause(&self) {
+        match self.state.state {
+            State::Stop => self.command(|client| Ok(client.play()?)),
+            State::Play | State::Pause => self.command(|client| Ok(client.pause_toggle()?)),
+        }
+    }
+
+    fn stop(&self) {
+        self.command(|client| Ok(client.stop()?));
+    }
+
+    fn play(&self) {
+        match self.state.state {
+            State::Pause => self.command(|client| Ok(client.unpause()?)),
+            State::Play | State::Stop => self.command(|client| Ok(client.play()?)),
+        }
+    }
+
+    /// Offset is in microseconds, MPD seeks by whole seconds
+    fn seek(&self, offset: i64) {
+        let secs = u32::try_from(offset.unsigned_abs() / 1_000_000).unwrap_or(u32::MAX);
+        let change =
+            if offset >= 0 { ValueChange::Increase(secs) } else { ValueChange::Decrease(secs) };
+        self.command(move |client| Ok(client.seek_current(change)?));
+    }
+
+    /// Ignored when the track is no longer current or the position is out of
+    /// its range as required by the specification
+    fn set_position(&self, track: ObjectPath<'_>, position: i64) {
+        let Some(song) = &self.state.song else {
+            return;
+        };
+        let in_range = song.duration.is_none_or(|duration| position <= micros(duration));
+        if track != track_id(song) || position < 0 || !in_range {
+            return;
+        }
+
+        let secs = u32::try_from(position / 1_000_000).unwrap_or(u32::MAX);
+        self.command(move |client| Ok(client.seek_current(ValueChange::Set(secs))?));
+    }
+
+    fn open_uri(&self, uri: &str) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(format!("Opening '{uri}' is not supported")))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        match self.state.state {
+            State::Play => "Playing",
+            State::Pause => "Paused",
+            State::Stop => "Stopped",
+        }
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> &str {
+        self.state.loop_status()
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&mut self, value: String) {
+        let (repeat, single) = match value.as_str() {
+            "None" => (false, OnOffOneshot::Off),
+            "Track" => (true, OnOffOneshot::On),
+            "Playlist" => (true, OnOffOneshot::Off),
+            _ => {
+                log::warn!(value = value.as_str(); "Received unknown MPRIS loop status");
+                return;
+            }
+        };
+        self.command(move |client| {
+            client.repeat(repeat)?;
+            client.single(single)?;
+            Ok(())
+        });
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.state.random
+    }
+
+    #[zbus(property)]
+    fn set_shuffle(&mut self, value: bool) {
+        self.command(move |client| Ok(client.random(value)?));
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, Value<'static>> {
+        metadata(self.state.song.as_ref(), self.art_url.as_deref())
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        f64::from(*self.state.volume.value()) / 100.0
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, value: f64) {
+        // Clamped to 0..=100 so the cast cannot truncate
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let volume = Volume::new((value * 100.0).round().clamp(0.0, 100.0) as u8);
+        self.command(move |client| Ok(client.set_volume(volume)?));
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        micros(self.state.position())
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    /// MPD cannot change the playback rate
+    #[zbus(property)]
+    fn set_rate(&mut self, _value: f64) {}
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_seek(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
+
+    use crossbeam::channel::unbounded;
+    use rstest::rstest;
+    use zbus::{
+        blocking::fdo::PropertiesProxy,
+        names::InterfaceName,
+        zvariant::{OwnedValue, Value},
+    };
+
+    use super::{Mpris, OBJECT_PATH, Player, PlayerState, metadata};
+    use crate::{
+        mpd::commands::{Song, State, Status, Volume, status::OnOffOneshot},
+        shared::events::ClientRequest,
+        tests::fixtures::dbus::p2p_connection,
+    };
+
+    fn song() -> Song {
+        Song {
+            id: 7,
+            file: "artist/album/song.flac".to_owned(),
+            duration: Some(Duration::from_secs(90)),
+            metadata: HashMap::from([
+                ("title".to_owned(), "Title".into()),
+                ("artist".to_owned(), vec!["First".to_owned(), "Second".to_owned()].into()),
+                ("track".to_owned(), "3/12".into()),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case(false, OnOffOneshot::On, "None")]
+    #[case(true, OnOffOneshot::Off, "Playlist")]
+    #[case(true, OnOffOneshot::On, "Track")]
+    #[case(true, OnOffOneshot::Oneshot, "Track")]
+    fn loop_status(#[case] repeat: bool, #[case] single: OnOffOneshot, #[case] expected: &str) {
+        let status = Status { repeat, single, ..Default::default() };
+
+        assert_eq!(PlayerState::new(&status, None).loop_status(), expected);
+    }
+
+    #[test]
+    fn metadata_of_song() {
+        let song = song();
+
+        let result = metadata(Some(&song), Some("file:///cover.jpg"));
+
+        assert_eq!(result["mpris:trackid"], Value::from(super::track_id(&song)));
+        assert_eq!(result["mpris:length"], Value::from(90_000_000_i64));
+        assert_eq!(result["mpris:artUrl"], Value::from("file:///cover.jpg"));
+        assert_eq!(result["xesam:title"], Value::from("Title"));
+        assert_eq!(result["xesam:artist"], Value::from(vec!["First", "Second"]));
+        assert_eq!(result["xesam:trackNumber"], Value::from(3));
+        assert!(!result.contains_key("xesam:album"));
+        assert!(!result.contains_key("xesam:url"));
+    }
+
+    #[test]
+    fn metadata_without_song() {
+        let result = metadata(None, None);
+
+        assert_eq!(result.len(), 1);
+        assert!(result.contains_key("mpris:trackid"));
+    }
+
+    #[rstest]
+    #[case(State::Play, 12, false)]
+    #[case(State::Play, 30, true)]
+    #[case(State::Pause, 10, false)]
+    #[case(State::Pause, 3, true)]
+    fn seek_is_detected(#[case] state: State, #[case] elapsed: u64, #[case] expected: bool) {
+        let updated_at = Instant::now();
+        let old = PlayerState {
+            state,
+            elapsed: Duration::from_secs(10),
+            updated_at,
+            ..PlayerState::new(&Status::default(), None)
+        };
+        let new = PlayerState {
+            state,
+            elapsed: Duration::from_secs(elapsed),
+            updated_at: updated_at + Duration::from_secs(2),
+            ..old.clone()
+        };
+
+        assert_eq!(old.seeked_to(&new), expected);
+    }
+
+    #[test]
+    fn serves_player_over_dbus() {
+        let (tx, rx) = unbounded();
+        let song = song();
+        let status = Status {
+            state: State::Pause,
+            volume: Volume::new(40),
+            random: true,
+            ..Default::default()
+        };
+        let player = Player {
+            client_request_sender: tx,
+            state: PlayerState::new(&status, Some((0, &song))),
+            art_url: None,
+        };
+
+        let (_server, client) = p2p_connection(|builder| Mpris::new(builder, player).unwrap());
+
+        let properties = PropertiesProxy::builder(&client)
+            .destination("org.mpris.MediaPlayer2.rmpc")
+            .unwrap()
+            .path(OBJECT_PATH)
+            .unwrap()
+            .build()
+            .unwrap();
+        let interface = InterfaceName::from_static_str("org.mpris.MediaPlayer2.Player").unwrap();
+        let get = |name: &str| -> OwnedValue { properties.get(interface.clone(), name).unwrap() };
+
+        assert_eq!(get("PlaybackStatus"), Value::from("Paused").try_into().unwrap());
+        assert_eq!(get("Volume"), Value::from(0.4).try_into().unwrap());
+        assert_eq!(get("Shuffle"), Value::from(true).try_into().unwrap());
+        assert_eq!(get("LoopStatus"), Value::from("None").try_into().unwrap());
+        let metadata: HashMap<String, OwnedValue> = get("Metadata").try_into().unwrap();
+        assert_eq!(metadata["xesam:title"], Value::from("Title").try_into().unwrap());
+
+        client
+            .call_method(
+                Some("org.mpris.MediaPlayer2.rmpc"),
+                OBJECT_PATH,
+                Some("org.mpris.MediaPlayer2.Player"),
+                "PlayPause",
+                &(),
+            )
+            .unwrap();
+        assert!(matches!(rx.try_recv(), Ok(ClientRequest::Command(_))));
+    }
+}
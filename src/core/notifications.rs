@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use crossbeam::channel::{Sender, unbounded};
+use zbus::{blocking::Connection, zvariant::Value};
+
+use crate::{
+    config::{
+        notifications::{Notification, Urgency},
+        theme::properties::{Property, PropertyKind},
+    },
+    context::AppContext,
+    mpd::{commands::Song, mpd_client::MpdClient},
+    shared::image::write_album_art_file,
+    ui::panes::PropertySource,
+};
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationEvent {
+    SongChange,
+    StateChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Message {
+    title: String,
+    body: String,
+    urgency: Urgency,
+    image_url: Option<String>,
+}
+
+/// Sends desktop notifications through the org.freedesktop.Notifications
+/// service. Notifications are sent one by one from a separate thread, each one
+/// replaces the previous so that they do not stack up.
+pub struct Notifier {
+    sender: Sender<Message>,
+}
+
+/// Starts the thread sending notifications if they are enabled in the config.
+/// There is nobody to show them without a session bus, ie. over SSH or on a
+/// plain TTY, so nothing is started in that case.
+pub fn init(context: &AppContext) -> Option<Notifier> {
+    if !context.config.notifications.enabled {
+        return None;
+    }
+
+    let connection = match Connection::session() {
+        Ok(connection) => connection,
+        Err(err) => {
+            log::error!(err:?; "Failed to connect to the session bus for notifications");
+            return None;
+        }
+    };
+
+    let (sender, receiver) = unbounded::<Message>();
+    let result = std::thread::Builder::new().name("notifications".to_owned()).spawn(move || {
+        let mut replaces_id = 0;
+        for message in receiver {
+            match send(&connection, replaces_id, &message) {
+                Ok(id) => replaces_id = id,
+                Err(err) => log::error!(err:?; "Failed to send notification"),
+            }
+        }
+    });
+
+    match result {
+        Ok(_) => Some(Notifier { sender }),
+        Err(err) => {
+            log::error!(err:?; "Failed to start notifications thread");
+            None
+        }
+    }
+}
+
+impl Notifier {
+    pub fn notify(&self, context: &AppContext, event: NotificationEvent) {
+        let notifications = &context.config.notifications;
+        let Some(notification) = (match event {
+            NotificationEvent::SongChange => notifications.song_change.as_ref(),
+            NotificationEvent::StateChange => notifications.state_change.as_ref(),
+        }) else {
+            return;
+        };
+
+        let song = context.find_current_song_in_queue().map(|(_, song)| song);
+        let message = message(context, notification, song);
+
+        // Album art has to be fetched from MPD first which happens on the client
+        // thread, only the song change notification shows it
+        match (event, song, context.config.cache_dir.clone()) {
+            (NotificationEvent::SongChange, Some(song), Some(cache_dir)) => {
+                let sender = self.sender.clone();
+                let (id, file) = (song.id, song.file.clone());
+                context.command(move |client| {
+                    let image_url = match client.find_album_art(&file) {
+                        Ok(Some(data)) => write_album_art_file(
+                            &cache_dir.join("notifications"),
+                            &format!("cover_{id}"),
+                            &data,
+                        )
+                        .inspect_err(|err| log::error!(err:?; "Failed to write album art"))
+                        .ok(),
+                        Ok(None) => None,
+                        Err(err) => {
+                            log::error!(err:?; "Failed to fetch album art for notification");
+                            None
+                        }
+                    };
+                    sender.send(Message { image_url, ..message })?;
+                    Ok(())
+                });
+            }
+            _ => {
+                if let Err(err) = self.sender.send(message) {
+                    log::error!(err:?; "Failed to queue notification");
+                }
+            }
+        }
+    }
+}
+
+fn message(context: &AppContext, notification: &Notification, song: Option<&Song>) -> Message {
+    let format = |properties: &[Property<PropertyKind>]| -> String {
+        properties
+            .iter()
+            .filter_map(|property| {
+                property.as_string(
+                    song,
+                    &PropertySource::from(context),
+                    &context.config.theme.format_tag_separator,
+                    context.config.theme.multiple_tag_resolution_strategy,
+                )
+            })
+            .collect()
+    };
+
+    Message {
+        title: format(&notification.title),
+        body: format(&notification.body),
+        urgency: notification.urgency,
+        image_url: None,
+    }
+}
+
+/// Returns the id of the new notification which the next one replaces
+fn send(connection: &Connection, replaces_id: u32, message: &Message) -> Result<u32> {
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+    hints.insert("urgency", message.urgency.to_hint().into());
+    if let Some(url) = &message.image_url {
+        hints.insert("image-path", url.as_str().into());
+    }
+
+    let reply = connection.call_method(
+        Some(DESTINATION),
+        PATH,
+        Some(DESTINATION),
+        "Notify",
+        &(
+            "rmpc",
+            replaces_id,
+            "",
+            message.title.as_str(),
+            message.body.as_str(),
+            Vec::<&str>::new(),
+            hints,
+            -1_i32,
+        ),
+    )?;
+
+    Ok(reply.body().deserialize()?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use rstest::rstest;
+    use zbus::{interface, zvariant::OwnedValue};
+
+    use super::{Message, PATH, message, send};
+    use crate::{
+        config::notifications::Urgency,
+        context::AppContext,
+        mpd::commands::Song,
+        tests::fixtures::{app_context, dbus::p2p_connection},
+    };
+
+    #[rstest]
+    fn default_song_change_message(app_context: AppContext) {
+        let song = Song {
+            metadata: HashMap::from([
+                ("title".to_owned(), "Title".into()),
+                ("artist".to_owned(), "Artist".into()),
+            ]),
+            ..Default::default()
+        };
+        let notification = app_context.config.notifications.song_change.as_ref().unwrap();
+
+        assert_eq!(message(&app_context, notification, Some(&song)), Message {
+            title: "Title".to_owned(),
+            body: "Artist - Unknown Album".to_owned(),
+            urgency: Urgency::Low,
+            image_url: None,
+        });
+    }
+
+    /// Arguments of the `Notify` method which the test checks
+    #[derive(Debug)]
+    struct Call {
+        app_name: String,
+        replaces_id: u32,
+        summary: String,
+        body: String,
+        hints: HashMap<String, OwnedValue>,
+    }
+
+    #[derive(Default)]
+    struct Server {
+        calls: Arc<Mutex<Vec<Call>>>,
+    }
+
+    #[allow(clippy::too_many_arguments, clippy::needless_pass_by_value)]
+    #[interface(name = "org.freedesktop.Notifications")]
+    impl Server {
+        fn notify(
+            &self,
+            app_name: String,
+            replaces_id: u32,
+            app_icon: String,
+            summary: String,
+            body: String,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            expire_timeout: i32,
+        ) -> u32 {
+            assert!(app_icon.is_empty() && actions.is_empty() && expire_timeout == -1);
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(Call { app_name, replaces_id, summary, body, hints });
+            u32::try_from(calls.len()).unwrap() + 10
+        }
+    }
+
+    #[test]
+    fn replaces_previous_notification() {
+        let server = Server::default();
+        let calls = Arc::clone(&server.calls);
+        let (_server, client) =
+            p2p_connection(|builder| builder.serve_at(PATH, server).unwrap().build().unwrap());
+
+        let message = Message {
+            title: "Title".to_owned(),
+            body: "Body".to_owned(),
+            urgency: Urgency::Critical,
+            image_url: Some("file:///cover.png".to_owned()),
+        };
+        let id = send(&client, 0, &message).unwrap();
+        send(&client, id, &message).unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.iter().map(|call| call.replaces_id).collect::<Vec<_>>(), [0, 11]);
+        assert_eq!(calls[0].app_name, "rmpc");
+        assert_eq!(calls[0].summary, "Title");
+        assert_eq!(calls[0].body, "Body");
+        let hints = &calls[0].hints;
+        assert_eq!(u8::try_from(&hints["urgency"]).unwrap(), 2);
+        assert_eq!(<&str>::try_from(&hints["image-path"]).unwrap(), "file:///cover.png");
+    }
+}
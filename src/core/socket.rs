@@ -1,11 +1,11 @@
 use std::{
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, Write},
     os::unix::net::UnixListener,
     path::PathBuf,
     sync::Arc,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use crossbeam::channel::Sender;
 
 use crate::{
@@ -13,7 +13,14 @@ use crate::{
     WorkRequest,
     config::Config,
     shared::{
-        ipc::{SocketCommand, SocketCommandExecute, get_socket_path},
+        ipc::{
+            SocketCommand,
+            SocketCommandExecute,
+            SocketResponse,
+            create_socket_dir,
+            find_instances,
+            get_socket_path,
+        },
         macros::try_cont,
     },
     try_skip,
@@ -23,9 +30,19 @@ pub(crate) fn init(
     event_tx: Sender<AppEvent>,
     work_tx: Sender<WorkRequest>,
     config: Arc<Config>,
+    config_path: Option<PathBuf>,
+    name: Option<&str>,
 ) -> Result<SocketGuard> {
+    if let Some(name) = name {
+        if !find_instances(None, Some(name)).unwrap_or_default().is_empty() {
+            bail!("rmpc instance named '{name}' is already running");
+        }
+    }
+
+    create_socket_dir()?;
+
     let pid = std::process::id();
-    let addr = get_socket_path(pid);
+    let addr = get_socket_path(pid, name);
     let guard = SocketGuard(addr.clone());
     let listener = UnixListener::bind(&addr).context("Failed to bind to unix socket")?;
 
@@ -36,10 +53,38 @@ pub(crate) fn init(
 
             let mut buf = String::new();
             try_cont!(reader.read_line(&mut buf), "Failed to read from socket client");
+            if buf.is_empty() {
+                // Client only checked whether this instance is alive
+                continue;
+            }
             let command: SocketCommand =
                 try_cont!(serde_json::from_str(&buf), "Failed to parse socket command");
 
             log::debug!(command:?, addr:?; "Got command from unix socket");
+            if let SocketCommand::Get(query) = command {
+                let response = match query.query(&event_tx, config_path.as_deref()) {
+                    Ok(value) => SocketResponse::Ok(value),
+                    Err(err) => SocketResponse::Err(err.to_string()),
+                };
+                let mut response = try_cont!(
+                    serde_json::to_string(&response),
+                    "Failed to serialize socket response"
+                );
+                response.push('\n');
+                try_skip!(
+                    reader.get_mut().write_all(response.as_bytes()),
+                    "Failed to write response to socket client"
+                );
+                continue;
+            }
+            if let SocketCommand::Subscribe(subscription) = command {
+                try_skip!(
+                    subscription.subscribe(reader.into_inner(), &event_tx),
+                    "Failed to subscribe socket client"
+                );
+                continue;
+            }
+
             try_skip!(
                 command.execute(&event_tx, &work_tx, &config),
                 "Socket command execution failed"
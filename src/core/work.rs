@@ -1,10 +1,10 @@
 use std::{path::PathBuf, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use crossbeam::channel::{Receiver, Sender};
 
 use crate::{
-    config::{Config, cli_config::CliConfig},
+    config::{Config, cli::Command, cli_config::CliConfig},
     shared::{
         events::{AppEvent, ClientRequest, WorkDone, WorkRequest},
         lrc::LrcIndex,
@@ -37,6 +37,15 @@ fn handle_work_request(
     config: &CliConfig,
 ) -> Result<WorkDone> {
     match request {
+        // Following would block the client shared with the UI forever
+        WorkRequest::Command(
+            Command::Status { follow: true, .. } | Command::Song { follow: true, .. },
+        ) => {
+            bail!("--follow cannot be used in command mode")
+        }
+        WorkRequest::Command(Command::Idle { .. }) => {
+            bail!("idle cannot be used in command mode")
+        }
         WorkRequest::Command(command) => {
             let callback = command.execute(config)?; // TODO log
             try_skip!(
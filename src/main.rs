@@ -14,14 +14,15 @@ use log::info;
 use rustix::path::Arg;
 use shared::{
     dependencies::CAVA,
-    ipc::{get_socket_path, list_all_socket_paths},
+    ipc::{find_instances, find_single_instance, list_instances, validate_instance_name},
     macros::{status_warn, try_skip},
 };
 
 use crate::{
     config::{
         ConfigFile,
-        cli::{Args, Command},
+        cli::{Args, Command, ConfigCommand, RemoteCmd},
+        theme_mode::ThemeVariants,
     },
     mpd::client::Client,
     shared::{
@@ -50,7 +51,14 @@ fn main() -> Result<()> {
     let mut args = Args::parse();
     let config_path = args.config_path();
     match args.command {
-        Some(Command::Config { current: false }) => {
+        Some(Command::Config { command: Some(ConfigCommand::Check), .. }) => {
+            let report = config::check::check(&config_path, args.theme.as_deref());
+            print!("{report}");
+            if report.has_errors() {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Config { current: false, command: None }) => {
             std::io::stdout().write_all(include_bytes!(
                 "../docs/src/content/docs/next/assets/example_config.ron"
             ))?;
@@ -60,7 +68,7 @@ fn main() -> Result<()> {
                 "../docs/src/content/docs/next/assets/example_theme.ron"
             ))?;
         }
-        Some(Command::Config { current: true }) => match File::open(&config_path) {
+        Some(Command::Config { current: true, command: None }) => match File::open(&config_path) {
             Ok(mut file) => {
                 let mut config = String::new();
                 file.read_to_string(&mut config)?;
@@ -123,6 +131,11 @@ fn main() -> Result<()> {
                 option_env!("VERGEN_GIT_DESCRIBE").map(|g| format!(" git {g}")).unwrap_or_default()
             );
             println!("\n{:<20} {}", "Config path", config_path.as_str()?);
+            if let Ok((_, files)) = ConfigFile::read_with_includes(&config_path) {
+                for file in files.iter().skip(1) {
+                    println!("{:<20} {}", "Included config", file.display());
+                }
+            }
             println!("{:<20} {:?}", "Theme path", config_file.theme);
 
             println!("\nMPD:");
@@ -146,6 +159,7 @@ fn main() -> Result<()> {
             println!("{}", UEBERZUGPP.display());
 
             println!("\nVisualizer:");
+            println!("{:<20} {}", "Backend", config.cava.backend);
             println!("{}", CAVA.display());
         }
         Some(Command::Version) => {
@@ -155,22 +169,37 @@ fn main() -> Result<()> {
                 option_env!("VERGEN_GIT_DESCRIBE").map(|g| format!(" git {g}")).unwrap_or_default()
             );
         }
-        Some(Command::Remote { command, pid }) => {
-            if let Some(pid) = pid {
-                let path = get_socket_path(pid);
-                command.write_to_socket(&path)?;
-                eprintln!("Successfully sent remote command to {}", path.display());
-            } else {
-                for path in list_all_socket_paths()? {
-                    if let Err(err) = command.clone().write_to_socket(&path) {
-                        eprintln!("Failed to send remote command. Error: '{err:?}'");
-                        continue;
-                    }
-                    eprintln!("Successfully sent remote command to {}", path.display());
+        Some(Command::Remote { command: RemoteCmd::List, .. }) => {
+            println!("{:<10} {:<20} Socket", "PID", "Name");
+            for instance in list_instances()? {
+                println!(
+                    "{:<10} {:<20} {}",
+                    instance.pid,
+                    instance.name.as_deref().unwrap_or("-"),
+                    instance.path.display()
+                );
+            }
+        }
+        Some(Command::Remote { command: command @ RemoteCmd::Get { .. }, pid, name }) => {
+            let instance = find_single_instance(pid, name.as_deref())?;
+            let value = command.query_socket(&instance.path)?;
+            println!("{}", serde_json::to_string(&value)?);
+        }
+        Some(Command::Remote { command: command @ RemoteCmd::Subscribe { .. }, pid, name }) => {
+            let instance = find_single_instance(pid, name.as_deref())?;
+            command.subscribe_socket(&instance.path, std::io::stdout().lock())?;
+        }
+        Some(Command::Remote { command, pid, name }) => {
+            for instance in find_instances(pid, name.as_deref())? {
+                let path = instance.path;
+                if let Err(err) = command.clone().write_to_socket(&path) {
+                    eprintln!("Failed to send remote command. Error: '{err:?}'");
+                    continue;
                 }
+                eprintln!("Successfully sent remote command to {}", path.display());
             }
         }
-        Some(cmd) => {
+        Some(cmd) if cmd != Command::Daemon => {
             logging::init_console().expect("Logger to initialize");
             let config: CliConfigFile = match CliConfigFile::read(&config_path) {
                 Ok(cfg) => cfg,
@@ -194,7 +223,11 @@ fn main() -> Result<()> {
             client.set_read_timeout(None)?;
             (cmd.execute(&config)?)(&mut client)?;
         }
-        None => {
+        command => {
+            let daemon = command.is_some();
+            if let Some(name) = &args.name {
+                validate_instance_name(name)?;
+            }
             let (worker_tx, worker_rx) = unbounded::<WorkRequest>();
             let (client_tx, client_rx) = unbounded::<ClientRequest>();
             let (event_tx, event_rx) = unbounded::<AppEvent>();
@@ -205,13 +238,13 @@ fn main() -> Result<()> {
                 .name("dependency_check".to_string())
                 .spawn(|| DEPENDENCIES.iter().for_each(|d| d.log()))?;
 
-            let config = match ConfigFile::read(&config_path).and_then(|val| {
+            let mut config = match ConfigFile::read(&config_path).and_then(|val| {
                 val.into_config(
                     Some(&config_path),
                     args.theme.as_deref(),
                     std::mem::take(&mut args.address),
                     std::mem::take(&mut args.password),
-                    false,
+                    daemon,
                 )
             }) {
                 Ok(cfg) => cfg,
@@ -222,7 +255,7 @@ fn main() -> Result<()> {
                         None,
                         std::mem::take(&mut args.address),
                         std::mem::take(&mut args.password),
-                        false,
+                        daemon,
                     )?
                 }
                 Err(err) => {
@@ -240,11 +273,28 @@ fn main() -> Result<()> {
                         None,
                         std::mem::take(&mut args.address),
                         std::mem::take(&mut args.password),
-                        false,
+                        daemon,
                     )?
                 }
             };
 
+            // Theme given on the command line is not replaced by the light or dark one
+            if args.theme.is_some() {
+                config.theme_variants = ThemeVariants::default();
+            }
+            // Queried before the input thread starts reading the terminal's input
+            let theme_mode = if daemon || config.theme_variants.is_empty() {
+                None
+            } else {
+                shared::terminal::query_background_mode()
+                    .inspect_err(|err| log::warn!(err:?; "Failed to query terminal background"))
+                    .ok()
+                    .flatten()
+            };
+            if let Some(mode) = theme_mode {
+                config.apply_theme_mode(mode);
+            }
+
             config.validate()?;
 
             if let Some(lyrics_dir) = &config.lyrics_dir {
@@ -252,7 +302,9 @@ fn main() -> Result<()> {
                     .send(WorkRequest::IndexLyrics { lyrics_dir: lyrics_dir.clone() })
                     .context("Failed to request lyrics indexing")?;
             }
-            event_tx.send(AppEvent::RequestRender).context("Failed to render first frame")?;
+            if !daemon {
+                event_tx.send(AppEvent::RequestRender).context("Failed to render first frame")?;
+            }
 
             let mut client = Client::init(
                 config.address.clone(),
@@ -267,7 +319,7 @@ fn main() -> Result<()> {
 
             let tx_clone = event_tx.clone();
 
-            let context = AppContext::try_new(
+            let mut context = AppContext::try_new(
                 &mut client,
                 config,
                 tx_clone,
@@ -276,6 +328,7 @@ fn main() -> Result<()> {
                 Scheduler::new((event_tx.clone(), client_tx.clone())),
             )
             .context("Failed to create app context")?;
+            context.detected_theme_mode = theme_mode;
 
             core::client::init(
                 client_rx.clone(),
@@ -289,11 +342,12 @@ fn main() -> Result<()> {
                 event_tx.clone(),
                 Arc::clone(&context.config),
             )?;
-            core::input::init(event_tx.clone())?;
             let _sock_guard = core::socket::init(
                 event_tx.clone(),
                 worker_tx.clone(),
                 Arc::clone(&context.config),
+                config_path.is_file().then(|| config_path.clone()),
+                args.name.as_deref(),
             )
             .context("Failed to initialize socket listener")?;
 
@@ -306,8 +360,17 @@ fn main() -> Result<()> {
                 .inspect_err(|e| log::warn!("Failed to initialize config watcher: {e}")),
             );
 
+            if daemon {
+                info!("Daemon initialized successfully");
+                core::daemon::init(context, event_rx)?.join().expect("daemon loop to not panic");
+                return Ok(());
+            }
+
+            core::input::init(event_tx.clone())?;
             let enable_mouse = context.config.enable_mouse;
-            let terminal = ui::setup_terminal(enable_mouse).context("Failed to setup terminal")?;
+            let report_focus = !context.config.theme_variants.is_empty();
+            let terminal = ui::setup_terminal(enable_mouse, report_focus)
+                .context("Failed to setup terminal")?;
 
             let event_loop_handle = core::event_loop::init(context, event_rx, terminal)?;
 
@@ -323,7 +386,7 @@ fn main() -> Result<()> {
 
             let mut terminal = event_loop_handle.join().expect("event loop to not panic");
 
-            ui::restore_terminal(&mut terminal, enable_mouse)
+            ui::restore_terminal(&mut terminal, enable_mouse, report_focus)
                 .context("Terminal restore to succeed")?;
         }
     }
@@ -1,7 +1,10 @@
+use serde::Serialize;
+
 use crate::mpd::{FromMpd, LineHandled, errors::MpdError};
 
-#[derive(Debug, Clone, Copy, strum::Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, Serialize)]
 #[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum IdleEvent {
     Player,   /* the player has been started, stopped or seeked or tags of
                * the currently playing song have changed (e.g.
@@ -17,3 +17,45 @@ impl FromMpd for MpdList {
         Ok(LineHandled::Yes)
     }
 }
+
+/// Albums listed by `list album group date`, each paired with the date it is
+/// grouped under. The date is empty for albums without one.
+#[derive(Debug, Default)]
+pub struct AlbumList {
+    current_date: String,
+    pub albums: Vec<(String, String)>,
+}
+
+impl FromMpd for AlbumList {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "date" => self.current_date = value,
+            "album" => self.albums.push((value, self.current_date.clone())),
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn album_list_pairs_albums_with_their_group_date() {
+        let mut result = AlbumList::default();
+        for line in
+            ["Album: No date", "Date: 2001", "Album: A", "Album: B", "Date: 2005", "Album: A"]
+        {
+            result.next(line.to_owned()).unwrap();
+        }
+
+        assert_eq!(result.albums, vec![
+            ("No date".to_owned(), String::new()),
+            ("A".to_owned(), "2001".to_owned()),
+            ("B".to_owned(), "2001".to_owned()),
+            ("A".to_owned(), "2005".to_owned()),
+        ]);
+    }
+}
@@ -3,7 +3,7 @@ use std::time::Duration;
 use anyhow::anyhow;
 use serde::Serialize;
 
-use super::Volume;
+use super::{Song, Volume};
 use crate::mpd::{FromMpd, LineHandled, ParseErrorExt, errors::MpdError};
 
 #[derive(Debug, Serialize, Default, Clone)]
@@ -47,6 +47,18 @@ pub struct Status {
     pub lastloadedplaylist: Option<String>, // last loaded stored playlist
 }
 
+impl Status {
+    /// Finds the currently playing or paused song in the given queue together
+    /// with its index.
+    pub fn find_current_song_in<'a>(&self, queue: &'a [Song]) -> Option<(usize, &'a Song)> {
+        if self.state == State::Stop {
+            return None;
+        }
+
+        self.songid.and_then(|id| queue.iter().enumerate().find(|(_, song)| song.id == id))
+    }
+}
+
 impl FromMpd for Status {
     fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
         match key {
@@ -99,7 +111,7 @@ pub enum State {
     Pause,
 }
 
-#[derive(Debug, Serialize, Default, Clone, Copy, strum::AsRefStr)]
+#[derive(Debug, Serialize, Default, PartialEq, Eq, Clone, Copy, strum::AsRefStr)]
 pub enum OnOffOneshot {
     #[strum(serialize = "On")]
     On,
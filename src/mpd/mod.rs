@@ -6,8 +6,10 @@ pub mod errors;
 pub mod mpd_client;
 pub mod proto_client;
 mod queue_position;
+mod single_or_range;
 pub mod version;
 pub use queue_position::QueuePosition;
+pub use single_or_range::SingleOrRange;
 
 pub(crate) trait FromMpd
 where
@@ -1,9 +1,4 @@
-use std::{
-    borrow::Cow,
-    fmt::Write as _,
-    ops::{Range, RangeInclusive},
-    str::FromStr,
-};
+use std::{borrow::Cow, fmt::Write as _, str::FromStr};
 
 use anyhow::Result;
 use derive_more::Deref;
@@ -14,6 +9,7 @@ use strum::{AsRefStr, Display};
 use super::{
     FromMpd,
     QueuePosition,
+    SingleOrRange,
     client::Client,
     commands::{
         IdleEvent,
@@ -26,7 +22,7 @@ use super::{
         Update,
         Volume,
         decoders::Decoders,
-        list::MpdList,
+        list::{AlbumList, MpdList},
         list_playlist::FileList,
         mpd_config::MpdConfig,
         outputs::Outputs,
@@ -43,9 +39,9 @@ use crate::shared::{ext::error::ErrorExt, macros::status_error};
 type MpdResult<T> = Result<T, MpdError>;
 
 #[derive(AsRefStr, Debug)]
-#[allow(dead_code)]
 pub enum SaveMode {
     #[strum(serialize = "create")]
+    #[allow(dead_code)]
     Create,
     #[strum(serialize = "append")]
     Append,
@@ -94,7 +90,9 @@ pub trait MpdClient: Sized {
     fn commands(&mut self) -> MpdResult<MpdList>;
     fn update(&mut self, path: Option<&str>) -> MpdResult<Update>;
     fn rescan(&mut self, path: Option<&str>) -> MpdResult<Update>;
-    fn idle(&mut self, subsystem: Option<IdleEvent>) -> MpdResult<Vec<IdleEvent>>;
+    /// Waits until something changes in any of the subsystems, or in any
+    /// subsystem if none are given
+    fn idle(&mut self, subsystems: &[IdleEvent]) -> MpdResult<Vec<IdleEvent>>;
     fn enter_idle(&mut self) -> MpdResult<ProtoClient<'static, '_, Self>>
     where
         Self: SocketClient;
@@ -141,6 +139,7 @@ pub trait MpdClient: Sized {
     fn search(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>>;
     fn move_in_queue(&mut self, from: SingleOrRange, to: QueuePosition) -> MpdResult<()>;
     fn move_id(&mut self, id: u32, to: QueuePosition) -> MpdResult<()>;
+    fn swap(&mut self, pos1: usize, pos2: usize) -> MpdResult<()>;
     fn find_one(&mut self, filter: &[Filter<'_>]) -> MpdResult<Option<Song>>;
     fn find_add(&mut self, filter: &[Filter<'_>], position: Option<QueuePosition>)
     -> MpdResult<()>;
@@ -149,7 +148,23 @@ pub trait MpdClient: Sized {
         filter: &[Filter<'_>],
         position: Option<QueuePosition>,
     ) -> MpdResult<()>;
+    /// Same as [`Self::find`] but takes a raw MPD filter expression
+    fn find_expression(&mut self, expression: &str) -> MpdResult<Vec<Song>>;
+    /// Same as [`Self::search`] but takes a raw MPD filter expression
+    fn search_expression(&mut self, expression: &str) -> MpdResult<Vec<Song>>;
+    fn find_add_expression(
+        &mut self,
+        expression: &str,
+        position: Option<QueuePosition>,
+    ) -> MpdResult<()>;
+    fn search_add_expression(
+        &mut self,
+        expression: &str,
+        position: Option<QueuePosition>,
+    ) -> MpdResult<()>;
     fn list_tag(&mut self, tag: Tag, filter: Option<&[Filter<'_>]>) -> MpdResult<MpdList>;
+    /// Lists all albums together with their dates.
+    fn list_albums_with_date(&mut self) -> MpdResult<AlbumList>;
     /// Shuffles the current queue.
     fn shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()>;
     // Database
@@ -295,11 +310,11 @@ impl MpdClient for Client<'_> {
     }
 
     // Queries
-    fn idle(&mut self, subsystem: Option<IdleEvent>) -> MpdResult<Vec<IdleEvent>> {
-        if let Some(subsystem) = subsystem {
-            self.send(&format!("idle {subsystem}")).and_then(read_response)
-        } else {
+    fn idle(&mut self, subsystems: &[IdleEvent]) -> MpdResult<Vec<IdleEvent>> {
+        if subsystems.is_empty() {
             self.send("idle").and_then(read_response)
+        } else {
+            self.send(&format!("idle {}", subsystems.iter().join(" "))).and_then(read_response)
         }
     }
 
@@ -507,6 +522,10 @@ impl MpdClient for Client<'_> {
         self.send(&format!("moveid {id} \"{}\"", to.as_mpd_str())).and_then(read_ok)
     }
 
+    fn swap(&mut self, pos1: usize, pos2: usize) -> MpdResult<()> {
+        self.send(&format!("swap {pos1} {pos2}")).and_then(read_ok)
+    }
+
     fn find_one(&mut self, filter: &[Filter<'_>]) -> MpdResult<Option<Song>> {
         let mut songs: Vec<Song> =
             self.send(&format!("find \"({})\"", filter.to_query_str())).and_then(read_response)?;
@@ -541,6 +560,36 @@ impl MpdClient for Client<'_> {
         self.send(&format!("searchadd \"({query})\"{position_arg}")).and_then(read_ok)
     }
 
+    fn find_expression(&mut self, expression: &str) -> MpdResult<Vec<Song>> {
+        self.send(&format!("find {}", expression.quote_and_escape())).and_then(read_response)
+    }
+
+    fn search_expression(&mut self, expression: &str) -> MpdResult<Vec<Song>> {
+        self.send(&format!("search {}", expression.quote_and_escape())).and_then(read_response)
+    }
+
+    fn find_add_expression(
+        &mut self,
+        expression: &str,
+        position: Option<QueuePosition>,
+    ) -> MpdResult<()> {
+        let position_arg: String =
+            position.map_or(String::new(), |v| format!(" position {}", v.as_mpd_str()));
+        self.send(&format!("findadd {}{position_arg}", expression.quote_and_escape()))
+            .and_then(read_ok)
+    }
+
+    fn search_add_expression(
+        &mut self,
+        expression: &str,
+        position: Option<QueuePosition>,
+    ) -> MpdResult<()> {
+        let position_arg: String =
+            position.map_or(String::new(), |v| format!(" position {}", v.as_mpd_str()));
+        self.send(&format!("searchadd {}{position_arg}", expression.quote_and_escape()))
+            .and_then(read_ok)
+    }
+
     fn list_tag(&mut self, tag: Tag, filter: Option<&[Filter<'_>]>) -> MpdResult<MpdList> {
         self.send(&if let Some(filter) = filter {
             format!("list {} \"({})\"", tag.as_str(), filter.to_query_str())
@@ -550,6 +599,10 @@ impl MpdClient for Client<'_> {
         .and_then(read_response)
     }
 
+    fn list_albums_with_date(&mut self) -> MpdResult<AlbumList> {
+        self.send("list album group date").and_then(read_response)
+    }
+
     fn shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()> {
         if let Some(range) = range {
             self.send(&format!("shuffle {}", range.as_mpd_range())).and_then(read_ok)
@@ -922,46 +975,9 @@ impl MpdClient for Client<'_> {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub struct SingleOrRange {
-    pub start: usize,
-    pub end: Option<usize>,
-}
-
-impl From<RangeInclusive<usize>> for SingleOrRange {
-    fn from(value: RangeInclusive<usize>) -> Self {
-        Self::range(*value.start(), value.end() + 1)
-    }
-}
-
-impl From<Range<usize>> for SingleOrRange {
-    fn from(value: Range<usize>) -> Self {
-        Self::range(value.start, value.end)
-    }
-}
-
 #[derive(Deref)]
 pub struct Ranges(Vec<SingleOrRange>);
 
-#[allow(dead_code)]
-impl SingleOrRange {
-    pub fn single(idx: usize) -> Self {
-        Self { start: idx, end: None }
-    }
-
-    pub fn range(start: usize, end: usize) -> Self {
-        Self { start, end: Some(end) }
-    }
-
-    pub fn as_mpd_range(&self) -> String {
-        if let Some(end) = self.end {
-            format!("\"{}:{}\"", self.start, end)
-        } else {
-            format!("\"{}\"", self.start)
-        }
-    }
-}
-
 #[derive(Debug, PartialEq, Eq, Clone, Display)]
 #[strum(serialize_all = "lowercase")]
 #[allow(unused)]
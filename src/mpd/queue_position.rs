@@ -19,6 +19,19 @@ impl QueuePosition {
             QueuePosition::Absolute(v) => format!("{v}"),
         }
     }
+
+    /// Position of an existing song for commands which, unlike `move`, only
+    /// take absolute positions in MPD. +0 is the song right after the current
+    /// one and -0 the one right before it. Relative positions are `None` when
+    /// there is no current song or they would be before the start of the queue.
+    #[must_use]
+    pub fn resolve(self, current: Option<usize>) -> Option<usize> {
+        match self {
+            QueuePosition::RelativeAdd(v) => current.map(|current| current + 1 + v),
+            QueuePosition::RelativeSub(v) => current?.checked_sub(1 + v),
+            QueuePosition::Absolute(v) => Some(v),
+        }
+    }
 }
 
 impl std::str::FromStr for QueuePosition {
@@ -54,4 +67,13 @@ mod tests {
         assert!("0".parse::<QueuePosition>().unwrap() == QueuePosition::Absolute(0));
         assert!("15".parse::<QueuePosition>().unwrap() == QueuePosition::Absolute(15));
     }
+    #[test]
+    fn resolves_relative_positions_against_the_current_song() {
+        assert_eq!(QueuePosition::RelativeAdd(0).resolve(Some(3)), Some(4));
+        assert_eq!(QueuePosition::RelativeAdd(2).resolve(Some(3)), Some(6));
+        assert_eq!(QueuePosition::RelativeSub(0).resolve(Some(3)), Some(2));
+        assert_eq!(QueuePosition::RelativeSub(3).resolve(Some(3)), None);
+        assert_eq!(QueuePosition::RelativeAdd(0).resolve(None), None);
+        assert_eq!(QueuePosition::Absolute(5).resolve(None), Some(5));
+    }
 }
@@ -0,0 +1,76 @@
+use std::ops::{Range, RangeInclusive};
+
+/// Single position or a half open range (`start..end`) of positions in the
+/// queue or a playlist
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SingleOrRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl From<RangeInclusive<usize>> for SingleOrRange {
+    fn from(value: RangeInclusive<usize>) -> Self {
+        Self::range(*value.start(), value.end() + 1)
+    }
+}
+
+impl From<Range<usize>> for SingleOrRange {
+    fn from(value: Range<usize>) -> Self {
+        Self::range(value.start, value.end)
+    }
+}
+
+#[allow(dead_code)]
+impl SingleOrRange {
+    pub fn single(idx: usize) -> Self {
+        Self { start: idx, end: None }
+    }
+
+    pub fn range(start: usize, end: usize) -> Self {
+        Self { start, end: Some(end) }
+    }
+
+    pub fn as_mpd_range(&self) -> String {
+        if let Some(end) = self.end {
+            format!("\"{}:{}\"", self.start, end)
+        } else {
+            format!("\"{}\"", self.start)
+        }
+    }
+}
+
+/// Parses either a single position `<number>` or a range `<start>:<end>` where
+/// the end is exclusive, same as in MPD's protocol.
+impl std::str::FromStr for SingleOrRange {
+    type Err = anyhow::Error;
+
+    fn from_str(v: &str) -> anyhow::Result<Self> {
+        let Some((start, end)) = v.split_once(':') else {
+            return Ok(Self::single(v.trim().parse()?));
+        };
+
+        let start: usize = start.trim().parse()?;
+        let end: usize = end.trim().parse()?;
+        if end <= start {
+            anyhow::bail!("Invalid range '{v}'. End of the range must be greater than its start.");
+        }
+
+        Ok(Self::range(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_single_or_range_fromstr() {
+        assert_eq!("5".parse::<SingleOrRange>().unwrap(), SingleOrRange::single(5));
+        assert_eq!("0:3".parse::<SingleOrRange>().unwrap(), SingleOrRange::range(0, 3));
+        assert!("3:3".parse::<SingleOrRange>().is_err());
+        assert!("4:2".parse::<SingleOrRange>().is_err());
+        assert!(":2".parse::<SingleOrRange>().is_err());
+        assert!("a".parse::<SingleOrRange>().is_err());
+    }
+}
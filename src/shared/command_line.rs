@@ -0,0 +1,527 @@
+use std::path::PathBuf;
+
+use clap::{Arg, Command};
+use itertools::Itertools;
+
+/// Maximum number of commands kept in the command mode history
+const MAX_HISTORY_LEN: usize = 500;
+
+/// Arguments which are completed with values of the MPD tag of the same name
+const TAG_ARGS: [&str; 8] =
+    ["artist", "album", "albumartist", "genre", "title", "composer", "performer", "date"];
+
+/// Subcommands whose path arguments do not point into MPD's music directory
+const NON_MPD_PATH_COMMANDS: [&str; 3] = ["remote", "mount", "index-lrc"];
+
+/// Single argument of a command line
+#[derive(Debug, PartialEq, Eq)]
+pub struct CommandArg {
+    /// Byte offset of the argument's first character, including the quote
+    pub start: usize,
+    /// Byte offset right after the argument's last character
+    pub end: usize,
+    /// The argument without quotes and escapes
+    pub value: String,
+}
+
+/// Splits the command line into arguments by whitespace. Arguments can be
+/// quoted with single or double quotes and characters outside of single quotes
+/// escaped with a backslash, ie. `add "Some Artist/Some Album"`.
+pub fn split_args(input: &str) -> Vec<CommandArg> {
+    let mut result = Vec::new();
+    let mut current: Option<CommandArg> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        if quote.is_none() && c.is_whitespace() {
+            if let Some(mut arg) = current.take() {
+                arg.end = idx;
+                result.push(arg);
+            }
+            continue;
+        }
+
+        let arg = current.get_or_insert_with(|| CommandArg {
+            start: idx,
+            end: idx,
+            value: String::new(),
+        });
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('\\', None | Some('"')) => {
+                if let Some((_, escaped)) = chars.next() {
+                    arg.value.push(escaped);
+                }
+            }
+            (c, _) => arg.value.push(c),
+        }
+    }
+
+    if let Some(mut arg) = current {
+        arg.end = input.len();
+        result.push(arg);
+    }
+
+    result
+}
+
+/// Quotes the value if needed so that [`split_args`] reads it back as a single
+/// argument.
+pub fn quote_arg(value: &str) -> String {
+    if !value.is_empty()
+        && !value.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'))
+    {
+        return value.to_owned();
+    }
+
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result.push('"');
+    result
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompletionSource {
+    /// Values known upfront, ie. subcommand names
+    Values(Vec<String>),
+    /// Paths relative to MPD's music directory
+    MpdPath,
+    Playlist,
+    Tab,
+    /// Values of the given MPD tag
+    TagValue(String),
+}
+
+/// What should be completed for the argument under cursor, which is always the
+/// last one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompletionRequest {
+    /// Byte offset where the completed argument starts
+    pub start: usize,
+    /// Already typed part of the argument
+    pub prefix: String,
+    pub source: CompletionSource,
+}
+
+impl CompletionRequest {
+    /// Keeps only the values which start with the typed prefix, sorted
+    pub fn filter(&self, values: impl IntoIterator<Item = String>) -> Vec<String> {
+        values.into_iter().filter(|value| value.starts_with(&self.prefix)).sorted().collect()
+    }
+}
+
+/// Position in the clap command tree reached after reading the arguments before
+/// the cursor
+struct ParsedCommand<'a> {
+    cmd: &'a Command,
+    /// Names of all subcommands leading to `cmd`
+    path: Vec<&'a str>,
+    positional_idx: usize,
+    /// Option which is still missing its value
+    pending_option: Option<&'a Arg>,
+}
+
+fn parse_command<'a>(root: &'a Command, args: &[CommandArg]) -> ParsedCommand<'a> {
+    let mut parsed =
+        ParsedCommand { cmd: root, path: Vec::new(), positional_idx: 0, pending_option: None };
+
+    for arg in args {
+        let value = arg.value.as_str();
+        if parsed.pending_option.take().is_some() {
+            continue;
+        }
+
+        if let Some(long) = value.strip_prefix("--") {
+            parsed.pending_option = parsed
+                .cmd
+                .get_arguments()
+                .find(|a| !long.contains('=') && a.get_long() == Some(long))
+                .filter(|a| a.get_action().takes_values());
+        } else if value.len() > 1
+            && value.starts_with('-')
+            && !value[1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            parsed.pending_option = value.chars().last().and_then(|short| {
+                parsed
+                    .cmd
+                    .get_arguments()
+                    .find(|a| a.get_short() == Some(short))
+                    .filter(|a| a.get_action().takes_values())
+            });
+        } else if let Some(sub) =
+            parsed.cmd.find_subcommand(value).filter(|_| parsed.positional_idx == 0)
+        {
+            parsed.path.push(sub.get_name());
+            parsed.cmd = sub;
+        } else {
+            parsed.positional_idx += 1;
+        }
+    }
+
+    parsed
+}
+
+/// Returns the deepest subcommand fully typed in the input
+pub fn typed_subcommand<'a>(root: &'a Command, input: &str) -> Option<&'a Command> {
+    let parsed = parse_command(root, &split_args(input));
+    (!parsed.path.is_empty()).then_some(parsed.cmd)
+}
+
+/// Finds out what should be completed at the end of the input. `root` has to
+/// be already built so that all of its arguments are known.
+pub fn completion_request(root: &Command, input: &str) -> CompletionRequest {
+    let mut args = split_args(input);
+    let current = match args.last() {
+        Some(last) if last.end == input.len() => args.pop(),
+        _ => None,
+    }
+    .unwrap_or(CommandArg { start: input.len(), end: input.len(), value: String::new() });
+
+    let parsed = parse_command(root, &args);
+    let source = if let Some(option) = parsed.pending_option {
+        arg_source(option, &parsed.path)
+    } else if current.value.starts_with('-') && !parsed.path.is_empty() {
+        CompletionSource::Values(
+            parsed
+                .cmd
+                .get_arguments()
+                .filter(|a| !a.is_hide_set())
+                .filter_map(|a| a.get_long())
+                .map(|long| format!("--{long}"))
+                .collect(),
+        )
+    } else if parsed.cmd.has_subcommands() && parsed.positional_idx == 0 {
+        CompletionSource::Values(
+            parsed
+                .cmd
+                .get_subcommands()
+                .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+                .map(|sub| sub.get_name().to_owned())
+                .collect(),
+        )
+    } else {
+        let positionals = parsed.cmd.get_positionals().collect_vec();
+        let positional = positionals.get(parsed.positional_idx).or_else(|| {
+            positionals.last().filter(|a| a.get_num_args().is_some_and(|n| n.max_values() > 1))
+        });
+        positional.map_or(CompletionSource::Values(Vec::new()), |a| arg_source(a, &parsed.path))
+    };
+
+    CompletionRequest { start: current.start, prefix: current.value, source }
+}
+
+fn arg_source(arg: &Arg, path: &[&str]) -> CompletionSource {
+    let possible_values = arg.get_possible_values();
+    if !possible_values.is_empty() {
+        return CompletionSource::Values(
+            possible_values
+                .into_iter()
+                .filter(|value| !value.is_hide_set())
+                .map(|value| value.get_name().to_owned())
+                .collect(),
+        );
+    }
+
+    match arg.get_id().as_str() {
+        "files" | "file" | "path" | "uri"
+            if path.first().is_none_or(|cmd| !NON_MPD_PATH_COMMANDS.contains(cmd)) =>
+        {
+            CompletionSource::MpdPath
+        }
+        "playlist" => CompletionSource::Playlist,
+        "tab" => CompletionSource::Tab,
+        id if TAG_ARGS.contains(&id) => CompletionSource::TagValue(id.to_owned()),
+        _ => CompletionSource::Values(Vec::new()),
+    }
+}
+
+/// Previously executed commands, optionally persisted in a file
+#[derive(Debug, Default)]
+pub struct CommandHistory {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+    /// Index of the entry currently shown, `None` when editing a new command
+    position: Option<usize>,
+    /// Command which was being typed before browsing the history
+    draft: String,
+}
+
+impl CommandHistory {
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| match std::fs::read_to_string(path) {
+                Ok(content) => Some(content.lines().map(ToOwned::to_owned).collect()),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => {
+                    log::warn!(err:?, path:?; "Failed to read command history");
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self { path, entries, position: None, draft: String::new() }
+    }
+
+    /// Adds the command as the newest entry and saves the history
+    pub fn push(&mut self, command: &str) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != command);
+        self.entries.push(command.to_owned());
+        if self.entries.len() > MAX_HISTORY_LEN {
+            self.entries.drain(..self.entries.len() - MAX_HISTORY_LEN);
+        }
+        self.position = None;
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, self.entries.iter().join("\n")));
+        if let Err(err) = result {
+            log::warn!(err:?, path:?; "Failed to save command history");
+        }
+    }
+
+    /// Moves to an older entry. The currently typed command is kept so that it
+    /// can be restored by [`CommandHistory::next`].
+    pub fn prev(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                current.clone_into(&mut self.draft);
+                self.entries.len() - 1
+            }
+            Some(position) => position.saturating_sub(1),
+        };
+        self.position = Some(position);
+        self.entries.get(position).map(String::as_str)
+    }
+
+    /// Moves to a newer entry, or back to the command typed before browsing
+    pub fn next(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            self.entries.get(position + 1).map(String::as_str)
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Stops browsing the history, ie. after the command was edited
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{Arg, ArgAction, Command};
+
+    use super::*;
+
+    fn values(args: Vec<CommandArg>) -> Vec<String> {
+        args.into_iter().map(|arg| arg.value).collect()
+    }
+
+    fn cli() -> Command {
+        let mut cmd = Command::new("rmpc")
+            .subcommand(Command::new("volume").arg(Arg::new("value")))
+            .subcommand(
+                Command::new("add")
+                    .arg(Arg::new("files").num_args(0..))
+                    .arg(Arg::new("position").short('p').long("position")),
+            )
+            .subcommand(Command::new("repeat").arg(Arg::new("value").value_parser(["on", "off"])))
+            .subcommand(
+                Command::new("playlist").subcommand(
+                    Command::new("add")
+                        .arg(Arg::new("playlist"))
+                        .arg(Arg::new("files").action(ArgAction::Append)),
+                ),
+            )
+            .subcommand(Command::new("find").arg(Arg::new("artist").long("artist")))
+            .subcommand(
+                Command::new("remote").subcommand(Command::new("set").arg(Arg::new("path"))),
+            )
+            .subcommand(Command::new("hidden").hide(true));
+        cmd.build();
+        cmd
+    }
+
+    mod split_args {
+        use super::*;
+
+        #[test]
+        fn splits_by_whitespace() {
+            assert_eq!(values(split_args("  add  a/b.mp3 c ")), ["add", "a/b.mp3", "c"]);
+        }
+
+        #[test]
+        fn handles_quotes_and_escapes() {
+            assert_eq!(values(split_args(r#"add "a b/c" 'd "e"' f\ g"#)), [
+                "add", "a b/c", r#"d "e""#, "f g"
+            ]);
+        }
+
+        #[test]
+        fn unterminated_quote_reaches_end() {
+            let args = split_args(r#"add "Some Art"#);
+
+            assert_eq!(args[1], CommandArg { start: 4, end: 13, value: "Some Art".to_owned() });
+        }
+
+        #[test]
+        fn quote_arg_roundtrips() {
+            for value in ["simple", "with space", r#"quote"s"#, r"back\slash", ""] {
+                assert_eq!(values(split_args(&quote_arg(value))), [value]);
+            }
+        }
+    }
+
+    mod completion_request {
+        use super::*;
+
+        fn source(input: &str) -> CompletionSource {
+            completion_request(&cli(), input).source
+        }
+
+        #[test]
+        fn completes_subcommands() {
+            let request = completion_request(&cli(), "vo");
+
+            assert_eq!(request.start, 0);
+            assert_eq!(request.prefix, "vo");
+            assert_eq!(
+                request.filter(match request.source {
+                    CompletionSource::Values(ref values) => values.clone(),
+                    _ => Vec::new(),
+                }),
+                ["volume"]
+            );
+        }
+
+        #[test]
+        fn skips_hidden_subcommands() {
+            let CompletionSource::Values(values) = source("") else {
+                panic!("Expected values");
+            };
+
+            assert!(!values.contains(&"hidden".to_owned()));
+            assert!(!values.contains(&"help".to_owned()));
+        }
+
+        #[test]
+        fn completes_nested_subcommands() {
+            assert_eq!(source("playlist a"), CompletionSource::Values(vec!["add".to_owned()]));
+        }
+
+        #[test]
+        fn completes_possible_values() {
+            assert_eq!(
+                source("repeat "),
+                CompletionSource::Values(vec!["on".to_owned(), "off".to_owned()])
+            );
+        }
+
+        #[test]
+        fn completes_mpd_paths() {
+            let request = completion_request(&cli(), r#"add a.mp3 "Some Art"#);
+
+            assert_eq!(request.start, 10);
+            assert_eq!(request.prefix, "Some Art");
+            assert_eq!(request.source, CompletionSource::MpdPath);
+        }
+
+        #[test]
+        fn skips_option_values() {
+            assert_eq!(source("add -p 1 "), CompletionSource::MpdPath);
+            assert_eq!(source("add --position "), CompletionSource::Values(Vec::new()));
+        }
+
+        #[test]
+        fn completes_options() {
+            let CompletionSource::Values(values) = source("add --") else {
+                panic!("Expected values");
+            };
+
+            assert!(values.contains(&"--position".to_owned()));
+        }
+
+        #[test]
+        fn completes_by_argument_name() {
+            assert_eq!(source("playlist add "), CompletionSource::Playlist);
+            assert_eq!(source("playlist add name "), CompletionSource::MpdPath);
+            assert_eq!(source("find --artist "), CompletionSource::TagValue("artist".to_owned()));
+            assert_eq!(source("remote set "), CompletionSource::Values(Vec::new()));
+        }
+
+        #[test]
+        fn finds_typed_subcommand() {
+            let cli = cli();
+
+            assert_eq!(typed_subcommand(&cli, "playlist add x").map(|c| c.get_name()), Some("add"));
+            assert_eq!(typed_subcommand(&cli, "vol").map(|c| c.get_name()), None);
+        }
+
+        #[test]
+        fn completes_rmpc_cli() {
+            let mut cli = <crate::config::cli::Args as clap::CommandFactory>::command();
+            cli.build();
+
+            assert_eq!(completion_request(&cli, "add ").source, CompletionSource::MpdPath);
+            assert_eq!(
+                completion_request(&cli, "index-lrc ").source,
+                CompletionSource::Values(Vec::new())
+            );
+            assert_eq!(
+                completion_request(&cli, "repeat ").source,
+                CompletionSource::Values(vec!["on".to_owned(), "off".to_owned()])
+            );
+        }
+    }
+
+    mod history {
+        use super::*;
+
+        #[test]
+        fn browses_entries_and_restores_draft() {
+            let mut history = CommandHistory::default();
+            history.push("next");
+            history.push("volume +5");
+
+            assert_eq!(history.prev("vol"), Some("volume +5"));
+            assert_eq!(history.prev("volume +5"), Some("next"));
+            assert_eq!(history.prev("next"), Some("next"));
+            assert_eq!(history.next(), Some("volume +5"));
+            assert_eq!(history.next(), Some("vol"));
+            assert_eq!(history.next(), None);
+        }
+
+        #[test]
+        fn moves_repeated_command_to_the_end() {
+            let mut history = CommandHistory::default();
+            history.push("next");
+            history.push("prev");
+            history.push(" next ");
+            history.push("  ");
+
+            assert_eq!(history.entries, ["prev", "next"]);
+        }
+    }
+}
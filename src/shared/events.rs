@@ -1,16 +1,26 @@
 use std::{path::PathBuf, time::Duration};
 
 use anyhow::Result;
+use crossbeam::channel::Sender;
 use crossterm::event::KeyEvent;
 use serde::{Deserialize, Serialize};
 
 use super::{
+    ipc::{GetIpcCommand, Subscriber},
     lrc::{LrcIndex, LrcIndexEntry},
     mouse_event::MouseEvent,
     mpd_query::{MpdCommand, MpdQuery, MpdQueryResult, MpdQuerySync},
 };
 use crate::{
-    config::{Config, Size, cli::Command, tabs::PaneType, theme::UiConfig},
+    config::{
+        Config,
+        Size,
+        cli::Command,
+        keys::ChainStep,
+        tabs::PaneType,
+        theme::UiConfig,
+        theme_mode::ThemeMode,
+    },
     mpd::commands::IdleEvent,
     ui::UiAppEvent,
 };
@@ -51,6 +61,11 @@ pub(crate) enum WorkDone {
 #[derive(Debug)]
 pub(crate) enum AppEvent {
     UserKeyInput(KeyEvent),
+    /// Waiting for continuation of a pending key sequence timed out
+    KeySequenceTimeout,
+    /// Runs the action in the focused pane as if its keybind was pressed, ie.
+    /// after it was picked from the command palette
+    RunAction(ChainStep),
     UserMouseInput(MouseEvent),
     Status(String, Level, Duration),
     InfoModal {
@@ -84,6 +99,27 @@ pub(crate) enum AppEvent {
     ThemeChanged {
         theme: Box<UiConfig>,
     },
+    /// Terminal reported its background color at startup or when it gained
+    /// focus
+    TerminalBackgroundDetected(ThemeMode),
+    /// Mode forced by `rmpc remote set theme-mode`, `None` follows the
+    /// terminal's background again
+    ThemeModeForced(Option<ThemeMode>),
+    /// `rmpc daemon` received SIGTERM or SIGINT and should shut down
+    Terminate,
+    /// Query from `rmpc remote get`, answered through the sender
+    RemoteQuery {
+        query: GetIpcCommand,
+        tx: Sender<Result<serde_json::Value>>,
+    },
+    /// Client of `rmpc remote subscribe` which should receive events
+    RemoteSubscribe(Subscriber),
+    /// Lyrics pane highlighted a new line
+    LyricsLineChanged {
+        index: usize,
+        time: Duration,
+        line: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Copy, Eq, Hash, PartialEq)]
@@ -0,0 +1,88 @@
+/// Result of a successful [`fuzzy_match`]
+#[derive(Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better
+    pub score: i64,
+    /// Indices of the matched characters in the text, in chars not bytes
+    pub indices: Vec<usize>,
+}
+
+const MATCH_SCORE: i64 = 1;
+const CONSECUTIVE_BONUS: i64 = 5;
+const WORD_START_BONUS: i64 = 8;
+
+/// Matches the pattern against the text case insensitively. All characters of
+/// the pattern have to be present in the text in the same order, but not
+/// necessarily next to each other. Consecutive characters and characters at
+/// the start of words score higher. Whitespace in the pattern is ignored.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let text = text.chars().collect::<Vec<_>>();
+    let mut indices = Vec::new();
+    let mut score = 0;
+    let mut text_idx = 0;
+
+    for pattern_char in pattern.chars().filter(|c| !c.is_whitespace()) {
+        let offset = text[text_idx..].iter().position(|c| chars_eq(*c, pattern_char))?;
+        let idx = text_idx + offset;
+
+        score += MATCH_SCORE;
+        if indices.last().is_some_and(|last| last + 1 == idx) {
+            score += CONSECUTIVE_BONUS;
+        }
+        if is_word_start(&text, idx) {
+            score += WORD_START_BONUS;
+        }
+
+        indices.push(idx);
+        text_idx = idx + 1;
+    }
+
+    // Prefer matches closer to the start of the text
+    let leading_gap = indices.first().copied().unwrap_or_default();
+    score -= i64::try_from(leading_gap).unwrap_or(i64::MAX).min(10);
+
+    Some(FuzzyMatch { score, indices })
+}
+
+fn chars_eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn is_word_start(text: &[char], idx: usize) -> bool {
+    let Some(prev) = idx.checked_sub(1).and_then(|prev| text.get(prev)) else {
+        return true;
+    };
+    !prev.is_alphanumeric() || (prev.is_lowercase() && text[idx].is_uppercase())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequence_case_insensitively() {
+        let result = fuzzy_match("tgp", "Toggle pause").unwrap();
+
+        assert_eq!(result.indices, [0, 2, 7]);
+    }
+
+    #[test]
+    fn rejects_missing_characters() {
+        assert_eq!(fuzzy_match("xyz", "Toggle pause"), None);
+        assert_eq!(fuzzy_match("ab", "ba"), None);
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        assert_eq!(fuzzy_match("", "Anything"), Some(FuzzyMatch { score: 0, indices: vec![] }));
+    }
+
+    #[test]
+    fn prefers_word_starts_and_consecutive_characters() {
+        let word_start = fuzzy_match("vol", "Raise volume").unwrap();
+        let scattered = fuzzy_match("vol", "Save over all").unwrap();
+
+        assert!(word_start.score > scattered.score);
+    }
+}
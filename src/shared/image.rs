@@ -1,6 +1,6 @@
-use std::{env, io::Cursor};
+use std::{env, io::Cursor, path::Path};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use image::{
     AnimationDecoder,
     DynamicImage,
@@ -10,7 +10,7 @@ use image::{
 use ratatui::layout::Rect;
 use rustix::path::Arg;
 
-use super::dependencies::UEBERZUGPP;
+use super::{dependencies::UEBERZUGPP, terminal};
 use crate::config::{
     Size,
     album_art::{HorizontalAlign, VerticalAlign},
@@ -84,36 +84,7 @@ pub fn query_device_attrs(is_tmux: bool) -> Result<ImageProtocol> {
         "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1b[c"
     };
 
-    let stdin = rustix::stdio::stdin();
-    let termios_orig = rustix::termios::tcgetattr(stdin)?;
-    let mut termios = termios_orig.clone();
-
-    termios.local_modes &= !rustix::termios::LocalModes::ICANON;
-    termios.local_modes &= !rustix::termios::LocalModes::ECHO;
-    // Set read timeout to 100ms as we cannot reliably check for end of terminal
-    // response
-    termios.special_codes[rustix::termios::SpecialCodeIndex::VTIME] = 1;
-    // Set read minimum to 0
-    termios.special_codes[rustix::termios::SpecialCodeIndex::VMIN] = 0;
-
-    rustix::termios::tcsetattr(stdin, rustix::termios::OptionalActions::Drain, &termios)?;
-
-    rustix::io::write(rustix::stdio::stdout(), query.as_bytes())?;
-
-    let mut buf = String::new();
-    loop {
-        let mut charbuffer = [0; 1];
-        rustix::io::read(stdin, &mut charbuffer)?;
-
-        buf.push(charbuffer[0].into());
-
-        if charbuffer[0] == b'\0' || buf.ends_with(";c") {
-            break;
-        }
-    }
-
-    // Reset to previous attrs
-    rustix::termios::tcsetattr(stdin, rustix::termios::OptionalActions::Now, &termios_orig)?;
+    let buf = terminal::query(query.as_bytes(), |buf| buf.ends_with(";c"))?;
 
     log::debug!(buf:?; "devattr response");
 
@@ -135,37 +106,7 @@ pub fn is_ueberzug_x11_supported() -> bool {
 
 #[allow(dead_code)]
 pub fn read_size_csi() -> Result<Option<(u16, u16)>> {
-    let stdin = rustix::stdio::stdin();
-    let termios_orig = rustix::termios::tcgetattr(stdin)?;
-    let mut termios = termios_orig.clone();
-
-    termios.local_modes &= !rustix::termios::LocalModes::ICANON;
-    termios.local_modes &= !rustix::termios::LocalModes::ECHO;
-    // Set read timeout to 100ms as we cannot reliably check for end of terminal
-    // response
-    termios.special_codes[rustix::termios::SpecialCodeIndex::VTIME] = 1;
-    // Set read minimum to 0
-    termios.special_codes[rustix::termios::SpecialCodeIndex::VMIN] = 0;
-
-    rustix::termios::tcsetattr(stdin, rustix::termios::OptionalActions::Drain, &termios)?;
-
-    let stdout = rustix::stdio::stdout();
-    rustix::io::write(stdout, b"\x1b[14t")?;
-
-    let mut buf = String::new();
-    loop {
-        let mut charbuffer = [0; 1];
-        rustix::io::read(stdin, &mut charbuffer)?;
-
-        buf.push(charbuffer[0].into());
-
-        if charbuffer[0] == b'\0' || buf.ends_with('t') {
-            break;
-        }
-    }
-
-    // Reset to previous attrs
-    rustix::termios::tcsetattr(stdin, rustix::termios::OptionalActions::Now, &termios_orig)?;
+    let buf = terminal::query(b"\x1b[14t", |buf| buf.ends_with('t'))?;
 
     let Some(buf) = buf.strip_prefix("\u{1b}[4;") else {
         return Ok(None);
@@ -304,6 +245,39 @@ pub fn jpg_encode(img: &DynamicImage) -> Result<Vec<u8>> {
     Ok(jpg)
 }
 
+/// Writes album art to `dir` for other applications and returns its `file://`
+/// URL. The directory is shared by all running instances, so only the art
+/// previously written by this instance is removed. Each song should still get
+/// its own `name` so that clients caching the art by its URL notice the change.
+pub fn write_album_art_file(dir: &Path, name: &str, data: &[u8]) -> Result<String> {
+    let prefix = format!("{}_", std::process::id());
+    match std::fs::read_dir(dir) {
+        Ok(entries) => {
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                    std::fs::remove_file(entry.path())?;
+                }
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            std::fs::create_dir_all(dir)?;
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    let extension = image::guess_format(data)
+        .ok()
+        .and_then(|format| format.extensions_str().first().copied())
+        .unwrap_or("jpg");
+    let path = dir.join(format!("{prefix}{name}.{extension}"));
+    std::fs::write(&path, data)?;
+
+    Ok(url::Url::from_file_path(&path)
+        .map_err(|()| anyhow!("Invalid album art path '{}'", path.display()))?
+        .to_string())
+}
+
 pub struct GifData<'frames> {
     pub frames: image::Frames<'frames>,
     pub dimensions: (u32, u32),
@@ -322,3 +296,32 @@ pub fn get_gif_frames(data: &[u8]) -> Result<Option<GifData<'_>>> {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::write_album_art_file;
+
+    #[test]
+    fn album_art_file_replaces_only_own_art() {
+        let dir = std::env::temp_dir().join(format!("rmpc-album-art-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let other_instance = dir.join("1_cover_5.jpg");
+        std::fs::write(&other_instance, b"other").unwrap();
+
+        write_album_art_file(&dir, "cover_1", b"first").unwrap();
+        let url = write_album_art_file(&dir, "cover_2", b"second").unwrap();
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        files.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let own = format!("{}_cover_2.jpg", std::process::id());
+        let mut expected = vec!["1_cover_5.jpg".to_owned(), own.clone()];
+        expected.sort();
+        assert_eq!(files, expected);
+        assert!(url.ends_with(&own), "{url}");
+    }
+}
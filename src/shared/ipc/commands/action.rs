@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use crossbeam::channel::Sender;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppEvent,
+    WorkRequest,
+    config::{Config, keys::actions::ChainStepFile},
+    shared::ipc::SocketCommandExecute,
+};
+
+/// Action run by `rmpc remote action` as if its keybind was pressed
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct ActionIpcCommand {
+    pub(crate) action: ChainStepFile,
+}
+
+impl ActionIpcCommand {
+    /// Parses the action the same way as in the keybinds config. Arguments are
+    /// passed to the action as strings, ie. `SwitchToTab Queue` is read as
+    /// `SwitchToTab("Queue")`.
+    pub(crate) fn parse(action: &str, args: &[String]) -> Result<Self> {
+        let input = if args.is_empty() {
+            action.to_owned()
+        } else {
+            let args: Vec<String> = args.iter().map(ron::to_string).try_collect()?;
+            format!("{action}({})", args.join(", "))
+        };
+
+        let action = ron::from_str(&input).with_context(|| format!("Invalid action '{input}'"))?;
+        Ok(Self { action })
+    }
+}
+
+impl SocketCommandExecute for ActionIpcCommand {
+    fn execute(
+        self,
+        event_tx: &Sender<AppEvent>,
+        _work_tx: &Sender<WorkRequest>,
+        _config: &Config,
+    ) -> Result<()> {
+        event_tx.send(AppEvent::RunAction(self.action.into()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::config::keys::{
+        GlobalAction,
+        actions::{CommonActionFile, GlobalActionFile, QueueActionsFile},
+    };
+
+    #[test]
+    fn parses_action_in_all_sections() {
+        let cmd = ActionIpcCommand::parse("Delete", &[]).unwrap();
+
+        assert_eq!(cmd.action, ChainStepFile {
+            navigation: Some(CommonActionFile::Delete),
+            queue: Some(QueueActionsFile::Delete),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn passes_arguments_as_strings() {
+        let cmd = ActionIpcCommand::parse("SwitchToTab", &["Album \"Art\"".to_owned()]).unwrap();
+
+        assert_eq!(
+            cmd.action.global,
+            Some(GlobalActionFile::SwitchToTab("Album \"Art\"".to_owned()))
+        );
+    }
+
+    #[test]
+    fn survives_socket_roundtrip() {
+        let cmd = ActionIpcCommand::parse("SwitchToTab", &["Queue".to_owned()]).unwrap();
+
+        let json = serde_json::to_string(&cmd).unwrap();
+        let cmd: ActionIpcCommand = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            crate::config::keys::ChainStep::from(cmd.action).global,
+            Some(GlobalAction::SwitchToTab("Queue".into()))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_action() {
+        assert!(ActionIpcCommand::parse("NotAnAction", &[]).is_err());
+    }
+}
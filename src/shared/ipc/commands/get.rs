@@ -0,0 +1,54 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::{Context, Result};
+use crossbeam::channel::{Sender, bounded};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppEvent, config::cli::RemoteGet};
+
+/// How long to wait for the UI to answer before giving up
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Value requested by `rmpc remote get`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GetIpcCommand {
+    Tab,
+    Pane,
+    Selected,
+    Marked,
+    Filter,
+    ConfigPath,
+    Theme,
+}
+
+impl From<RemoteGet> for GetIpcCommand {
+    fn from(value: RemoteGet) -> Self {
+        match value {
+            RemoteGet::Tab => GetIpcCommand::Tab,
+            RemoteGet::Pane => GetIpcCommand::Pane,
+            RemoteGet::Selected => GetIpcCommand::Selected,
+            RemoteGet::Marked => GetIpcCommand::Marked,
+            RemoteGet::Filter => GetIpcCommand::Filter,
+            RemoteGet::ConfigPath => GetIpcCommand::ConfigPath,
+            RemoteGet::Theme => GetIpcCommand::Theme,
+        }
+    }
+}
+
+impl GetIpcCommand {
+    /// Answers the query. Everything except the config path lives in the UI
+    /// so the query is passed to the main loop and its answer awaited.
+    pub(crate) fn query(
+        self,
+        event_tx: &Sender<AppEvent>,
+        config_path: Option<&Path>,
+    ) -> Result<serde_json::Value> {
+        if self == GetIpcCommand::ConfigPath {
+            return Ok(serde_json::to_value(config_path)?);
+        }
+
+        let (tx, rx) = bounded(1);
+        event_tx.send(AppEvent::RemoteQuery { query: self, tx })?;
+        rx.recv_timeout(QUERY_TIMEOUT).context("rmpc did not answer the query in time")?
+    }
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+use crossbeam::channel::Sender;
+use crossterm::event::KeyEvent;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppEvent,
+    WorkRequest,
+    config::{Config, keys::KeySequence},
+    shared::ipc::SocketCommandExecute,
+};
+
+/// Keys pressed by `rmpc remote keys`
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct KeysIpcCommand {
+    pub(crate) keys: KeySequence,
+}
+
+impl SocketCommandExecute for KeysIpcCommand {
+    fn execute(
+        self,
+        event_tx: &Sender<AppEvent>,
+        _work_tx: &Sender<WorkRequest>,
+        _config: &Config,
+    ) -> Result<()> {
+        for key in self.keys.0 {
+            event_tx.send(AppEvent::UserKeyInput(KeyEvent::new(key.key, key.modifiers)))?;
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,28 @@
 use std::{path::PathBuf, time::Duration};
 
-use anyhow::{Context, Result};
+use action::ActionIpcCommand;
+use anyhow::{Context, Result, anyhow};
 use index_lrc::IndexLrcCommand;
+use keys::KeysIpcCommand;
 use set::SetIpcCommand;
 use status_message::StatusMessageCommand;
+use subscribe::SubscribeIpcCommand;
 use tmux::TmuxHookCommand;
 
 use super::SocketCommand;
 use crate::config::{
     ConfigFile,
-    cli::{RemoteCmd, SetCommand},
+    cli::{RemoteCmd, SetCommand, ThemeModeOrAuto},
+    theme_mode::ThemeMode,
 };
 
+pub(super) mod action;
+pub(super) mod get;
 pub(super) mod index_lrc;
+pub(super) mod keys;
 pub(super) mod set;
 pub(super) mod status_message;
+pub(super) mod subscribe;
 pub(super) mod tmux;
 
 impl TryFrom<RemoteCmd> for SocketCommand {
@@ -39,6 +47,17 @@ impl TryFrom<RemoteCmd> for SocketCommand {
                 }))
             }
             RemoteCmd::Tmux { hook } => Ok(SocketCommand::TmuxHook(TmuxHookCommand { hook })),
+            RemoteCmd::Action { action, args } => {
+                Ok(SocketCommand::Action(ActionIpcCommand::parse(&action, &args)?))
+            }
+            RemoteCmd::Keys { keys } => Ok(SocketCommand::Keys(KeysIpcCommand {
+                keys: keys.parse().map_err(|err| anyhow!("Invalid keys '{keys}': {err}"))?,
+            })),
+            RemoteCmd::List => Err(anyhow!("List command is not sent to instances")),
+            RemoteCmd::Get { what } => Ok(SocketCommand::Get(what.into())),
+            RemoteCmd::Subscribe { events } => Ok(SocketCommand::Subscribe(SubscribeIpcCommand {
+                events: events.into_iter().map(Into::into).collect(),
+            })),
             RemoteCmd::Set { command: SetCommand::Config { path } } if path == "-" => {
                 Ok(SocketCommand::Set(Box::new(SetIpcCommand::Config(ron::de::from_reader(
                     std::io::stdin(),
@@ -54,6 +73,13 @@ impl TryFrom<RemoteCmd> for SocketCommand {
                     std::io::stdin(),
                 )?))))
             }
+            RemoteCmd::Set { command: SetCommand::ThemeMode { mode } } => {
+                Ok(SocketCommand::Set(Box::new(SetIpcCommand::ThemeMode(match mode {
+                    ThemeModeOrAuto::Light => Some(ThemeMode::Light),
+                    ThemeModeOrAuto::Dark => Some(ThemeMode::Dark),
+                    ThemeModeOrAuto::Auto => None,
+                }))))
+            }
             RemoteCmd::Set { command: SetCommand::Theme { path } } => {
                 let pathbuf = PathBuf::from(&path);
                 let file = std::fs::File::open(&pathbuf)
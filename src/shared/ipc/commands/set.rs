@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     AppEvent,
     WorkRequest,
-    config::{Config, ConfigFile, theme::UiConfigFile},
+    config::{Config, ConfigFile, theme::UiConfigFile, theme_mode::ThemeMode},
     shared::ipc::SocketCommandExecute,
 };
 
@@ -16,6 +16,8 @@ use crate::{
 pub(crate) enum SetIpcCommand {
     Config(ConfigFile),
     Theme(UiConfigFile),
+    /// `None` follows the terminal's background again
+    ThemeMode(Option<ThemeMode>),
 }
 
 impl SocketCommandExecute for SetIpcCommand {
@@ -34,6 +36,7 @@ impl SocketCommandExecute for SetIpcCommand {
                 let theme = Box::new(theme.try_into()?);
                 Ok(event_tx.send(AppEvent::ThemeChanged { theme })?)
             }
+            SetIpcCommand::ThemeMode(mode) => Ok(event_tx.send(AppEvent::ThemeModeForced(mode))?),
         }
     }
 }
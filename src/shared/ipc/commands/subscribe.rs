@@ -0,0 +1,186 @@
+use std::{io::Write, os::unix::net::UnixStream, time::Duration};
+
+use anyhow::Result;
+use crossbeam::channel::{Sender, unbounded};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppEvent, config::cli::SubscribeEvent, mpd::commands::Song, shared::events::Level};
+
+/// Subscription to events of a running instance requested by `rmpc remote
+/// subscribe`. Empty list of events means all events.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct SubscribeIpcCommand {
+    pub(crate) events: Vec<RemoteEventKind>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RemoteEventKind {
+    SongChange,
+    TabChange,
+    PaneFocus,
+    Status,
+    LyricsLine,
+    ConfigReload,
+}
+
+impl From<SubscribeEvent> for RemoteEventKind {
+    fn from(value: SubscribeEvent) -> Self {
+        match value {
+            SubscribeEvent::SongChange => RemoteEventKind::SongChange,
+            SubscribeEvent::TabChange => RemoteEventKind::TabChange,
+            SubscribeEvent::PaneFocus => RemoteEventKind::PaneFocus,
+            SubscribeEvent::Status => RemoteEventKind::Status,
+            SubscribeEvent::LyricsLine => RemoteEventKind::LyricsLine,
+            SubscribeEvent::ConfigReload => RemoteEventKind::ConfigReload,
+        }
+    }
+}
+
+/// Event streamed to subscribers, serialized as a single JSON line
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum RemoteEvent {
+    SongChange { song: Option<Song> },
+    TabChange { tab: String },
+    PaneFocus { pane: Option<String> },
+    Status { message: String, level: Level },
+    LyricsLine { index: usize, time_ms: u128, line: String },
+    ConfigReload,
+}
+
+impl RemoteEvent {
+    pub(crate) fn lyrics_line(index: usize, time: Duration, line: String) -> Self {
+        RemoteEvent::LyricsLine { index, time_ms: time.as_millis(), line }
+    }
+
+    fn kind(&self) -> RemoteEventKind {
+        match self {
+            RemoteEvent::SongChange { .. } => RemoteEventKind::SongChange,
+            RemoteEvent::TabChange { .. } => RemoteEventKind::TabChange,
+            RemoteEvent::PaneFocus { .. } => RemoteEventKind::PaneFocus,
+            RemoteEvent::Status { .. } => RemoteEventKind::Status,
+            RemoteEvent::LyricsLine { .. } => RemoteEventKind::LyricsLine,
+            RemoteEvent::ConfigReload => RemoteEventKind::ConfigReload,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Subscriber {
+    events: Vec<RemoteEventKind>,
+    tx: Sender<String>,
+}
+
+impl Subscriber {
+    fn wants(&self, kind: RemoteEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// Subscribers of the main loop's events. Subscribers whose connection was
+/// closed are dropped on the next emitted event.
+#[derive(Debug, Default)]
+pub(crate) struct Subscribers(Vec<Subscriber>);
+
+impl Subscribers {
+    pub(crate) fn add(&mut self, subscriber: Subscriber) {
+        self.0.push(subscriber);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn emit(&mut self, event: &RemoteEvent) {
+        let kind = event.kind();
+        if !self.0.iter().any(|subscriber| subscriber.wants(kind)) {
+            return;
+        }
+
+        let mut line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(err) => {
+                log::error!(err:?, event:?; "Failed to serialize remote event");
+                return;
+            }
+        };
+        line.push('\n');
+
+        self.0.retain(|subscriber| {
+            !subscriber.wants(kind) || subscriber.tx.send(line.clone()).is_ok()
+        });
+    }
+}
+
+impl SubscribeIpcCommand {
+    /// Registers the subscriber in the main loop and streams its events to
+    /// the client from a separate thread so that a slow client cannot block
+    /// the socket listener.
+    pub(crate) fn subscribe(
+        self,
+        mut stream: UnixStream,
+        event_tx: &Sender<AppEvent>,
+    ) -> Result<()> {
+        let (tx, rx) = unbounded::<String>();
+        event_tx.send(AppEvent::RemoteSubscribe(Subscriber { events: self.events, tx }))?;
+
+        std::thread::Builder::new().name("subscriber".to_owned()).spawn(move || {
+            for line in rx {
+                if let Err(err) = stream.write_all(line.as_bytes()) {
+                    log::debug!(err:?; "Subscriber disconnected");
+                    break;
+                }
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crossbeam::channel::unbounded;
+
+    use super::*;
+
+    fn subscriber(
+        events: Vec<RemoteEventKind>,
+    ) -> (Subscriber, crossbeam::channel::Receiver<String>) {
+        let (tx, rx) = unbounded();
+        (Subscriber { events, tx }, rx)
+    }
+
+    #[test]
+    fn events_are_filtered_by_kind() {
+        let mut subscribers = Subscribers::default();
+        let (all, all_rx) = subscriber(Vec::new());
+        let (tabs, tabs_rx) = subscriber(vec![RemoteEventKind::TabChange]);
+        subscribers.add(all);
+        subscribers.add(tabs);
+
+        subscribers.emit(&RemoteEvent::ConfigReload);
+        subscribers.emit(&RemoteEvent::TabChange { tab: "Queue".to_owned() });
+
+        assert_eq!(all_rx.try_iter().collect::<Vec<_>>(), [
+            "{\"event\":\"config_reload\"}\n",
+            "{\"event\":\"tab_change\",\"tab\":\"Queue\"}\n"
+        ]);
+        assert_eq!(tabs_rx.try_iter().collect::<Vec<_>>(), [
+            "{\"event\":\"tab_change\",\"tab\":\"Queue\"}\n"
+        ]);
+    }
+
+    #[test]
+    fn disconnected_subscribers_are_dropped() {
+        let mut subscribers = Subscribers::default();
+        let (sub, rx) = subscriber(Vec::new());
+        subscribers.add(sub);
+        drop(rx);
+
+        subscribers.emit(&RemoteEvent::ConfigReload);
+
+        assert!(subscribers.is_empty());
+    }
+}
@@ -1,6 +1,14 @@
-use std::{io::Write, os::unix::net::UnixStream, path::PathBuf};
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt},
+        net::UnixStream,
+    },
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use crossbeam::channel::Sender;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -10,7 +18,9 @@ use crate::{
     WorkRequest,
     config::{Config, cli::RemoteCmd},
     shared::ipc::commands::{
+        action::ActionIpcCommand,
         index_lrc::IndexLrcCommand,
+        keys::KeysIpcCommand,
         set::SetIpcCommand,
         status_message::StatusMessageCommand,
         tmux::TmuxHookCommand,
@@ -19,31 +29,147 @@ use crate::{
 
 mod commands;
 
-pub fn get_socket_path(pid: u32) -> PathBuf {
-    let mut temp = std::env::temp_dir();
-    temp.push(format!("rmpc-{pid}.sock"));
-    temp
-}
-
-pub fn list_all_socket_paths() -> Result<impl Iterator<Item = PathBuf>> {
-    let res: Vec<_> = std::fs::read_dir(std::env::temp_dir())?
-        .map(|entry| -> Result<Option<_>> {
-            let entry = entry?;
-            let filename = entry.file_name();
-            let filename = filename.to_string_lossy();
-            if filename.starts_with("rmpc-") && filename.ends_with(".sock") {
-                Ok(Some(entry.path()))
-            } else {
-                Ok(None)
-            }
-        })
-        .filter_map(|entry| match entry {
-            Ok(Some(val)) => Some(Ok(val)),
-            Ok(None) => None,
-            Err(err) => Some(Err(err)),
-        })
-        .try_collect()?;
-    Ok(res.into_iter())
+pub(crate) use commands::{
+    get::GetIpcCommand,
+    subscribe::{RemoteEvent, SubscribeIpcCommand, Subscriber, Subscribers},
+};
+
+/// Directory with the sockets of all running instances. Prefers
+/// `$XDG_RUNTIME_DIR/rmpc` and falls back to a per user directory in the temp
+/// dir.
+pub fn socket_dir() -> PathBuf {
+    match runtime_dir() {
+        Some(dir) => dir.join("rmpc"),
+        None => std::env::temp_dir().join(format!("rmpc-{}", rustix::process::getuid().as_raw())),
+    }
+}
+
+fn runtime_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+}
+
+/// Creates the [`socket_dir`] if it does not exist yet. Anyone can create the
+/// fallback in the temp dir beforehand, so it is used only if it belongs to
+/// the current user and nobody else can access it.
+pub fn create_socket_dir() -> Result<PathBuf> {
+    let dir = socket_dir();
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .with_context(|| format!("Failed to create socket directory '{}'", dir.display()))?;
+
+    if runtime_dir().is_none() {
+        ensure_private_dir(&dir)?;
+    }
+
+    Ok(dir)
+}
+
+fn ensure_private_dir(dir: &Path) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(dir)
+        .with_context(|| format!("Failed to read socket directory '{}'", dir.display()))?;
+    if !metadata.is_dir()
+        || metadata.uid() != rustix::process::getuid().as_raw()
+        || metadata.mode() & 0o777 != 0o700
+    {
+        bail!(
+            "Socket directory '{}' must be a directory owned by the current user with permissions 700",
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+pub fn get_socket_path(pid: u32, name: Option<&str>) -> PathBuf {
+    socket_dir().join(match name {
+        Some(name) => format!("{pid}-{name}.sock"),
+        None => format!("{pid}.sock"),
+    })
+}
+
+/// Instance names end up in the socket's file name
+pub fn validate_instance_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains('/') {
+        bail!("Invalid instance name '{name}', it must not be empty or contain '/'");
+    }
+    Ok(())
+}
+
+/// rmpc instance which created a socket in the [`socket_dir`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub pid: u32,
+    pub name: Option<String>,
+    pub path: PathBuf,
+}
+
+impl Instance {
+    /// Parses `<pid>.sock` or `<pid>-<name>.sock`
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?.strip_suffix(".sock")?;
+        let (pid, name) = match file_name.split_once('-') {
+            Some((pid, name)) => (pid, Some(name.to_owned())),
+            None => (file_name, None),
+        };
+        let pid = pid.parse().ok()?;
+
+        Some(Self { pid, name, path })
+    }
+
+    /// Sockets of instances which did not exit cleanly are left behind but
+    /// refuse connections
+    fn is_alive(&self) -> bool {
+        UnixStream::connect(&self.path).is_ok()
+    }
+}
+
+/// Lists instances with a live socket and removes the stale ones
+pub fn list_instances() -> Result<Vec<Instance>> {
+    let dir = socket_dir();
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read '{}'", dir.display()));
+        }
+    };
+
+    let mut instances = Vec::new();
+    for entry in entries {
+        let Some(instance) = Instance::from_path(entry?.path()) else {
+            continue;
+        };
+
+        if instance.is_alive() {
+            instances.push(instance);
+        } else if let Err(err) = std::fs::remove_file(&instance.path) {
+            log::warn!(err:?, instance:?; "Failed to remove stale socket");
+        }
+    }
+    instances.sort_by_key(|instance| instance.pid);
+
+    Ok(instances)
+}
+
+/// Instances targeted by a remote command. All of them unless selected by PID
+/// or name.
+pub fn find_instances(pid: Option<u32>, name: Option<&str>) -> Result<Vec<Instance>> {
+    let instances = list_instances()?
+        .into_iter()
+        .filter(|instance| pid.is_none_or(|pid| instance.pid == pid))
+        .filter(|instance| name.is_none_or(|name| instance.name.as_deref() == Some(name)))
+        .collect_vec();
+
+    match (pid, name) {
+        (Some(pid), _) if instances.is_empty() => {
+            bail!("No rmpc instance with PID {pid} was found")
+        }
+        (_, Some(name)) if instances.is_empty() => {
+            bail!("No rmpc instance named '{name}' was found")
+        }
+        _ => Ok(instances),
+    }
 }
 
 pub(crate) trait SocketCommandExecute {
@@ -61,6 +187,19 @@ pub(crate) enum SocketCommand {
     StatusMessage(StatusMessageCommand),
     TmuxHook(TmuxHookCommand),
     Set(Box<SetIpcCommand>),
+    Action(ActionIpcCommand),
+    Keys(KeysIpcCommand),
+    /// The only command which is answered with a [`SocketResponse`]
+    Get(GetIpcCommand),
+    /// Keeps the connection open and streams [`RemoteEvent`]s to it
+    Subscribe(SubscribeIpcCommand),
+}
+
+/// Answer to a [`SocketCommand::Get`], written back as a single JSON line
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) enum SocketResponse {
+    Ok(serde_json::Value),
+    Err(String),
 }
 
 impl SocketCommandExecute for SocketCommand {
@@ -75,6 +214,12 @@ impl SocketCommandExecute for SocketCommand {
             SocketCommand::StatusMessage(cmd) => cmd.execute(event_tx, work_tx, config),
             SocketCommand::TmuxHook(cmd) => cmd.execute(event_tx, work_tx, config),
             SocketCommand::Set(cmd) => cmd.execute(event_tx, work_tx, config),
+            SocketCommand::Action(cmd) => cmd.execute(event_tx, work_tx, config),
+            SocketCommand::Keys(cmd) => cmd.execute(event_tx, work_tx, config),
+            SocketCommand::Get(_) => bail!("Get command has to be answered through the socket"),
+            SocketCommand::Subscribe(_) => {
+                bail!("Subscribe command has to be answered through the socket")
+            }
         }
     }
 }
@@ -87,4 +232,103 @@ impl RemoteCmd {
         let mut stream = UnixStream::connect(path).context("Failed to connect to socket")?;
         stream.write_all(cmd.as_bytes()).context("Failed to write command to socket.")
     }
+
+    /// Sends the command and waits for the instance's answer
+    pub fn query_socket(self, path: &Path) -> Result<serde_json::Value> {
+        let cmd = SocketCommand::try_from(self)?;
+        let mut cmd = serde_json::to_string(&cmd).context("Failed to serialize command.")?;
+        cmd.push('\n');
+
+        let mut stream = UnixStream::connect(path).context("Failed to connect to socket")?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        stream.write_all(cmd.as_bytes()).context("Failed to write command to socket.")?;
+
+        let mut buf = String::new();
+        BufReader::new(stream)
+            .read_line(&mut buf)
+            .context("Failed to read response from socket")?;
+        match serde_json::from_str(&buf).context("Failed to parse response from socket")? {
+            SocketResponse::Ok(value) => Ok(value),
+            SocketResponse::Err(err) => bail!(err),
+        }
+    }
+
+    /// Sends the command and copies the streamed events to the output until
+    /// the instance closes the connection
+    pub fn subscribe_socket(self, path: &Path, mut out: impl Write) -> Result<()> {
+        let cmd = SocketCommand::try_from(self)?;
+        let mut cmd = serde_json::to_string(&cmd).context("Failed to serialize command.")?;
+        cmd.push('\n');
+
+        let mut stream = UnixStream::connect(path).context("Failed to connect to socket")?;
+        stream.write_all(cmd.as_bytes()).context("Failed to write command to socket.")?;
+
+        for line in BufReader::new(stream).lines() {
+            let line = line.context("Failed to read event from socket")?;
+            writeln!(out, "{line}")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the instance with the given PID or name, or the only running
+/// instance if neither was given
+pub fn find_single_instance(pid: Option<u32>, name: Option<&str>) -> Result<Instance> {
+    match find_instances(pid, name)?.as_slice() {
+        [] => bail!("No running rmpc instance was found"),
+        [instance] => Ok(instance.clone()),
+        _ => bail!("Multiple running rmpc instances were found, select one with --pid or --name"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{fs::Permissions, os::unix::fs::PermissionsExt, path::PathBuf};
+
+    use rstest::rstest;
+
+    use super::{Instance, ensure_private_dir, validate_instance_name};
+
+    #[rstest]
+    #[case("/run/rmpc/123.sock", Some((123, None)))]
+    #[case("/run/rmpc/123-music.sock", Some((123, Some("music"))))]
+    #[case("/run/rmpc/123-my-music.sock", Some((123, Some("my-music"))))]
+    #[case("/run/rmpc/abc.sock", None)]
+    #[case("/run/rmpc/123.txt", None)]
+    fn parses_instance_from_socket_path(
+        #[case] path: &str,
+        #[case] expected: Option<(u32, Option<&str>)>,
+    ) {
+        let instance = Instance::from_path(PathBuf::from(path));
+
+        assert_eq!(
+            instance.as_ref().map(|instance| (instance.pid, instance.name.as_deref())),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("music", true)]
+    #[case("", false)]
+    #[case("../music", false)]
+    fn validates_instance_name(#[case] name: &str, #[case] valid: bool) {
+        assert_eq!(validate_instance_name(name).is_ok(), valid);
+    }
+
+    #[test]
+    fn socket_dir_must_be_private() {
+        let dir = std::env::temp_dir().join(format!("rmpc-socket-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::set_permissions(&dir, Permissions::from_mode(0o755)).unwrap();
+        let shared = ensure_private_dir(&dir);
+        std::fs::set_permissions(&dir, Permissions::from_mode(0o700)).unwrap();
+        let private = ensure_private_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(shared.is_err());
+        assert!(private.is_ok());
+    }
 }
@@ -1,29 +1,221 @@
-use crossterm::event::{KeyCode, KeyEvent as CKeyEvent};
+use std::{collections::HashMap, fmt::Display, time::Instant};
+
+use crossterm::event::{KeyCode, KeyEvent as CKeyEvent, KeyModifiers};
 
 #[cfg(debug_assertions)]
 use crate::config::keys::LogsActions;
 use crate::{
-    config::keys::{CommonAction, GlobalAction, QueueActions},
+    config::keys::{
+        AlbumsActions,
+        ArtistsActions,
+        ChainStep,
+        CommonAction,
+        DirectoriesActions,
+        GlobalAction,
+        KeySequence,
+        PlaylistsActions,
+        QueueActions,
+        SearchActions,
+    },
     context::AppContext,
 };
 
 #[derive(Debug, Clone)]
 pub struct KeyEvent {
     inner: CKeyEvent,
+    /// All keys pressed so far, ending with the current one
+    sequence: KeySequence,
     already_handled: bool,
+    /// Set when the sequence is a prefix of a longer keybind
+    pending: bool,
+    /// Resolve the sequence right away even if it is a prefix of a longer
+    /// keybind
+    complete: bool,
+    /// Count typed before the keybind, ie. 5 in "5j"
+    count: Option<usize>,
+    /// Action of a chain which is run instead of looking up the sequence in
+    /// keybinds
+    step: Option<ChainStep>,
+}
+
+/// Keys pressed so far which are a prefix of a longer keybind
+#[derive(Debug, Clone)]
+pub struct PendingKeys {
+    pub sequence: KeySequence,
+    /// Count typed before the sequence
+    pub count: Option<usize>,
+    pub since: Instant,
+}
+
+impl Display for PendingKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(count) = self.count {
+            write!(f, "{count}")?;
+        }
+        write!(f, "{}", self.sequence)
+    }
+}
+
+/// Keybind pressed while recording a macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroStep {
+    pub sequence: KeySequence,
+    pub count: Option<usize>,
+}
+
+/// What to do with the register pressed after one of the macro keybinds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroRegisterOp {
+    Record,
+    Play { count: usize },
+}
+
+/// Records keybinds into registers so they can be replayed later, same as
+/// vim's `q` and `@`.
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    registers: HashMap<char, Vec<MacroStep>>,
+    recording: Option<(char, Vec<MacroStep>)>,
+    awaiting_register: Option<MacroRegisterOp>,
+}
+
+impl MacroRecorder {
+    /// Makes the next key pressed be used as a register for the given
+    /// operation.
+    pub fn await_register(&mut self, op: MacroRegisterOp) {
+        self.awaiting_register = Some(op);
+    }
+
+    pub fn take_awaiting_register(&mut self) -> Option<MacroRegisterOp> {
+        self.awaiting_register.take()
+    }
+
+    pub fn recording_register(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    pub fn start_recording(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    /// Saves the recorded keybinds to their register. Returns the register and
+    /// the number of recorded keybinds if a macro was being recorded.
+    pub fn stop_recording(&mut self) -> Option<(char, usize)> {
+        let (register, steps) = self.recording.take()?;
+        let len = steps.len();
+        self.registers.insert(register, steps);
+        Some((register, len))
+    }
+
+    /// Adds the key to the macro being recorded if it was handled. Keys which
+    /// start waiting for a register, ie. the macro keybinds themselves, are
+    /// skipped.
+    pub fn record(&mut self, key: &KeyEvent) {
+        if !key.is_handled() || key.sequence.len() == 0 || self.awaiting_register.is_some() {
+            return;
+        }
+        if let Some((_, steps)) = &mut self.recording {
+            steps.push(MacroStep { sequence: key.sequence.clone(), count: key.count });
+        }
+    }
+
+    pub fn get(&self, register: char) -> Option<&[MacroStep]> {
+        self.registers.get(&register).map(Vec::as_slice)
+    }
 }
 
 impl From<CKeyEvent> for KeyEvent {
     fn from(value: CKeyEvent) -> Self {
-        Self { inner: value, already_handled: false }
+        Self {
+            inner: value,
+            sequence: KeySequence::from(crate::config::keys::Key::from(value)),
+            already_handled: false,
+            pending: false,
+            complete: false,
+            count: None,
+            step: None,
+        }
     }
 }
 
 impl KeyEvent {
+    /// Creates an event for an already finished sequence, ie. after the wait
+    /// for its continuation timed out.
+    pub fn from_sequence(sequence: KeySequence) -> Option<Self> {
+        let last = sequence.last()?;
+        Some(Self {
+            inner: CKeyEvent::new(last.key, last.modifiers),
+            sequence,
+            already_handled: false,
+            pending: false,
+            complete: true,
+            count: None,
+            step: None,
+        })
+    }
+
+    /// Creates an event which resolves to the given step of an action chain
+    /// instead of any keybind.
+    pub fn from_step(step: ChainStep) -> Self {
+        Self {
+            inner: CKeyEvent::new(KeyCode::Null, KeyModifiers::NONE),
+            sequence: KeySequence::default(),
+            already_handled: false,
+            pending: false,
+            complete: true,
+            count: None,
+            step: Some(step),
+        }
+    }
+
     pub fn code(&self) -> KeyCode {
         self.inner.code
     }
 
+    pub fn sequence(&self) -> &KeySequence {
+        &self.sequence
+    }
+
+    /// How many times the action should be repeated, 1 if no count was typed
+    pub fn count(&self) -> usize {
+        self.count.unwrap_or(1)
+    }
+
+    /// The count typed before the keybind if there was one
+    pub fn explicit_count(&self) -> Option<usize> {
+        self.count
+    }
+
+    pub fn set_count(&mut self, count: Option<usize>) {
+        self.count = count;
+    }
+
+    /// Prepends keys which were pressed before this one
+    pub fn prepend(&mut self, prefix: &KeySequence) {
+        let mut sequence = prefix.clone();
+        sequence.0.extend(std::mem::take(&mut self.sequence.0));
+        self.sequence = sequence;
+    }
+
+    /// Returns the same key event without any of the keys pressed before
+    pub fn without_prefix(&self) -> Self {
+        Self::from(self.inner)
+    }
+
+    pub fn mark_complete(&mut self) {
+        self.complete = true;
+    }
+
+    pub fn is_handled(&self) -> bool {
+        self.already_handled
+    }
+
+    /// Whether the event was not handled and the sequence can still continue
+    /// to a longer keybind
+    pub fn is_pending(&self) -> bool {
+        self.pending && !self.already_handled
+    }
+
     pub fn stop_propagation(&mut self) {
         self.already_handled = true;
     }
@@ -32,51 +224,146 @@ impl KeyEvent {
         self.already_handled = false;
     }
 
-    pub fn as_common_action(&mut self, context: &AppContext) -> Option<CommonAction> {
-        if self.already_handled {
-            None
-        } else if let Some(action) = context.config.keybinds.navigation.get(&self.inner.into()) {
-            self.already_handled = true;
-            Some(*action)
-        } else {
-            None
+    fn find_action<V: Clone>(
+        &mut self,
+        context: &AppContext,
+        keybinds: &HashMap<KeySequence, V>,
+        from_step: impl FnOnce(&ChainStep) -> Option<&V>,
+    ) -> Option<V> {
+        if self.already_handled || self.pending {
+            return None;
         }
-    }
 
-    pub fn as_global_action<'ctx>(
-        &mut self,
-        context: &'ctx AppContext,
-    ) -> Option<&'ctx GlobalAction> {
-        if self.already_handled {
-            None
-        } else if let Some(action) = context.config.keybinds.global.get(&self.inner.into()) {
+        if let Some(step) = &self.step {
+            let action = from_step(step)?.clone();
             self.already_handled = true;
-            Some(action)
-        } else {
-            None
+            return Some(action);
         }
+
+        // Wait for the rest of the sequence if it can continue to any other
+        // keybind, even in a different section. Digits are also kept as they
+        // can be a count for the next keybind, a bound digit runs after the
+        // wait times out.
+        if !self.complete
+            && (context.config.keybinds.is_prefix(&self.sequence)
+                || self.sequence.as_count().is_some())
+        {
+            self.pending = true;
+            return None;
+        }
+
+        let action = keybinds.get(&self.sequence)?.clone();
+        self.already_handled = true;
+        Some(action)
+    }
+
+    pub fn as_common_action(&mut self, context: &AppContext) -> Option<CommonAction> {
+        self.find_action(context, &context.config.keybinds.navigation, |step| {
+            step.navigation.as_ref()
+        })
+    }
+
+    pub fn as_global_action(&mut self, context: &AppContext) -> Option<GlobalAction> {
+        self.find_action(context, &context.config.keybinds.global, |step| step.global.as_ref())
     }
 
     #[cfg(debug_assertions)]
     pub fn as_logs_action(&mut self, context: &AppContext) -> Option<LogsActions> {
-        if self.already_handled {
-            None
-        } else if let Some(action) = context.config.keybinds.logs.get(&self.inner.into()) {
-            self.already_handled = true;
-            Some(*action)
-        } else {
-            None
-        }
+        self.find_action(context, &context.config.keybinds.logs, |_| None)
     }
 
     pub fn as_queue_action(&mut self, context: &AppContext) -> Option<QueueActions> {
-        if self.already_handled {
-            None
-        } else if let Some(action) = context.config.keybinds.queue.get(&self.inner.into()) {
-            self.already_handled = true;
-            Some(*action)
-        } else {
-            None
-        }
+        self.find_action(context, &context.config.keybinds.queue, |step| step.queue.as_ref())
+    }
+
+    pub fn as_albums_action(&mut self, context: &AppContext) -> Option<AlbumsActions> {
+        self.find_action(context, &context.config.keybinds.albums, |step| step.albums.as_ref())
+    }
+
+    pub fn as_artists_action(&mut self, context: &AppContext) -> Option<ArtistsActions> {
+        self.find_action(context, &context.config.keybinds.artists, |step| step.artists.as_ref())
+    }
+
+    pub fn as_directories_action(&mut self, context: &AppContext) -> Option<DirectoriesActions> {
+        self.find_action(context, &context.config.keybinds.directories, |step| {
+            step.directories.as_ref()
+        })
+    }
+
+    pub fn as_playlists_action(&mut self, context: &AppContext) -> Option<PlaylistsActions> {
+        self.find_action(context, &context.config.keybinds.playlists, |step| {
+            step.playlists.as_ref()
+        })
+    }
+
+    pub fn as_search_action(&mut self, context: &AppContext) -> Option<SearchActions> {
+        self.find_action(context, &context.config.keybinds.search, |step| step.search.as_ref())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent as CKeyEvent, KeyModifiers};
+    use rstest::rstest;
+
+    use super::{KeyEvent, MacroRecorder, MacroRegisterOp, MacroStep};
+    use crate::{config::keys::GlobalAction, context::AppContext, tests::fixtures::app_context};
+
+    fn handled_key(c: char, count: Option<usize>) -> KeyEvent {
+        let mut key = KeyEvent::from(CKeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        key.set_count(count);
+        key.stop_propagation();
+        key
+    }
+
+    #[test]
+    fn records_handled_keys_to_register() {
+        let mut macros = MacroRecorder::default();
+
+        macros.start_recording('a');
+        macros.record(&handled_key('j', Some(5)));
+        macros.record(&KeyEvent::from(CKeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE)));
+        macros.await_register(MacroRegisterOp::Play { count: 1 });
+        macros.record(&handled_key('@', None));
+        macros.take_awaiting_register();
+        macros.record(&handled_key('d', None));
+
+        assert_eq!(macros.recording_register(), Some('a'));
+        assert_eq!(macros.stop_recording(), Some(('a', 2)));
+        assert_eq!(macros.recording_register(), None);
+        assert_eq!(macros.get('a').unwrap(), &[
+            MacroStep { sequence: "j".parse().unwrap(), count: Some(5) },
+            MacroStep { sequence: "d".parse().unwrap(), count: None },
+        ]);
+    }
+
+    #[test]
+    fn recording_again_replaces_register() {
+        let mut macros = MacroRecorder::default();
+        macros.start_recording('a');
+        macros.record(&handled_key('j', None));
+        macros.stop_recording();
+
+        macros.start_recording('a');
+        macros.stop_recording();
+
+        assert_eq!(macros.get('a'), Some([].as_slice()));
+        assert_eq!(macros.get('b'), None);
+    }
+
+    #[rstest]
+    fn bound_digit_is_held_as_count_until_the_wait_times_out(app_context: AppContext) {
+        let mut key = KeyEvent::from(CKeyEvent::new(KeyCode::Char('1'), KeyModifiers::NONE));
+
+        let action = key.as_global_action(&app_context);
+        let mut timed_out = KeyEvent::from_sequence(key.sequence().clone()).unwrap();
+
+        assert_eq!(action, None);
+        assert!(key.is_pending());
+        assert_eq!(
+            timed_out.as_global_action(&app_context),
+            Some(GlobalAction::SwitchToTab("Queue".into()))
+        );
     }
 }
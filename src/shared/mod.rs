@@ -1,7 +1,9 @@
+pub mod command_line;
 pub mod dependencies;
 pub mod env;
 pub mod events;
 pub mod ext;
+pub mod fuzzy;
 pub mod geometry;
 pub mod id;
 pub mod image;
@@ -14,6 +16,7 @@ pub mod mouse_event;
 pub mod mpd_query;
 pub mod percent;
 pub mod ring_vec;
+pub mod scripting;
 pub mod string_util;
 pub mod terminal;
 pub mod tmux;
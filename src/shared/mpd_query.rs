@@ -1,8 +1,8 @@
 use std::{any::Any, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use bon::Builder;
-use crossbeam::channel::Sender;
+use crossbeam::channel::{Sender, bounded};
 use ratatui::{style::Style, widgets::ListItem};
 
 use super::events::AppEvent;
@@ -44,6 +44,32 @@ pub struct MpdCommand {
     pub callback: Box<dyn FnOnce(&mut Client<'_>) -> Result<()> + Send>,
 }
 
+/// Sends the query to the client thread and blocks until it is answered
+pub(crate) fn query_sync<T: Send + Sync + 'static>(
+    client_request_sender: &Sender<ClientRequest>,
+    on_done: impl FnOnce(&mut Client<'_>) -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let (tx, rx) = bounded(1);
+    let query = MpdQuerySync {
+        callback: Box::new(|client| Ok(MpdQueryResult::Any(Box::new((on_done)(client)?)))),
+        tx,
+    };
+
+    if let Err(err) = client_request_sender.send(ClientRequest::QuerySync(query)) {
+        log::error!(error:? = err; "Failed to send query request");
+        bail!("Failed to send sync query request");
+    }
+
+    if let MpdQueryResult::Any(any) = rx.recv()? {
+        if let Ok(val) = any.downcast::<T>() {
+            return Ok(*val);
+        }
+        bail!("Received unknown type answer for sync query request",);
+    }
+
+    bail!("Received unknown MpdQueryResult for sync query request");
+}
+
 impl MpdQuery {
     pub(crate) fn should_be_skipped(&self, other: &Self) -> bool {
         let Some(self_replace_id) = self.replace_id else {
@@ -90,6 +116,7 @@ pub(crate) enum MpdQueryResult {
     Preview { data: Option<Vec<PreviewGroup>>, origin_path: Option<Vec<String>> },
     SongsList { data: Vec<Song>, origin_path: Option<Vec<String>> },
     LsInfo { data: Vec<String>, origin_path: Option<Vec<String>> },
+    AlbumsWithDate(Vec<(String, String)>),
     DirOrSong { data: Vec<DirOrSong>, origin_path: Option<Vec<String>> },
     AddToPlaylist { playlists: Vec<String>, song_file: String },
     AlbumArt(Option<Vec<u8>>),
@@ -99,6 +126,7 @@ pub(crate) enum MpdQueryResult {
     Outputs(Vec<Output>),
     Decoders(Vec<Decoder>),
     ExternalCommand(Arc<Vec<String>>, Vec<Song>),
+    CommandCompletions(Vec<String>),
     Any(Box<dyn Any + Send + Sync>),
 }
 
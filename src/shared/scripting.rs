@@ -0,0 +1,333 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use crossbeam::channel::Sender;
+use rhai::{AST, Dynamic, Engine, EvalAltResult, Scope, serde::to_dynamic};
+
+use super::{
+    events::{AppEvent, ClientRequest, WorkRequest},
+    macros::{status_error, status_info, status_warn},
+    mpd_query::query_sync,
+};
+use crate::{
+    config::cli::Args,
+    context::AppContext,
+    mpd::{
+        client::Client,
+        commands::{Volume, status::OnOffOneshot},
+        mpd_client::MpdClient,
+    },
+};
+
+/// Actions run only on a key press and can afford far more operations than
+/// properties which are evaluated on every render
+const MAX_ACTION_OPERATIONS: u64 = 1_000_000;
+const MAX_PROPERTY_OPERATIONS: u64 = 10_000;
+/// Limits on the data a script can build so that even a short script cannot
+/// exhaust memory or the stack. Arrays have to fit the whole queue.
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 100_000;
+const MAX_MAP_SIZE: usize = 10_000;
+const MAX_CALL_LEVELS: usize = 32;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Reads and compiles the script at the given path. Scripts are compiled once
+/// when the config is loaded and only evaluated afterwards.
+pub(crate) fn compile(path: &Path) -> Result<AST> {
+    let source = std::fs::read_to_string(path)?;
+    Ok(sandboxed_engine(MAX_ACTION_OPERATIONS).compile(source)?)
+}
+
+/// Engine without access to the file system or other modules, whose output
+/// goes to the log instead of the terminal occupied by the UI
+fn sandboxed_engine(max_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(max_operations);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_ARRAY_SIZE);
+    engine.set_max_map_size(MAX_MAP_SIZE);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.disable_symbol("eval");
+    engine.on_print(|text| log::info!(text; "Script printed a message"));
+    engine.on_debug(|text, source, pos| {
+        log::debug!(text, source:?, pos:? = pos; "Script printed a debug message");
+    });
+    engine
+}
+
+/// Engines running the configured `scripts`. Actions can control MPD, show
+/// messages and run commands while properties can only read the current state.
+#[derive(derive_more::Debug)]
+pub(crate) struct Scripts {
+    #[debug(skip)]
+    actions: Engine,
+    #[debug(skip)]
+    properties: Engine,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        let mut actions = sandboxed_engine(MAX_ACTION_OPERATIONS);
+        register_mpd(&mut actions);
+        register_rmpc(&mut actions);
+
+        Self { actions, properties: sandboxed_engine(MAX_PROPERTY_OPERATIONS) }
+    }
+}
+
+impl Scripts {
+    pub(crate) fn run_action(&self, name: &str, context: &AppContext) -> Result<()> {
+        let ast = context
+            .config
+            .scripts
+            .get(name)
+            .with_context(|| format!("Script '{name}' is not defined"))?;
+
+        let mut scope = state_scope(context)?;
+        scope.push("mpd", Mpd(context.client_request_sender.clone()));
+        scope.push("rmpc", Rmpc {
+            app_event_sender: context.app_event_sender.clone(),
+            work_sender: context.work_sender.clone(),
+        });
+
+        self.actions
+            .run_ast_with_scope(&mut scope, ast)
+            .with_context(|| format!("Script '{name}' failed"))
+    }
+
+    /// Evaluates the script as a header property. Returning unit from the
+    /// script, or failing, displays the property's default.
+    pub(crate) fn property(&self, name: &str, context: &AppContext) -> Option<String> {
+        let Some(ast) = context.config.scripts.get(name) else {
+            log::error!(name; "Script used as a property is not defined");
+            return None;
+        };
+
+        let result = state_scope(context).and_then(|mut scope| {
+            Ok(self.properties.eval_ast_with_scope::<Dynamic>(&mut scope, ast)?)
+        });
+
+        match result {
+            Ok(value) if value.is_unit() => None,
+            Ok(value) => Some(value.to_string()),
+            Err(err) => {
+                log::error!(name, err:?; "Script used as a property failed");
+                None
+            }
+        }
+    }
+}
+
+/// Read only state available to every script. Status and song have the same
+/// structure as the output of `rmpc status` and `rmpc song`.
+fn state_scope(context: &AppContext) -> Result<Scope<'static>> {
+    let song = match context.find_current_song_in_queue() {
+        Some((_, song)) => to_dynamic(song)?,
+        None => Dynamic::UNIT,
+    };
+
+    let mut scope = Scope::new();
+    scope.push_constant("status", to_dynamic(&context.status)?);
+    scope.push_constant("song", song);
+    scope.push_constant("queue_length", i64::try_from(context.queue.len()).unwrap_or(i64::MAX));
+    scope.push_constant("active_tab", context.active_tab.to_string());
+    Ok(scope)
+}
+
+/// Handle to the MPD connection, each call waits for MPD's answer
+#[derive(Clone)]
+struct Mpd(Sender<ClientRequest>);
+
+impl Mpd {
+    fn call<T: Send + Sync + 'static>(
+        &self,
+        callback: impl FnOnce(&mut Client<'_>) -> Result<T> + Send + 'static,
+    ) -> ScriptResult<T> {
+        query_sync(&self.0, callback).map_err(|err| format!("{err:#}").into())
+    }
+}
+
+fn register_mpd(engine: &mut Engine) {
+    engine.register_type_with_name::<Mpd>("Mpd");
+    engine.register_fn("play", |mpd: &mut Mpd| mpd.call(|client| Ok(client.play()?)));
+    engine.register_fn("play_pos", |mpd: &mut Mpd, pos: i64| {
+        let pos = usize::try_from(pos).map_err(|_| format!("Invalid position '{pos}'"))?;
+        mpd.call(move |client| Ok(client.play_pos(pos)?))
+    });
+    engine.register_fn("pause", |mpd: &mut Mpd| mpd.call(|client| Ok(client.pause()?)));
+    engine.register_fn("unpause", |mpd: &mut Mpd| mpd.call(|client| Ok(client.unpause()?)));
+    engine
+        .register_fn("toggle_pause", |mpd: &mut Mpd| mpd.call(|client| Ok(client.pause_toggle()?)));
+    engine.register_fn("stop", |mpd: &mut Mpd| mpd.call(|client| Ok(client.stop()?)));
+    engine.register_fn("next", |mpd: &mut Mpd| mpd.call(|client| Ok(client.next()?)));
+    engine.register_fn("prev", |mpd: &mut Mpd| mpd.call(|client| Ok(client.prev()?)));
+    engine.register_fn("set_volume", |mpd: &mut Mpd, volume: i64| {
+        let volume = Volume::new(u8::try_from(volume.clamp(0, 100)).unwrap_or_default());
+        mpd.call(move |client| Ok(client.set_volume(volume)?))
+    });
+    engine.register_fn("set_repeat", |mpd: &mut Mpd, enabled: bool| {
+        mpd.call(move |client| Ok(client.repeat(enabled)?))
+    });
+    engine.register_fn("set_random", |mpd: &mut Mpd, enabled: bool| {
+        mpd.call(move |client| Ok(client.random(enabled)?))
+    });
+    engine.register_fn("set_single", |mpd: &mut Mpd, value: &str| {
+        let value = on_off_oneshot(value)?;
+        mpd.call(move |client| Ok(client.single(value)?))
+    });
+    engine.register_fn("set_consume", |mpd: &mut Mpd, value: &str| {
+        let value = on_off_oneshot(value)?;
+        mpd.call(move |client| Ok(client.consume(value)?))
+    });
+    engine.register_fn("add", |mpd: &mut Mpd, uri: &str| {
+        let uri = uri.to_owned();
+        mpd.call(move |client| Ok(client.add(&uri, None)?))
+    });
+    engine.register_fn("clear", |mpd: &mut Mpd| mpd.call(|client| Ok(client.clear()?)));
+    engine.register_fn("update", |mpd: &mut Mpd| {
+        mpd.call(|client| Ok(i64::from(client.update(None)?.job_id)))
+    });
+    engine.register_fn("queue", |mpd: &mut Mpd| {
+        let queue = mpd.call(|client| Ok(client.playlist_info(false)?.unwrap_or_default()))?;
+        to_dynamic(queue)
+    });
+    engine.register_fn("outputs", |mpd: &mut Mpd| {
+        let outputs = mpd.call(|client| Ok(client.outputs()?.0))?;
+        to_dynamic(outputs)
+    });
+    engine.register_fn("enable_output", |mpd: &mut Mpd, id: i64| {
+        let id = output_id(id)?;
+        mpd.call(move |client| Ok(client.enable_output(id)?))
+    });
+    engine.register_fn("disable_output", |mpd: &mut Mpd, id: i64| {
+        let id = output_id(id)?;
+        mpd.call(move |client| Ok(client.disable_output(id)?))
+    });
+    engine.register_fn("toggle_output", |mpd: &mut Mpd, id: i64| {
+        let id = output_id(id)?;
+        mpd.call(move |client| Ok(client.toggle_output(id)?))
+    });
+}
+
+fn output_id(id: i64) -> ScriptResult<u32> {
+    u32::try_from(id).map_err(|_| format!("Invalid output id '{id}'").into())
+}
+
+fn on_off_oneshot(value: &str) -> ScriptResult<OnOffOneshot> {
+    match value {
+        "on" => Ok(OnOffOneshot::On),
+        "off" => Ok(OnOffOneshot::Off),
+        "oneshot" => Ok(OnOffOneshot::Oneshot),
+        _ => Err(format!("Invalid value '{value}', expected 'on', 'off' or 'oneshot'").into()),
+    }
+}
+
+/// Handle to rmpc itself for showing messages and running commands
+#[derive(Clone)]
+struct Rmpc {
+    app_event_sender: Sender<AppEvent>,
+    work_sender: Sender<WorkRequest>,
+}
+
+fn register_rmpc(engine: &mut Engine) {
+    engine.register_type_with_name::<Rmpc>("Rmpc");
+    engine.register_fn("info", |_: &mut Rmpc, message: &str| status_info!("{message}"));
+    engine.register_fn("warn", |_: &mut Rmpc, message: &str| status_warn!("{message}"));
+    engine.register_fn("error", |_: &mut Rmpc, message: &str| status_error!("{message}"));
+    engine.register_fn(
+        "modal",
+        |rmpc: &mut Rmpc, title: &str, message: &str| -> ScriptResult<()> {
+            rmpc.app_event_sender
+                .send(AppEvent::InfoModal {
+                    message: message.lines().map(ToOwned::to_owned).collect(),
+                    title: Some(title.to_owned()),
+                    size: None,
+                    id: None,
+                })
+                .map_err(|err| err.to_string().into())
+        },
+    );
+    engine.register_fn("command", |rmpc: &mut Rmpc, command: &str| -> ScriptResult<()> {
+        let Args { command: Some(command), .. } =
+            command.parse().map_err(|err| format!("Invalid command '{command}': {err:#}"))?
+        else {
+            return Err(format!("Invalid command '{command}'").into());
+        };
+        rmpc.work_sender.send(WorkRequest::Command(command)).map_err(|err| err.to_string().into())
+    });
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::Arc;
+
+    use rstest::rstest;
+
+    use super::{MAX_PROPERTY_OPERATIONS, Scripts, sandboxed_engine};
+    use crate::{
+        context::AppContext,
+        mpd::commands::{State, Volume},
+        tests::fixtures::app_context,
+    };
+
+    fn with_script(context: &mut AppContext, name: &str, source: &str) {
+        let mut config = context.config.as_ref().clone();
+        let ast = sandboxed_engine(MAX_PROPERTY_OPERATIONS).compile(source).unwrap();
+        config.scripts.insert(name.to_owned(), Arc::new(ast));
+        context.config = Arc::new(config);
+    }
+
+    #[rstest]
+    fn property_reads_status(mut app_context: AppContext) {
+        app_context.status.volume = Volume::new(42);
+        app_context.status.state = State::Pause;
+        with_script(
+            &mut app_context,
+            "vol",
+            r#"if status.state == "Pause" { `paused at ${status.volume}` }"#,
+        );
+
+        assert_eq!(
+            Scripts::default().property("vol", &app_context),
+            Some("paused at 42".to_owned())
+        );
+    }
+
+    #[rstest]
+    fn property_falls_back_on_unit_error_and_missing_script(mut app_context: AppContext) {
+        with_script(&mut app_context, "unit", "if song != () { song.file }");
+        with_script(&mut app_context, "endless", "loop {}");
+        let scripts = Scripts::default();
+
+        assert_eq!(scripts.property("unit", &app_context), None);
+        assert_eq!(scripts.property("endless", &app_context), None);
+        assert_eq!(scripts.property("missing", &app_context), None);
+    }
+
+    #[rstest]
+    fn scripts_cannot_exhaust_memory_or_stack(mut app_context: AppContext) {
+        with_script(&mut app_context, "string", r#"let s = "a"; for i in 0..40 { s += s } s"#);
+        with_script(&mut app_context, "array", "let a = [1]; for i in 0..40 { a += a } a.len()");
+        with_script(&mut app_context, "recursion", "fn f(n) { f(n + 1) } f(0)");
+        let scripts = Scripts::default();
+
+        assert_eq!(scripts.property("string", &app_context), None);
+        assert_eq!(scripts.property("array", &app_context), None);
+        assert_eq!(scripts.property("recursion", &app_context), None);
+        assert!(scripts.run_action("string", &app_context).is_err());
+    }
+
+    #[rstest]
+    fn properties_cannot_control_mpd(mut app_context: AppContext) {
+        with_script(&mut app_context, "next", "mpd.next(); 1");
+
+        assert_eq!(Scripts::default().property("next", &app_context), None);
+    }
+
+    #[rstest]
+    fn action_fails_for_missing_script(app_context: AppContext) {
+        assert!(Scripts::default().run_action("missing", &app_context).is_err());
+    }
+}
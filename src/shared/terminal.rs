@@ -5,6 +5,8 @@ use std::{
 
 use parking_lot::{Mutex, MutexGuard};
 
+use crate::config::theme_mode::ThemeMode;
+
 #[allow(dead_code)]
 pub struct Terminal {
     reader: TtyReader,
@@ -56,3 +58,123 @@ impl Read for TtyReader {
         self.stdin.lock().read(buf)
     }
 }
+
+/// Asks the terminal for its background color with OSC 11. Device attributes
+/// are queried right after it because every terminal answers them, so there is
+/// no need to wait for a timeout when OSC 11 is not supported. Must not be
+/// called while something else reads the terminal's input.
+pub fn query_background_mode() -> anyhow::Result<Option<ThemeMode>> {
+    let buf = query(b"\x1b]11;?\x1b\\\x1b[c", |buf| buf.contains("\x1b[?") && buf.ends_with('c'))?;
+
+    log::debug!(buf:?; "Background color response");
+
+    Ok(parse_background_mode(&buf))
+}
+
+/// Writes the query to the terminal and reads its response until
+/// `is_complete` returns true or nothing more arrives in time.
+pub fn query(query: &[u8], is_complete: impl Fn(&str) -> bool) -> anyhow::Result<String> {
+    let stdin = rustix::stdio::stdin();
+    let _guard = QueryModeGuard::enable()?;
+
+    {
+        // Locked so that the query does not end up in the middle of a frame
+        let writer = TERMINAL.writer();
+        let mut stdout = writer.lock();
+        stdout.write_all(query)?;
+        stdout.flush()?;
+    }
+
+    let mut buf = String::new();
+    loop {
+        let mut charbuffer = [0; 1];
+        rustix::io::read(stdin, &mut charbuffer)?;
+
+        buf.push(charbuffer[0].into());
+
+        if charbuffer[0] == b'\0' || is_complete(&buf) {
+            break;
+        }
+    }
+
+    Ok(buf)
+}
+
+/// Switches the terminal's input to non-canonical mode without echo for
+/// reading the response to a query. Previous attributes are restored on drop
+/// so that the terminal is not left in this mode when the query fails.
+struct QueryModeGuard {
+    termios_orig: rustix::termios::Termios,
+}
+
+impl QueryModeGuard {
+    fn enable() -> anyhow::Result<Self> {
+        let stdin = rustix::stdio::stdin();
+        let termios_orig = rustix::termios::tcgetattr(stdin)?;
+        let mut termios = termios_orig.clone();
+
+        termios.local_modes &= !rustix::termios::LocalModes::ICANON;
+        termios.local_modes &= !rustix::termios::LocalModes::ECHO;
+        // Set read timeout to 100ms as we cannot reliably check for end of terminal
+        // response
+        termios.special_codes[rustix::termios::SpecialCodeIndex::VTIME] = 1;
+        // Set read minimum to 0
+        termios.special_codes[rustix::termios::SpecialCodeIndex::VMIN] = 0;
+
+        rustix::termios::tcsetattr(stdin, rustix::termios::OptionalActions::Drain, &termios)?;
+
+        Ok(Self { termios_orig })
+    }
+}
+
+impl Drop for QueryModeGuard {
+    fn drop(&mut self) {
+        let stdin = rustix::stdio::stdin();
+        if let Err(err) = rustix::termios::tcsetattr(
+            stdin,
+            rustix::termios::OptionalActions::Now,
+            &self.termios_orig,
+        ) {
+            log::error!(err:?; "Failed to restore terminal attributes");
+        }
+    }
+}
+
+/// Parses the `rgb:RRRR/GGGG/BBBB` color from the OSC 11 response where each
+/// component has one to four hex digits
+fn parse_background_mode(response: &str) -> Option<ThemeMode> {
+    let (_, color) = response.split_once("\x1b]11;rgb:")?;
+    let color = color.split(['\x1b', '\x07']).next()?;
+
+    let mut components = color.split('/').map(|component| {
+        let max = 16_u32.checked_pow(u32::try_from(component.len()).ok()?)? - 1;
+        let value = u32::from_str_radix(component, 16).ok()?;
+        (max > 0).then(|| f64::from(value) / f64::from(max))
+    });
+    let (Some(Some(r)), Some(Some(g)), Some(Some(b)), None) =
+        (components.next(), components.next(), components.next(), components.next())
+    else {
+        return None;
+    };
+
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 { ThemeMode::Light } else { ThemeMode::Dark })
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::parse_background_mode;
+    use crate::config::theme_mode::ThemeMode;
+
+    #[test_case("\x1b]11;rgb:0000/0000/0000\x1b\\\x1b[?62;4c", Some(ThemeMode::Dark); "black")]
+    #[test_case("\x1b]11;rgb:ffff/ffff/ffff\x07\x1b[?62c", Some(ThemeMode::Light); "white with bel")]
+    #[test_case("\x1b]11;rgb:fd/f6/e3\x1b\\", Some(ThemeMode::Light); "two digits")]
+    #[test_case("\x1b]11;rgb:2828/2c2c/3434\x1b\\", Some(ThemeMode::Dark); "dark gray")]
+    #[test_case("\x1b[?62;4c", None; "unsupported")]
+    #[test_case("\x1b]11;rgb:ffff/ffff\x1b\\", None; "missing component")]
+    fn background_mode(response: &str, expected: Option<ThemeMode>) {
+        assert_eq!(parse_background_mode(response), expected);
+    }
+}
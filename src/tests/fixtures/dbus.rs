@@ -0,0 +1,26 @@
+use std::os::unix::net::UnixStream;
+
+use zbus::blocking::{Connection, connection};
+
+/// Connects a client to a server over a peer to peer D-Bus connection instead
+/// of the session bus. `serve` gets the server's builder to add its objects to
+/// and build it. Both sides are built at the same time because their
+/// handshakes wait for each other.
+#[allow(clippy::unwrap_used)]
+pub fn p2p_connection<T: Send + 'static>(
+    serve: impl FnOnce(connection::Builder<'static>) -> T + Send + 'static,
+) -> (T, Connection) {
+    let (server_stream, client_stream) = UnixStream::pair().unwrap();
+
+    let server = std::thread::spawn(move || {
+        serve(
+            connection::Builder::unix_stream(server_stream)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p(),
+        )
+    });
+    let client = connection::Builder::unix_stream(client_stream).p2p().build().unwrap();
+
+    (server.join().unwrap(), client)
+}
@@ -11,12 +11,16 @@ use crate::{
     mpd::commands::Status,
     shared::{
         events::{ClientRequest, WorkRequest},
+        key_event::MacroRecorder,
         lrc::LrcIndex,
         ring_vec::RingVec,
+        scripting::Scripts,
     },
 };
 
+pub mod dbus;
 pub mod mpd_client;
+pub mod temp_dir;
 
 #[fixture]
 pub fn status() -> Status {
@@ -61,6 +65,11 @@ pub fn app_context(
         scheduler,
         db_update_start: None,
         messages: RingVec::default(),
+        pending_keys: None,
+        macros: MacroRecorder::default(),
+        scripts: Scripts::default(),
+        detected_theme_mode: None,
+        forced_theme_mode: None,
     }
 }
 
@@ -10,6 +10,7 @@ use rstest::fixture;
 
 use crate::mpd::{
     QueuePosition,
+    SingleOrRange,
     commands::{
         IdleEvent,
         ListFiles,
@@ -19,7 +20,7 @@ use crate::mpd::{
         Status,
         Update,
         Volume,
-        list::MpdList,
+        list::{AlbumList, MpdList},
         list_playlist::FileList,
         metadata_tag::MetadataTagExt,
         mpd_config::MpdConfig,
@@ -28,7 +29,7 @@ use crate::mpd::{
         volume::Bound,
     },
     errors::MpdError,
-    mpd_client::{Filter, MpdClient, SaveMode, SingleOrRange, Tag, ValueChange},
+    mpd_client::{Filter, MpdClient, SaveMode, Tag, ValueChange},
     proto_client::SocketClient,
 };
 
@@ -137,7 +138,7 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
-    fn idle(&mut self, _subsystem: Option<IdleEvent>) -> MpdResult<Vec<IdleEvent>> {
+    fn idle(&mut self, _subsystems: &[IdleEvent]) -> MpdResult<Vec<IdleEvent>> {
         todo!("Not yet implemented")
     }
 
@@ -432,6 +433,10 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn swap(&mut self, _pos1: usize, _pos2: usize) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn find_one(&mut self, filter: &[Filter<'_>]) -> MpdResult<Option<Song>> {
         let mut res = self.find(filter)?;
         if res.len() > 1 {
@@ -457,10 +462,38 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn find_expression(&mut self, _expression: &str) -> MpdResult<Vec<Song>> {
+        todo!("Not yet implemented")
+    }
+
+    fn search_expression(&mut self, _expression: &str) -> MpdResult<Vec<Song>> {
+        todo!("Not yet implemented")
+    }
+
+    fn find_add_expression(
+        &mut self,
+        _expression: &str,
+        _position: Option<QueuePosition>,
+    ) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn search_add_expression(
+        &mut self,
+        _expression: &str,
+        _position: Option<QueuePosition>,
+    ) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn list_tag(&mut self, _tag: Tag, _filter: Option<&[Filter<'_>]>) -> MpdResult<MpdList> {
         todo!("Not yet implemented")
     }
 
+    fn list_albums_with_date(&mut self) -> MpdResult<AlbumList> {
+        todo!("Not yet implemented")
+    }
+
     fn shuffle(&mut self, _range: Option<SingleOrRange>) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+/// Directory for test files which is removed together with its content on
+/// drop. The name has to be unique among tests running at the same time.
+pub struct TempDir(pub PathBuf);
+
+#[allow(clippy::unwrap_used)]
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rmpc-test-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    /// Writes the file relative to this directory, creating its parent
+    /// directories, and returns its full path.
+    pub fn write(&self, name: &str, content: &str) -> PathBuf {
+        let path = self.0.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
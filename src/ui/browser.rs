@@ -227,51 +227,76 @@ where
             return Ok(());
         };
         let config = &context.config;
+        let count = event.count();
 
         match action {
             CommonAction::Up => {
-                self.stack_mut().current_mut().prev(config.scrolloff, config.wrap_navigation);
+                for _ in 0..count {
+                    self.stack_mut().current_mut().prev(config.scrolloff, config.wrap_navigation);
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
             CommonAction::Down => {
-                self.stack_mut().current_mut().next(config.scrolloff, config.wrap_navigation);
+                for _ in 0..count {
+                    self.stack_mut().current_mut().next(config.scrolloff, config.wrap_navigation);
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
             CommonAction::MoveUp => {
-                self.move_selected(MoveDirection::Up, context);
+                for _ in 0..count {
+                    self.move_selected(MoveDirection::Up, context);
+                }
             }
             CommonAction::MoveDown => {
-                self.move_selected(MoveDirection::Down, context);
+                for _ in 0..count {
+                    self.move_selected(MoveDirection::Down, context);
+                }
             }
             CommonAction::DownHalf => {
-                self.stack_mut().current_mut().next_half_viewport(context.config.scrolloff);
+                for _ in 0..count {
+                    self.stack_mut().current_mut().next_half_viewport(context.config.scrolloff);
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
             CommonAction::UpHalf => {
-                self.stack_mut().current_mut().prev_half_viewport(context.config.scrolloff);
+                for _ in 0..count {
+                    self.stack_mut().current_mut().prev_half_viewport(context.config.scrolloff);
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
             CommonAction::PageUp => {
-                self.stack_mut().current_mut().prev_viewport(context.config.scrolloff);
+                for _ in 0..count {
+                    self.stack_mut().current_mut().prev_viewport(context.config.scrolloff);
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
             CommonAction::PageDown => {
-                self.stack_mut().current_mut().next_viewport(context.config.scrolloff);
+                for _ in 0..count {
+                    self.stack_mut().current_mut().next_viewport(context.config.scrolloff);
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
             CommonAction::Bottom => {
-                self.stack_mut().current_mut().last();
+                if let Some(count) = event.explicit_count() {
+                    self.stack_mut().current_mut().select_idx(count - 1, config.scrolloff);
+                } else {
+                    self.stack_mut().current_mut().last();
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
             CommonAction::Top => {
-                self.stack_mut().current_mut().first();
+                if let Some(count) = event.explicit_count() {
+                    self.stack_mut().current_mut().select_idx(count - 1, config.scrolloff);
+                } else {
+                    self.stack_mut().current_mut().first();
+                }
                 self.prepare_preview(context);
                 context.render()?;
             }
@@ -388,6 +413,16 @@ where
 
                 context.render()?;
             }
+            CommonAction::Delete if event.explicit_count().is_some() => {
+                if let Some((start, _)) = self.stack().current().selected_with_idx() {
+                    let end = (start + count).min(self.stack().current().items.len());
+                    for idx in (start..end).rev() {
+                        let item = &self.stack().current().items[idx];
+                        self.delete(item, idx, context)?;
+                    }
+                    context.render()?;
+                }
+            }
             CommonAction::Delete => {
                 if let Some((index, item)) = self.stack().current().selected_with_idx() {
                     self.delete(item, index, context)?;
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::{cmp::Ordering, collections::BTreeSet};
 
 use log::error;
 use ratatui::widgets::{ListItem, ListState};
@@ -163,6 +163,35 @@ impl<T: std::fmt::Debug + DirStackItem + Clone + Send> Dir<T> {
         }
     }
 
+    /// Sorts the items while keeping the same items selected and marked
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&T, &T) -> Ordering, scrolloff: usize) {
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        order.sort_by(|a, b| compare(&self.items[*a], &self.items[*b]));
+        self.reorder(&order, scrolloff);
+    }
+
+    /// Reverses the items while keeping the same items selected and marked
+    pub fn reverse(&mut self, scrolloff: usize) {
+        let order: Vec<usize> = (0..self.items.len()).rev().collect();
+        self.reorder(&order, scrolloff);
+    }
+
+    /// Moves items to the new order given as a list of their old indices
+    fn reorder(&mut self, order: &[usize], scrolloff: usize) {
+        let mut new_positions = vec![0; order.len()];
+        for (new, old) in order.iter().enumerate() {
+            new_positions[*old] = new;
+        }
+
+        let mut items: Vec<Option<T>> =
+            std::mem::take(&mut self.items).into_iter().map(Some).collect();
+        self.items = order.iter().filter_map(|old| items[*old].take()).collect();
+        self.state.marked = self.state.marked.iter().map(|old| new_positions[*old]).collect();
+        if let Some(selected) = self.state.get_selected() {
+            self.state.select(new_positions.get(selected).copied(), scrolloff);
+        }
+    }
+
     pub fn next(&mut self, scrolloff: usize, wrap: bool) {
         self.state.next(scrolloff, wrap);
     }
@@ -318,6 +347,26 @@ mod tests {
         }
     }
 
+    mod sort_by {
+        use std::collections::BTreeSet;
+
+        use super::create_subject;
+
+        #[test]
+        fn keeps_selected_and_marked_items() {
+            let mut subject = create_subject();
+            subject.state.select(Some(1), 0);
+            subject.state.mark(0);
+            subject.state.mark(3);
+
+            subject.sort_by(|a, b| b.cmp(a), 0);
+
+            assert_eq!(subject.items, vec!["f", "d", "c", "b", "a"]);
+            assert_eq!(subject.selected().unwrap(), "b");
+            assert_eq!(subject.state.marked, BTreeSet::from([1, 4]));
+        }
+    }
+
     mod toggle_mark_selected {
         use std::collections::BTreeSet;
 
@@ -50,6 +50,11 @@ impl<T: std::fmt::Debug + DirStackItem + Clone + Send> DirStack<T> {
             .expect("Previous items to always contain at least one item. This should have been handled in pop()")
     }
 
+    /// Returns all of the dirs in the stack, from the bottom to the top
+    pub fn dirs_mut(&mut self) -> impl Iterator<Item = &mut Dir<T>> {
+        self.others.iter_mut().chain(std::iter::once(&mut self.current))
+    }
+
     pub fn path(&self) -> &[String] {
         &self.path
     }
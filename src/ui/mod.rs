@@ -1,22 +1,29 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::LazyLock, time::Instant};
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{
+        DisableFocusChange,
+        DisableMouseCapture,
+        EnableFocusChange,
+        EnableMouseCapture,
+        KeyCode,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use itertools::Itertools;
 use modals::{
     add_random_modal::AddRandomModal,
+    command_modal::CommandModal,
+    command_palette::CommandPaletteModal,
     decoders::DecodersModal,
     info_list_modal::InfoListModal,
-    input_modal::InputModal,
     keybinds::KeybindsModal,
     outputs::OutputsModal,
     select_modal::SelectModal,
 };
-use panes::{PaneContainer, Panes, pane_call};
+use panes::{PaneContainer, PaneRemoteState, Panes, pane_call};
 use ratatui::{
     Frame,
     Terminal,
@@ -27,6 +34,7 @@ use ratatui::{
     widgets::{Block, Borders},
 };
 use tab_screen::TabScreen;
+use widgets::key_sequence_popup::KeySequencePopup;
 
 use self::{modals::Modal, panes::Pane};
 use crate::{
@@ -34,7 +42,7 @@ use crate::{
     config::{
         Config,
         cli::Args,
-        keys::GlobalAction,
+        keys::{ChainStep, GlobalAction},
         tabs::{PaneType, SizedPaneOrSplit, TabName},
         theme::level_styles::LevelStyles,
     },
@@ -46,8 +54,10 @@ use crate::{
         version::Version,
     },
     shared::{
-        events::{Level, WorkRequest},
-        key_event::KeyEvent,
+        events::{AppEvent, Level, WorkRequest},
+        id::{self, Id},
+        ipc::GetIpcCommand,
+        key_event::{KeyEvent, MacroRegisterOp, PendingKeys},
         macros::{modal, status_error, status_info, status_warn},
         mouse_event::MouseEvent,
         terminal::{TERMINAL, TtyWriter},
@@ -83,6 +93,8 @@ pub struct Ui<'ui> {
 const OPEN_DECODERS_MODAL: &str = "open_decoders_modal";
 const OPEN_OUTPUTS_MODAL: &str = "open_outputs_modal";
 
+static KEY_SEQUENCE_TIMEOUT_ID: LazyLock<Id> = LazyLock::new(id::new);
+
 macro_rules! active_tab_call {
     ($self:ident, $ctx:ident, $fn:ident($($param:expr),+)) => {
         $self.tabs
@@ -187,6 +199,10 @@ impl<'ui> Ui<'ui> {
             modal.render(frame, context)?;
         }
 
+        if let Some(pending) = &context.pending_keys {
+            frame.render_widget(KeySequencePopup::new(context, pending), frame.area());
+        }
+
         Ok(())
     }
 
@@ -219,10 +235,186 @@ impl<'ui> Ui<'ui> {
         context: &mut AppContext,
     ) -> Result<KeyHandleResult> {
         if let Some(ref mut modal) = self.modals.last_mut() {
+            // Key sequences are not supported in modals
+            key.mark_complete();
             modal.handle_key(key, context)?;
             return Ok(KeyHandleResult::None);
         }
 
+        if let Some(op) = context.macros.take_awaiting_register() {
+            return self.on_macro_register(op, key, context);
+        }
+
+        let pending = context.pending_keys.take();
+        if let Some(pending) = &pending {
+            key.prepend(&pending.sequence);
+            key.set_count(pending.count);
+        }
+
+        let result = self.dispatch_key(key, context)?;
+
+        if key.is_pending() {
+            context.pending_keys = Some(PendingKeys {
+                sequence: key.sequence().clone(),
+                count: key.explicit_count(),
+                since: Instant::now(),
+            });
+            context.scheduler.schedule_replace(
+                *KEY_SEQUENCE_TIMEOUT_ID,
+                context.config.key_sequence_timeout,
+                |(tx, _)| Ok(tx.send(AppEvent::KeySequenceTimeout)?),
+            );
+            context.render()?;
+        } else {
+            if let Some(pending) = pending {
+                context.render()?;
+                if !key.is_handled() {
+                    // The sequence does not continue to any keybind, start over
+                    // with only the last key. Digits pressed before it were its
+                    // count.
+                    let mut key = key.without_prefix();
+                    key.set_count(pending.sequence.as_count().or(pending.count));
+                    return self.handle_key(&mut key, context);
+                }
+            }
+            context.macros.record(key);
+        }
+
+        Ok(result)
+    }
+
+    /// Runs the keybind of the pending key sequence if there is one after the
+    /// wait for its continuation timed out.
+    pub fn on_key_sequence_timeout(&mut self, context: &mut AppContext) -> Result<KeyHandleResult> {
+        let timeout = context.config.key_sequence_timeout;
+        let Some(pending) =
+            context.pending_keys.take_if(|pending| pending.since.elapsed() >= timeout)
+        else {
+            return Ok(KeyHandleResult::None);
+        };
+        context.render()?;
+
+        let Some(mut key) = KeyEvent::from_sequence(pending.sequence) else {
+            return Ok(KeyHandleResult::None);
+        };
+        key.set_count(pending.count);
+        let result = self.dispatch_key(&mut key, context)?;
+        context.macros.record(&key);
+        Ok(result)
+    }
+
+    /// Runs a single action as if a key bound to it was pressed. Does nothing
+    /// while a modal is open.
+    pub fn run_action(
+        &mut self,
+        step: ChainStep,
+        context: &mut AppContext,
+    ) -> Result<KeyHandleResult> {
+        if !self.modals.is_empty() {
+            return Ok(KeyHandleResult::None);
+        }
+
+        let name = step.to_string();
+        let mut key = KeyEvent::from_step(step);
+        let result = self.dispatch_key(&mut key, context)?;
+        if !key.is_handled() {
+            status_warn!("Action '{name}' is not available in the focused pane");
+        }
+
+        Ok(result)
+    }
+
+    /// Type of the focused pane in the active tab
+    pub fn focused_pane(&self, context: &AppContext) -> Option<PaneType> {
+        self.tabs.get(&context.active_tab).and_then(TabScreen::focused_pane).cloned()
+    }
+
+    /// Answers a query from `rmpc remote get`
+    pub fn remote_query(
+        &mut self,
+        query: GetIpcCommand,
+        context: &AppContext,
+    ) -> Result<serde_json::Value> {
+        let focused = self.focused_pane(context);
+
+        let value = match query {
+            GetIpcCommand::Tab => serde_json::to_value(context.active_tab.0.as_str())?,
+            GetIpcCommand::Pane => serde_json::to_value(focused.map(|pane| pane.to_string()))?,
+            GetIpcCommand::Theme => serde_json::to_value(&context.config.theme_name)?,
+            GetIpcCommand::ConfigPath => {
+                bail!("Config path is answered by the socket listener")
+            }
+            GetIpcCommand::Selected | GetIpcCommand::Marked | GetIpcCommand::Filter => {
+                let state = match &focused {
+                    Some(pane) => {
+                        let mut pane = self.panes.get_mut(pane, context)?;
+                        pane_call!(pane, remote_state(context))?
+                    }
+                    None => PaneRemoteState::default(),
+                };
+
+                match query {
+                    GetIpcCommand::Selected => serde_json::to_value(state.selected)?,
+                    GetIpcCommand::Marked => serde_json::to_value(state.marked)?,
+                    _ => serde_json::to_value(state.filter)?,
+                }
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Starts recording or replays a macro with the register pressed after
+    /// one of the macro keybinds.
+    fn on_macro_register(
+        &mut self,
+        op: MacroRegisterOp,
+        key: &KeyEvent,
+        context: &mut AppContext,
+    ) -> Result<KeyHandleResult> {
+        let register = match key.code() {
+            KeyCode::Char(c) if c.is_ascii_alphanumeric() => c,
+            KeyCode::Esc => return Ok(KeyHandleResult::None),
+            _ => {
+                status_error!("Invalid macro register '{}'", key.sequence());
+                return Ok(KeyHandleResult::None);
+            }
+        };
+
+        match op {
+            MacroRegisterOp::Record => {
+                context.macros.start_recording(register);
+                status_info!("Recording macro to register '{register}'");
+            }
+            MacroRegisterOp::Play { count } => {
+                let Some(steps) = context.macros.get(register).map(<[_]>::to_vec) else {
+                    status_error!("Macro register '{register}' is empty");
+                    return Ok(KeyHandleResult::None);
+                };
+                for step in std::iter::repeat_n(steps, count).flatten() {
+                    let Some(mut key) = KeyEvent::from_sequence(step.sequence) else {
+                        continue;
+                    };
+                    key.set_count(step.count);
+                    let result = self.dispatch_key(&mut key, context)?;
+                    // Replayed keys become part of the macro being recorded, if any
+                    context.macros.record(&key);
+                    if matches!(result, KeyHandleResult::Quit) {
+                        return Ok(result);
+                    }
+                }
+            }
+        }
+        context.render()?;
+
+        Ok(KeyHandleResult::None)
+    }
+
+    fn dispatch_key(
+        &mut self,
+        key: &mut KeyEvent,
+        context: &mut AppContext,
+    ) -> Result<KeyHandleResult> {
         active_tab_call!(self, context, handle_action(key, context))?;
 
         if let Some(action) = key.as_global_action(context) {
@@ -262,24 +454,10 @@ impl<'ui> Ui<'ui> {
                     }
                 }
                 GlobalAction::CommandMode => {
-                    modal!(
-                        context,
-                        InputModal::new(context)
-                            .title("Execute a command")
-                            .confirm_label("Execute")
-                            .on_confirm(|context, value| {
-                                let cmd = value.parse();
-                                log::debug!("executing {cmd:?}");
-
-                                if let Ok(Args { command: Some(cmd), .. }) = cmd {
-                                    if context.work_sender.send(WorkRequest::Command(cmd)).is_err()
-                                    {
-                                        log::error!("Failed to send command");
-                                    }
-                                }
-                                Ok(())
-                            })
-                    );
+                    modal!(context, CommandModal::new(context));
+                }
+                GlobalAction::CommandPalette => {
+                    modal!(context, CommandPaletteModal::new(context));
                 }
                 GlobalAction::NextTrack if context.status.state != State::Stop => {
                     context.command(move |client| {
@@ -428,8 +606,8 @@ impl<'ui> Ui<'ui> {
                     context.render()?;
                 }
                 GlobalAction::SwitchToTab(name) => {
-                    if context.config.tabs.names.contains(name) {
-                        self.change_tab(name.clone(), context)?;
+                    if context.config.tabs.names.contains(&name) {
+                        self.change_tab(name, context)?;
                         context.render()?;
                     } else {
                         status_error!(
@@ -446,6 +624,9 @@ impl<'ui> Ui<'ui> {
                 GlobalAction::ExternalCommand { command, .. } => {
                     run_external(command.clone(), create_env(context, std::iter::empty::<&str>()));
                 }
+                GlobalAction::Script { name, .. } => {
+                    context.scripts.run_action(&name, context)?;
+                }
                 GlobalAction::Quit => return Ok(KeyHandleResult::Quit),
                 GlobalAction::ShowHelp => {
                     let modal = KeybindsModal::new(context);
@@ -482,6 +663,25 @@ impl<'ui> Ui<'ui> {
                 GlobalAction::AddRandom => {
                     modal!(context, AddRandomModal::new(context));
                 }
+                GlobalAction::Chain(steps) => {
+                    for step in steps.iter() {
+                        let mut key = KeyEvent::from_step(step.clone());
+                        if let KeyHandleResult::Quit = self.dispatch_key(&mut key, context)? {
+                            return Ok(KeyHandleResult::Quit);
+                        }
+                    }
+                }
+                GlobalAction::RecordMacro => {
+                    if let Some((register, len)) = context.macros.stop_recording() {
+                        status_info!("Recorded {len} keys to register '{register}'");
+                    } else {
+                        context.macros.await_register(MacroRegisterOp::Record);
+                    }
+                    context.render()?;
+                }
+                GlobalAction::PlayMacro => {
+                    context.macros.await_register(MacroRegisterOp::Play { count: key.count() });
+                }
             }
         }
 
@@ -743,22 +943,34 @@ impl TryFrom<IdleEvent> for UiEvent {
 pub fn restore_terminal<B: Backend + std::io::Write>(
     terminal: &mut Terminal<B>,
     enable_mouse: bool,
+    report_focus: bool,
 ) -> Result<()> {
     if enable_mouse {
         execute!(std::io::stdout(), DisableMouseCapture)?;
     }
+    if report_focus {
+        execute!(std::io::stdout(), DisableFocusChange)?;
+    }
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     Ok(terminal.show_cursor()?)
 }
 
-pub fn setup_terminal(enable_mouse: bool) -> Result<Terminal<CrosstermBackend<TtyWriter>>> {
+/// Focus changes are reported only when they are needed because the terminal
+/// is queried for its background color on every focus
+pub fn setup_terminal(
+    enable_mouse: bool,
+    report_focus: bool,
+) -> Result<Terminal<CrosstermBackend<TtyWriter>>> {
     enable_raw_mode()?;
     let mut writer = TERMINAL.writer();
     execute!(writer, EnterAlternateScreen)?;
     if enable_mouse {
         execute!(writer, EnableMouseCapture)?;
     }
+    if report_focus {
+        execute!(writer, EnableFocusChange)?;
+    }
     let mut terminal = Terminal::new(CrosstermBackend::new(writer))?;
     terminal.clear()?;
     Ok(terminal)
@@ -913,3 +1125,41 @@ impl Config {
         )
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent as CKeyEvent, KeyModifiers};
+    use ratatui::{Terminal, backend::TestBackend};
+    use rstest::rstest;
+
+    use super::Ui;
+    use crate::{
+        context::AppContext,
+        mpd::commands::Song,
+        shared::{ipc::GetIpcCommand, key_event::KeyEvent},
+        tests::fixtures::{app_context, terminal},
+    };
+
+    #[rstest]
+    fn count_before_keybind_with_default_config(
+        mut app_context: AppContext,
+        mut terminal: Terminal<TestBackend>,
+    ) {
+        app_context.active_tab = "Queue".into();
+        app_context.queue =
+            (0..10).map(|id| Song { id, file: id.to_string(), ..Default::default() }).collect();
+        let mut ui = Ui::new(&app_context).unwrap();
+        ui.before_show(terminal.get_frame().area(), &mut app_context).unwrap();
+        terminal.draw(|frame| ui.render(frame, &mut app_context).unwrap()).unwrap();
+
+        for c in ['5', 'j'] {
+            let mut key = KeyEvent::from(CKeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            ui.handle_key(&mut key, &mut app_context).unwrap();
+        }
+
+        assert_eq!(app_context.active_tab, "Queue".into());
+        assert!(app_context.pending_keys.is_none());
+        assert_eq!(ui.remote_query(GetIpcCommand::Selected, &app_context).unwrap()["file"], "5");
+    }
+}
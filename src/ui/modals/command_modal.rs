@@ -0,0 +1,416 @@
+use anyhow::Result;
+use clap::CommandFactory;
+use crossterm::event::KeyCode;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    prelude::{Constraint, Layout},
+    style::Style,
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use super::{Modal, RectExt};
+use crate::{
+    MpdQueryResult,
+    config::{cli::Args, keys::CommonAction},
+    context::AppContext,
+    mpd::{commands::lsinfo::LsInfoEntry, mpd_client::MpdClient},
+    shared::{
+        command_line::{
+            CommandHistory,
+            CompletionRequest,
+            CompletionSource,
+            completion_request,
+            quote_arg,
+            typed_subcommand,
+        },
+        events::WorkRequest,
+        key_event::KeyEvent,
+        macros::{pop_modal, status_error},
+        mouse_event::MouseEvent,
+    },
+    ui::widgets::input::Input,
+};
+
+const COMMAND_COMPLETION: &str = "command_completion";
+
+#[derive(Debug)]
+struct Completion {
+    /// Byte offset of the completed argument in the command line
+    start: usize,
+    candidates: Vec<String>,
+    selected: Option<usize>,
+    /// Candidates are still being fetched from MPD
+    pending: bool,
+}
+
+#[derive(Debug)]
+pub struct CommandModal {
+    cli: clap::Command,
+    value: String,
+    history: CommandHistory,
+    completion: Option<Completion>,
+    /// Usage and description of the subcommand being typed
+    help: Option<(String, String)>,
+}
+
+impl CommandModal {
+    pub fn new(context: &AppContext) -> Self {
+        let mut cli = Args::command();
+        cli.build();
+        let history = CommandHistory::load(
+            context.config.cache_dir.as_ref().map(|dir| dir.join("command_history")),
+        );
+
+        Self { cli, value: String::new(), history, completion: None, help: None }
+    }
+
+    fn on_value_changed(&mut self) {
+        self.completion = None;
+        self.help = typed_subcommand(&self.cli, &self.value).map(|cmd| {
+            let usage = cmd.clone().render_usage().to_string();
+            let usage = usage.trim_start_matches("Usage: ");
+            let usage = usage.strip_prefix("rmpc ").unwrap_or(usage).to_owned();
+            let about = cmd
+                .get_about()
+                .map(|about| about.to_string().lines().next().unwrap_or_default().to_owned())
+                .unwrap_or_default();
+            (usage, about)
+        });
+    }
+
+    fn set_value(&mut self, value: String) {
+        self.value = value;
+        self.on_value_changed();
+    }
+
+    fn apply_candidate(&mut self, start: usize, candidate: &str) {
+        self.value.truncate(start);
+        self.value.push_str(&quote_arg(candidate));
+    }
+
+    fn complete(&mut self, context: &AppContext) {
+        let request = completion_request(&self.cli, &self.value);
+        let CompletionRequest { start, ref prefix, ref source } = request;
+
+        let candidates = match source {
+            CompletionSource::Values(values) => request.filter(values.iter().cloned()),
+            CompletionSource::Tab => {
+                request.filter(context.config.tabs.names.iter().map(|name| name.0.to_string()))
+            }
+            CompletionSource::MpdPath => {
+                let prefix = prefix.clone();
+                self.query_completions(start, context, move |client| {
+                    let dir = prefix.rsplit_once('/').map(|(dir, _)| dir);
+                    Ok(client
+                        .lsinfo(dir)?
+                        .0
+                        .into_iter()
+                        .filter_map(|entry| match entry {
+                            LsInfoEntry::Dir(dir) => Some(format!("{}/", dir.full_path)),
+                            LsInfoEntry::File(song) => Some(song.file),
+                            LsInfoEntry::Playlist(_) => None,
+                        })
+                        .filter(|path| path.starts_with(&prefix))
+                        .sorted()
+                        .collect())
+                });
+                return;
+            }
+            CompletionSource::Playlist => {
+                let prefix = prefix.clone();
+                self.query_completions(start, context, move |client| {
+                    Ok(client
+                        .list_playlists()?
+                        .into_iter()
+                        .map(|playlist| playlist.name)
+                        .filter(|name| name.starts_with(&prefix))
+                        .sorted()
+                        .collect())
+                });
+                return;
+            }
+            CompletionSource::TagValue(tag) => {
+                let prefix = prefix.clone();
+                let tag = tag.clone();
+                self.query_completions(start, context, move |client| {
+                    Ok(client
+                        .list_tag(tag.into(), None)?
+                        .0
+                        .into_iter()
+                        .filter(|value| value.starts_with(&prefix))
+                        .sorted()
+                        .collect())
+                });
+                return;
+            }
+        };
+
+        self.on_completions(start, candidates);
+    }
+
+    fn query_completions(
+        &mut self,
+        start: usize,
+        context: &AppContext,
+        callback: impl FnOnce(&mut crate::mpd::client::Client<'_>) -> Result<Vec<String>>
+        + Send
+        + 'static,
+    ) {
+        self.completion =
+            Some(Completion { start, candidates: Vec::new(), selected: None, pending: true });
+        context
+            .query()
+            .id(COMMAND_COMPLETION)
+            .replace_id(COMMAND_COMPLETION)
+            .query(move |client| Ok(MpdQueryResult::CommandCompletions(callback(client)?)));
+    }
+
+    /// A single candidate is applied right away, multiple candidates are
+    /// extended to their common prefix and then cycled through on
+    /// subsequent completions.
+    fn on_completions(&mut self, start: usize, candidates: Vec<String>) {
+        match candidates.as_slice() {
+            [] => {
+                self.completion = None;
+            }
+            [candidate] => {
+                let candidate = candidate.clone();
+                self.apply_candidate(start, &candidate);
+                if !candidate.ends_with('/') {
+                    self.value.push(' ');
+                }
+                self.on_value_changed();
+            }
+            [first, rest @ ..] => {
+                let common_len = rest.iter().fold(first.len(), |len, candidate| {
+                    first[..len]
+                        .char_indices()
+                        .zip(candidate.chars())
+                        .find(|((_, a), b)| a != b)
+                        .map_or(len.min(candidate.len()), |((idx, _), _)| idx)
+                });
+                let common = first[..common_len].to_owned();
+                if common.len() > self.value.len().saturating_sub(start) {
+                    self.apply_candidate(start, &common);
+                }
+                self.completion =
+                    Some(Completion { start, candidates, selected: None, pending: false });
+            }
+        }
+    }
+
+    fn cycle_completion(&mut self, forward: bool) -> bool {
+        let Some(completion) = self.completion.as_mut().filter(|c| !c.candidates.is_empty()) else {
+            return false;
+        };
+
+        let len = completion.candidates.len();
+        let selected = match (completion.selected, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(idx), true) => (idx + 1) % len,
+            (Some(idx), false) => (idx + len - 1) % len,
+        };
+        completion.selected = Some(selected);
+
+        let start = completion.start;
+        let candidate = completion.candidates[selected].clone();
+        self.apply_candidate(start, &candidate);
+        true
+    }
+
+    fn execute(&mut self, context: &AppContext) {
+        let cmd = self.value.parse();
+        log::debug!("executing {cmd:?}");
+
+        match cmd {
+            Ok(Args { command: Some(cmd), .. }) => {
+                self.history.push(&self.value);
+                if context.work_sender.send(WorkRequest::Command(cmd)).is_err() {
+                    log::error!("Failed to send command");
+                }
+            }
+            Ok(Args { command: None, .. }) => {}
+            Err(err) => {
+                let message = err.to_string();
+                let message = message.lines().next().unwrap_or_default();
+                status_error!(
+                    "Invalid command '{}': {}",
+                    self.value.trim(),
+                    message.trim_start_matches("error: ")
+                );
+            }
+        }
+    }
+
+    fn hint_lines(&self, area: Rect, context: &AppContext) -> Vec<Line<'static>> {
+        let usage_line = |text: &str| Line::styled(text.to_owned(), context.config.as_text_style());
+        let (usage, about) = self.help.as_ref().map_or(("", ""), |(u, a)| (u.as_str(), a.as_str()));
+
+        match &self.completion {
+            Some(Completion { pending: true, .. }) => {
+                vec![usage_line("Loading completions..."), usage_line(usage)]
+            }
+            Some(Completion { candidates, selected, .. }) if !candidates.is_empty() => {
+                // Scroll the candidates so that the selected one is always visible
+                let selected_idx = selected.unwrap_or(0);
+                let width = |items: &[String]| -> usize {
+                    items.iter().map(|c| c.chars().count() + 2).sum()
+                };
+                let mut offset = 0;
+                while offset < selected_idx
+                    && width(&candidates[offset..=selected_idx]) > area.width as usize
+                {
+                    offset += 1;
+                }
+
+                let spans = candidates
+                    .iter()
+                    .enumerate()
+                    .skip(offset)
+                    .flat_map(|(idx, candidate)| {
+                        let style = if Some(idx) == *selected {
+                            context.config.theme.current_item_style
+                        } else {
+                            context.config.as_text_style()
+                        };
+                        [Span::styled(candidate.clone(), style), Span::raw("  ")]
+                    })
+                    .collect_vec();
+                vec![Line::from(spans), usage_line(usage)]
+            }
+            _ => vec![usage_line(usage), usage_line(about)],
+        }
+    }
+}
+
+impl Modal for CommandModal {
+    fn render(&mut self, frame: &mut Frame, app: &mut AppContext) -> Result<()> {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(app.config.as_border_style())
+            .title_alignment(ratatui::prelude::Alignment::Center)
+            .title("Execute a command");
+
+        let popup_area = frame.area().centered_exact(frame.area().width.min(80), 7);
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = app.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let inner = block.inner(popup_area);
+        let [input_area, hints_area] =
+            *Layout::vertical([Constraint::Length(3), Constraint::Length(2)]).split(inner)
+        else {
+            return Ok(());
+        };
+
+        let input = Input::default()
+            .set_label(":")
+            .set_label_style(app.config.as_text_style())
+            .set_text(&self.value)
+            .set_focused(true)
+            .set_focused_style(app.config.theme.highlight_border_style)
+            .set_unfocused_style(app.config.as_border_style());
+
+        frame.render_widget(block, popup_area);
+        frame.render_widget(input, input_area);
+        frame.render_widget(Paragraph::new(self.hint_lines(hints_area, app)), hints_area);
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: &mut KeyEvent, context: &mut AppContext) -> Result<()> {
+        match key.code() {
+            KeyCode::Tab => {
+                if !self.cycle_completion(true) {
+                    self.complete(context);
+                }
+                context.render()?;
+                return Ok(());
+            }
+            KeyCode::BackTab => {
+                self.cycle_completion(false);
+                context.render()?;
+                return Ok(());
+            }
+            KeyCode::Up => {
+                if let Some(entry) = self.history.prev(&self.value).map(ToOwned::to_owned) {
+                    self.set_value(entry);
+                    context.render()?;
+                }
+                return Ok(());
+            }
+            KeyCode::Down => {
+                if let Some(entry) = self.history.next().map(ToOwned::to_owned) {
+                    self.set_value(entry);
+                    context.render()?;
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        match key.as_common_action(context) {
+            Some(CommonAction::Close) => {
+                pop_modal!(context);
+                return Ok(());
+            }
+            Some(CommonAction::Confirm) => {
+                self.execute(context);
+                pop_modal!(context);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        match key.code() {
+            KeyCode::Char(c) => {
+                self.value.push(c);
+                self.history.reset();
+                self.on_value_changed();
+
+                context.render()?;
+            }
+            KeyCode::Backspace => {
+                self.value.pop();
+                self.history.reset();
+                self.on_value_changed();
+
+                context.render()?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, _event: MouseEvent, _context: &mut AppContext) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_query_finished(
+        &mut self,
+        id: &'static str,
+        data: &mut MpdQueryResult,
+        context: &AppContext,
+    ) -> Result<()> {
+        match (id, data) {
+            (COMMAND_COMPLETION, MpdQueryResult::CommandCompletions(candidates)) => {
+                // Ignore results for completions which were since discarded by editing
+                let Some(start) = self.completion.as_ref().filter(|c| c.pending).map(|c| c.start)
+                else {
+                    return Ok(());
+                };
+                self.on_completions(start, std::mem::take(candidates));
+                context.render()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+use std::{collections::HashMap, fmt::Display};
+
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Margin, Rect},
+    style::Style,
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Row, Table, TableState},
+};
+use serde::de::{DeserializeOwned, IntoDeserializer, value::Error as ValueError};
+use strum::{IntoDiscriminant, VariantArray};
+
+use super::{Modal, RectExt};
+use crate::{
+    config::keys::{
+        ChainStep,
+        CommonAction,
+        GlobalAction,
+        KeySequence,
+        ToDescription,
+        actions::{
+            AlbumsActionsFile,
+            ArtistsActionsFile,
+            CommonActionFile,
+            DirectoriesActionsFile,
+            GlobalActionFile,
+            PlaylistsActionsFile,
+            QueueActionsFile,
+            SearchActionsFile,
+        },
+    },
+    context::AppContext,
+    shared::{
+        events::AppEvent,
+        fuzzy::fuzzy_match,
+        key_event::KeyEvent,
+        macros::pop_modal,
+        mouse_event::{MouseEvent, MouseEventKind},
+    },
+    ui::{dirstack::DirState, widgets::input::Input},
+};
+
+#[derive(Debug)]
+struct PaletteEntry {
+    section: &'static str,
+    name: String,
+    description: String,
+    /// Keybinds currently bound to the action, empty if there are none
+    keys: String,
+    step: ChainStep,
+}
+
+#[derive(Debug)]
+struct PaletteMatch {
+    entry_idx: usize,
+    /// Matched characters of the description
+    indices: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub struct CommandPaletteModal {
+    entries: Vec<PaletteEntry>,
+    matches: Vec<PaletteMatch>,
+    filter: String,
+    scrolling_state: DirState<TableState>,
+    table_area: Rect,
+}
+
+/// Lists every action of a keybind section. Actions which are bound are
+/// listed as they are configured, together with all of their keybinds. Unbound
+/// actions are listed only if they do not need any arguments.
+fn section_entries<A, F>(
+    section: &'static str,
+    keybinds: &HashMap<KeySequence, A>,
+    to_step: impl Fn(A) -> ChainStep,
+) -> Vec<PaletteEntry>
+where
+    A: Clone + PartialEq + Display + ToDescription + IntoDiscriminant,
+    A::Discriminant: VariantArray + PartialEq + std::fmt::Debug,
+    F: DeserializeOwned + Into<A>,
+{
+    let mut result = Vec::new();
+    for variant in A::Discriminant::VARIANTS {
+        let mut bound: Vec<(A, Vec<String>)> = Vec::new();
+        for (key, action) in keybinds.iter().filter(|(_, action)| &action.discriminant() == variant)
+        {
+            match bound.iter_mut().find(|(a, _)| a == action) {
+                Some((_, keys)) => keys.push(key.to_string()),
+                None => bound.push((action.clone(), vec![key.to_string()])),
+            }
+        }
+
+        if bound.is_empty() {
+            let name = format!("{variant:?}");
+            if let Ok(action) =
+                F::deserialize(IntoDeserializer::<ValueError>::into_deserializer(name.as_str()))
+            {
+                bound.push((action.into(), Vec::new()));
+            }
+        }
+
+        result.extend(bound.into_iter().sorted_by_key(|(a, _)| a.to_string()).map(
+            |(action, keys)| PaletteEntry {
+                section,
+                name: action.to_string(),
+                description: action.to_description().into_owned(),
+                keys: keys.into_iter().sorted().join(" "),
+                step: to_step(action),
+            },
+        ));
+    }
+    result
+}
+
+impl CommandPaletteModal {
+    pub fn new(context: &AppContext) -> Self {
+        let keybinds = &context.config.keybinds;
+        let mut entries =
+            [
+                section_entries::<_, GlobalActionFile>("Global", &keybinds.global, |a| ChainStep {
+                    global: Some(a),
+                    ..Default::default()
+                }),
+                section_entries::<_, CommonActionFile>("Navigation", &keybinds.navigation, |a| {
+                    ChainStep { navigation: Some(a), ..Default::default() }
+                }),
+                section_entries::<_, QueueActionsFile>("Queue", &keybinds.queue, |a| ChainStep {
+                    queue: Some(a),
+                    ..Default::default()
+                }),
+                section_entries::<_, AlbumsActionsFile>("Albums", &keybinds.albums, |a| {
+                    ChainStep { albums: Some(a), ..Default::default() }
+                }),
+                section_entries::<_, ArtistsActionsFile>("Artists", &keybinds.artists, |a| {
+                    ChainStep { artists: Some(a), ..Default::default() }
+                }),
+                section_entries::<_, DirectoriesActionsFile>(
+                    "Directories",
+                    &keybinds.directories,
+                    |a| ChainStep { directories: Some(a), ..Default::default() },
+                ),
+                section_entries::<_, PlaylistsActionsFile>("Playlists", &keybinds.playlists, |a| {
+                    ChainStep { playlists: Some(a), ..Default::default() }
+                }),
+                section_entries::<_, SearchActionsFile>("Search", &keybinds.search, |a| {
+                    ChainStep { search: Some(a), ..Default::default() }
+                }),
+            ]
+            .into_iter()
+            .flatten()
+            .collect_vec();
+        entries.retain(|entry| entry.step.global != Some(GlobalAction::CommandPalette));
+
+        // Switching to a tab needs its name so unbound tabs have to be added separately
+        for tab in &context.config.tabs.names {
+            let action = GlobalAction::SwitchToTab(tab.clone());
+            if entries.iter().all(|entry| entry.step.global.as_ref() != Some(&action)) {
+                entries.push(PaletteEntry {
+                    section: "Global",
+                    name: action.to_string(),
+                    description: action.to_description().into_owned(),
+                    keys: String::new(),
+                    step: ChainStep { global: Some(action), ..Default::default() },
+                });
+            }
+        }
+
+        let mut result = Self {
+            entries,
+            matches: Vec::new(),
+            filter: String::new(),
+            scrolling_state: DirState::default(),
+            table_area: Rect::default(),
+        };
+        result.update_matches(context);
+        result
+    }
+
+    /// Filters and sorts the entries by how well their description matches the
+    /// filter. The action's name is used when the description does not match.
+    fn update_matches(&mut self, context: &AppContext) {
+        self.matches = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(entry_idx, entry)| {
+                fuzzy_match(&self.filter, &entry.description)
+                    .map(|m| (m.score, PaletteMatch { entry_idx, indices: m.indices }))
+                    .or_else(|| {
+                        fuzzy_match(&self.filter, &entry.name)
+                            .map(|m| (m.score - 1, PaletteMatch { entry_idx, indices: Vec::new() }))
+                    })
+            })
+            // Stable sort keeps the sections in order for equally good matches
+            .sorted_by_key(|(score, _)| std::cmp::Reverse(*score))
+            .map(|(_, m)| m)
+            .collect();
+
+        self.scrolling_state.set_content_len(Some(self.matches.len()));
+        if self.matches.is_empty() {
+            self.scrolling_state.select(None, context.config.scrolloff);
+        } else {
+            self.scrolling_state.first();
+        }
+    }
+
+    fn run_selected(&self, context: &AppContext) -> Result<()> {
+        let Some(entry) = self
+            .scrolling_state
+            .get_selected()
+            .and_then(|idx| self.matches.get(idx))
+            .and_then(|m| self.entries.get(m.entry_idx))
+        else {
+            pop_modal!(context);
+            return Ok(());
+        };
+
+        // The modal has to be closed first so that the action runs in the
+        // focused pane
+        pop_modal!(context);
+        context.app_event_sender.send(AppEvent::RunAction(entry.step.clone()))?;
+        Ok(())
+    }
+}
+
+impl Modal for CommandPaletteModal {
+    fn render(&mut self, frame: &mut Frame, app: &mut AppContext) -> Result<()> {
+        let popup_area = frame.area().centered(80, 70);
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = app.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(app.config.as_border_style())
+            .title_alignment(ratatui::prelude::Alignment::Center)
+            .title("Command palette");
+
+        let [input_area, table_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Percentage(100)])
+                .areas(block.inner(popup_area).inner(Margin { horizontal: 1, vertical: 0 }));
+
+        let input = Input::default()
+            .set_label_style(app.config.as_text_style())
+            .set_text(&self.filter)
+            .set_focused(true)
+            .set_focused_style(app.config.theme.highlight_border_style)
+            .set_unfocused_style(app.config.as_border_style());
+
+        let text_style = app.config.as_text_style();
+        let match_style = app.config.theme.highlighted_item_style;
+        let rows = self.matches.iter().filter_map(|m| {
+            let entry = self.entries.get(m.entry_idx)?;
+            let description = entry
+                .description
+                .chars()
+                .enumerate()
+                .map(|(idx, c)| {
+                    let style = if m.indices.contains(&idx) { match_style } else { text_style };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect_vec();
+
+            Some(Row::new([
+                Cell::from(Line::from(description)),
+                Cell::from(format!("{}: {}", entry.section, entry.name)),
+                Cell::from(entry.keys.as_str()),
+            ]))
+        });
+
+        let constraints =
+            [Constraint::Percentage(55), Constraint::Percentage(30), Constraint::Percentage(15)];
+        let table = Table::new(rows, constraints)
+            .column_spacing(1)
+            .style(text_style)
+            .row_highlight_style(app.config.theme.current_item_style);
+
+        self.table_area = table_area;
+        self.scrolling_state.set_viewport_len(Some(table_area.height.into()));
+
+        frame.render_widget(block, popup_area);
+        frame.render_widget(input, input_area);
+        frame.render_stateful_widget(table, table_area, self.scrolling_state.as_render_state_ref());
+        if let Some(scrollbar) = app.config.as_styled_scrollbar() {
+            frame.render_stateful_widget(
+                scrollbar,
+                popup_area.inner(Margin { horizontal: 0, vertical: 1 }),
+                self.scrolling_state.as_scrollbar_state_ref(),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: &mut KeyEvent, context: &mut AppContext) -> Result<()> {
+        // Letters are used for the filter so only the arrow keys move the selection
+        match key.code() {
+            KeyCode::Up => {
+                self.scrolling_state.prev(context.config.scrolloff, context.config.wrap_navigation);
+                context.render()?;
+                return Ok(());
+            }
+            KeyCode::Down => {
+                self.scrolling_state.next(context.config.scrolloff, context.config.wrap_navigation);
+                context.render()?;
+                return Ok(());
+            }
+            KeyCode::PageUp => {
+                self.scrolling_state.prev_viewport(context.config.scrolloff);
+                context.render()?;
+                return Ok(());
+            }
+            KeyCode::PageDown => {
+                self.scrolling_state.next_viewport(context.config.scrolloff);
+                context.render()?;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        match key.as_common_action(context) {
+            Some(CommonAction::Close) => {
+                pop_modal!(context);
+                return Ok(());
+            }
+            Some(CommonAction::Confirm) => {
+                return self.run_selected(context);
+            }
+            _ => {}
+        }
+
+        match key.code() {
+            KeyCode::Char(c) => {
+                self.filter.push(c);
+                self.update_matches(context);
+                context.render()?;
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.update_matches(context);
+                context.render()?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEvent, context: &mut AppContext) -> Result<()> {
+        if !self.table_area.contains(event.into()) {
+            return Ok(());
+        }
+
+        match event.kind {
+            MouseEventKind::LeftClick => {
+                let y: usize = event.y.saturating_sub(self.table_area.y).into();
+                if let Some(idx) = self.scrolling_state.get_at_rendered_row(y) {
+                    self.scrolling_state.select(Some(idx), context.config.scrolloff);
+                    context.render()?;
+                }
+            }
+            MouseEventKind::DoubleClick => {
+                self.run_selected(context)?;
+            }
+            MouseEventKind::ScrollDown => {
+                self.scrolling_state.next(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::ScrollUp => {
+                self.scrolling_state.prev(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::MiddleClick | MouseEventKind::RightClick => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::{
+        config::keys::{GlobalAction, QueueActions},
+        tests::fixtures::app_context,
+    };
+
+    fn find<'a>(modal: &'a CommandPaletteModal, step: &ChainStep) -> Option<&'a PaletteEntry> {
+        modal.entries.iter().find(|entry| &entry.step == step)
+    }
+
+    #[rstest]
+    fn lists_bound_and_unbound_actions(app_context: AppContext) {
+        let modal = CommandPaletteModal::new(&app_context);
+
+        let quit = ChainStep { global: Some(GlobalAction::Quit), ..Default::default() };
+        let unbound =
+            ChainStep { global: Some(GlobalAction::ToggleSingleOnOff), ..Default::default() };
+        let queue = ChainStep { queue: Some(QueueActions::Delete), ..Default::default() };
+        let palette =
+            ChainStep { global: Some(GlobalAction::CommandPalette), ..Default::default() };
+
+        assert_eq!(find(&modal, &quit).map(|entry| entry.keys.as_str()), Some("q"));
+        assert_eq!(find(&modal, &unbound).map(|entry| entry.keys.as_str()), Some(""));
+        assert_eq!(find(&modal, &queue).map(|entry| entry.section), Some("Queue"));
+        assert!(find(&modal, &palette).is_none());
+    }
+
+    #[rstest]
+    fn filters_by_description(app_context: AppContext) {
+        let mut modal = CommandPaletteModal::new(&app_context);
+
+        modal.filter = "raise vol".to_owned();
+        modal.update_matches(&app_context);
+
+        let best = &modal.entries[modal.matches[0].entry_idx];
+        assert_eq!(best.step.global, Some(GlobalAction::VolumeUp));
+        assert!(modal.matches.len() < modal.entries.len());
+    }
+}
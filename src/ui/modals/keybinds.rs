@@ -243,53 +243,38 @@ impl Modal for KeybindsModal {
         let keybinds = &app.config.keybinds;
         let header_style = app.config.theme.current_item_style;
 
-        let global = keybinds.global.sort_by_action().collect_vec();
-        let navigation = keybinds.navigation.sort_by_action().collect_vec();
-        let queue = keybinds.queue.sort_by_action().collect_vec();
-        let global_rows: (Vec<_>, Vec<_>) = row(
-            &global,
-            key_area.width,
-            action_area.width,
-            desc_area.width,
-            self.filter.as_deref(),
-            app.config.theme.highlighted_item_style,
-        )
-        .unzip();
-        let nav_rows: (Vec<_>, Vec<_>) = row(
-            &navigation,
-            key_area.width,
-            action_area.width,
-            desc_area.width,
-            self.filter.as_deref(),
-            app.config.theme.highlighted_item_style,
-        )
-        .unzip();
-        let queue_rows: (Vec<_>, Vec<_>) = row(
-            &queue,
-            key_area.width,
-            action_area.width,
-            desc_area.width,
-            self.filter.as_deref(),
-            app.config.theme.highlighted_item_style,
-        )
-        .unzip();
-
-        let rows = row_header(&global, "Global", header_style)
-            .into_iter()
-            .chain(global_rows.1)
-            .chain(row_header(&navigation, "Navigation", header_style))
-            .chain(nav_rows.1)
-            .chain(row_header(&queue, "Queue", header_style))
-            .chain(queue_rows.1)
-            .collect_vec();
+        let sections = [
+            ("Global", keybinds.global.sort_by_action().collect_vec()),
+            ("Navigation", keybinds.navigation.sort_by_action().collect_vec()),
+            ("Queue", keybinds.queue.sort_by_action().collect_vec()),
+            ("Albums", keybinds.albums.sort_by_action().collect_vec()),
+            ("Artists", keybinds.artists.sort_by_action().collect_vec()),
+            ("Directories", keybinds.directories.sort_by_action().collect_vec()),
+            ("Playlists", keybinds.playlists.sort_by_action().collect_vec()),
+            ("Search", keybinds.search.sort_by_action().collect_vec()),
+        ];
 
+        let mut rows = Vec::new();
         self.filter_rows = Vec::new();
-        self.filter_rows.push(None);
-        self.filter_rows.extend(global_rows.0.into_iter().map(Some));
-        self.filter_rows.push(None);
-        self.filter_rows.extend(nav_rows.0.into_iter().map(Some));
-        self.filter_rows.push(None);
-        self.filter_rows.extend(queue_rows.0.into_iter().map(Some));
+        for (name, keys) in &sections {
+            let Some(header) = row_header(keys, name, header_style) else {
+                continue;
+            };
+            let (filter_rows, section_rows): (Vec<_>, Vec<_>) = row(
+                keys,
+                key_area.width,
+                action_area.width,
+                desc_area.width,
+                self.filter.as_deref(),
+                app.config.theme.highlighted_item_style,
+            )
+            .unzip();
+
+            rows.push(header);
+            rows.extend(section_rows);
+            self.filter_rows.push(None);
+            self.filter_rows.extend(filter_rows.into_iter().map(Some));
+        }
 
         self.scrolling_state.set_content_len(Some(rows.len()));
         self.scrolling_state.set_viewport_len(Some(table_area.height.into()));
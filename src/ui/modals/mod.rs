@@ -14,6 +14,8 @@ use crate::{
 };
 
 pub mod add_random_modal;
+pub mod command_modal;
+pub mod command_palette;
 pub mod confirm_modal;
 pub mod decoders;
 pub mod info_list_modal;
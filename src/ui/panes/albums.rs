@@ -2,10 +2,15 @@ use anyhow::{Context, Result, anyhow};
 use itertools::Itertools;
 use ratatui::{Frame, prelude::Rect};
 
-use super::Pane;
+use super::{Pane, PaneRemoteState};
 use crate::{
     MpdQueryResult,
-    config::{sort_mode::SortOptions, tabs::PaneType},
+    config::{
+        artists::{AlbumDisplayMode, AlbumSortMode},
+        keys::AlbumsActions,
+        sort_mode::SortOptions,
+        tabs::PaneType,
+    },
     context::AppContext,
     mpd::{
         QueuePosition,
@@ -36,6 +41,34 @@ pub struct AlbumsPane {
     filter_input_mode: bool,
     browser: Browser<DirOrSong>,
     initialized: bool,
+    reverse: bool,
+    album_display_mode: AlbumDisplayMode,
+    album_sort_by: AlbumSortMode,
+    albums_with_date: Vec<(String, String)>,
+    albums: Vec<AlbumEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AlbumEntry {
+    /// Name shown in the list and used as its path in the stack
+    name: String,
+    album: String,
+    /// Only set when albums are split by date
+    date: Option<String>,
+}
+
+impl AlbumEntry {
+    fn name_only(album: &str) -> Self {
+        Self { name: album.to_owned(), album: album.to_owned(), date: None }
+    }
+
+    fn filter(&self) -> Vec<Filter<'static>> {
+        let mut filter = vec![Filter::new(Tag::Album, self.album.clone())];
+        if let Some(date) = &self.date {
+            filter.push(Filter::new(Tag::Custom("Date".to_owned()), date.clone()));
+        }
+        filter
+    }
 }
 
 const INIT: &str = "init";
@@ -49,7 +82,99 @@ impl AlbumsPane {
             filter_input_mode: false,
             browser: Browser::new(),
             initialized: false,
+            reverse: false,
+            album_display_mode: AlbumDisplayMode::NameOnly,
+            album_sort_by: AlbumSortMode::Name,
+            albums_with_date: Vec::new(),
+            albums: Vec::new(),
+        }
+    }
+
+    fn entry(&self, name: &str) -> AlbumEntry {
+        self.albums
+            .iter()
+            .find(|entry| entry.name == name)
+            .cloned()
+            .unwrap_or_else(|| AlbumEntry::name_only(name))
+    }
+
+    fn root(&self) -> Vec<DirOrSong> {
+        let mut root =
+            self.albums.iter().map(|entry| DirOrSong::name_only(entry.name.clone())).collect_vec();
+        if self.reverse {
+            root.reverse();
         }
+        root
+    }
+
+    /// Groups the already fetched albums again after the sort or display mode
+    /// changed and keeps the cursor on the same album.
+    fn regroup_albums(&mut self, context: &AppContext) -> Result<()> {
+        let selected = match self.stack.path() {
+            [] => self.stack.current().selected().map(DirStackItem::as_path),
+            [album, ..] => Some(album.as_str()),
+        }
+        .map(|name| self.entry(name).album);
+
+        self.albums =
+            group_albums(&self.albums_with_date, self.album_sort_by, self.album_display_mode);
+        self.stack = DirStack::new(self.root());
+
+        let idx = selected.and_then(|album| {
+            self.stack
+                .current()
+                .items
+                .iter()
+                .position(|item| self.entry(item.as_path()).album == album)
+        });
+        if let Some(idx) = idx {
+            self.stack.current_mut().select_idx(idx, context.config.scrolloff);
+        }
+
+        self.prepare_preview(context)?;
+        context.render()?;
+
+        Ok(())
+    }
+
+    fn handle_albums_action(&mut self, event: &mut KeyEvent, context: &AppContext) -> Result<()> {
+        let Some(action) = event.as_albums_action(context) else {
+            return Ok(());
+        };
+
+        match action {
+            AlbumsActions::ToggleReverseSort => {
+                self.reverse = !self.reverse;
+                let root = if self.stack.path().is_empty() {
+                    self.stack.current_mut()
+                } else {
+                    self.stack.previous_mut()
+                };
+                root.reverse(context.config.scrolloff);
+                if self.stack.path().is_empty() {
+                    self.prepare_preview(context)?;
+                }
+                context.render()?;
+            }
+            AlbumsActions::CycleAlbumSortMode => {
+                self.album_sort_by = self.album_sort_by.cycle();
+                match self.album_sort_by {
+                    AlbumSortMode::Name => status_info!("Sorting albums by name"),
+                    AlbumSortMode::Date => status_info!("Sorting albums by date"),
+                }
+                self.regroup_albums(context)?;
+            }
+            AlbumsActions::CycleAlbumDisplayMode => {
+                self.album_display_mode = self.album_display_mode.cycle();
+                match self.album_display_mode {
+                    AlbumDisplayMode::SplitByDate => status_info!("Splitting albums by date"),
+                    AlbumDisplayMode::NameOnly => status_info!("Showing only album names"),
+                }
+                self.regroup_albums(context)?;
+            }
+        }
+
+        Ok(())
     }
 
     fn open_or_play(&mut self, autoplay: bool, context: &AppContext) -> Result<()> {
@@ -71,7 +196,7 @@ impl AlbumsPane {
                 }
             }
             [] => {
-                let current = current.clone();
+                let filter = self.entry(current.as_path()).filter();
                 let sort_order = context.config.browser_song_sort.clone();
                 context
                     .query()
@@ -79,7 +204,7 @@ impl AlbumsPane {
                     .replace_id(OPEN_OR_PLAY)
                     .target(PaneType::Albums)
                     .query(move |client| {
-                        let data = list_titles(client, current.as_path(), &sort_order)?.collect();
+                        let data = list_titles(client, &filter, &sort_order)?.collect();
                         Ok(MpdQueryResult::DirOrSong { data, origin_path: Some(next_path) })
                     });
                 self.stack_mut().push(Vec::new());
@@ -112,8 +237,8 @@ impl Pane for AlbumsPane {
         if !self.initialized {
             context.query().id(INIT).replace_id(INIT).target(PaneType::Albums).query(
                 move |client| {
-                    let result = client.list_tag(Tag::Album, None).context("Cannot list tags")?;
-                    Ok(MpdQueryResult::LsInfo { data: result.0, origin_path: None })
+                    let result = client.list_albums_with_date().context("Cannot list albums")?;
+                    Ok(MpdQueryResult::AlbumsWithDate(result.albums))
                 },
             );
             self.initialized = true;
@@ -133,8 +258,8 @@ impl Pane for AlbumsPane {
                 context.query().id(INIT).replace_id(INIT).target(PaneType::Albums).query(
                     move |client| {
                         let result =
-                            client.list_tag(Tag::Album, None).context("Cannot list tags")?;
-                        Ok(MpdQueryResult::LsInfo { data: result.0, origin_path: None })
+                            client.list_albums_with_date().context("Cannot list albums")?;
+                        Ok(MpdQueryResult::AlbumsWithDate(result.albums))
                     },
                 );
             }
@@ -153,6 +278,7 @@ impl Pane for AlbumsPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, context: &mut AppContext) -> Result<()> {
         self.handle_filter_input(event, context)?;
+        self.handle_albums_action(event, context)?;
         self.handle_common_action(event, context)?;
         self.handle_global_action(event, context)?;
         Ok(())
@@ -176,9 +302,10 @@ impl Pane for AlbumsPane {
                 self.stack_mut().set_preview(data);
                 context.render()?;
             }
-            (INIT, MpdQueryResult::LsInfo { data, origin_path: _ }) => {
-                let root = data.into_iter().map(DirOrSong::name_only).collect_vec();
-                self.stack = DirStack::new(root);
+            (INIT, MpdQueryResult::AlbumsWithDate(data)) => {
+                self.albums = group_albums(&data, self.album_sort_by, self.album_display_mode);
+                self.albums_with_date = data;
+                self.stack = DirStack::new(self.root());
                 self.prepare_preview(context)?;
             }
             (OPEN_OR_PLAY, MpdQueryResult::DirOrSong { data, origin_path }) => {
@@ -196,15 +323,45 @@ impl Pane for AlbumsPane {
         }
         Ok(())
     }
+
+    fn remote_state(&mut self, _context: &AppContext) -> Result<PaneRemoteState> {
+        Ok(PaneRemoteState::from_dir(self.stack().current()))
+    }
+}
+
+/// Turns the albums paired with their dates into the list entries for the
+/// given sort and display mode. Albums are merged by name unless they are
+/// split by date.
+fn group_albums(
+    albums: &[(String, String)],
+    sort_mode: AlbumSortMode,
+    display_mode: AlbumDisplayMode,
+) -> Vec<AlbumEntry> {
+    albums
+        .iter()
+        .sorted_by(|(album_a, date_a), (album_b, date_b)| match sort_mode {
+            AlbumSortMode::Name => album_a.cmp(album_b).then_with(|| date_a.cmp(date_b)),
+            AlbumSortMode::Date => date_a.cmp(date_b).then_with(|| album_a.cmp(album_b)),
+        })
+        .map(|(album, date)| match display_mode {
+            AlbumDisplayMode::SplitByDate => AlbumEntry {
+                name: format!("({}) {album}", if date.is_empty() { "<no date>" } else { date }),
+                album: album.clone(),
+                date: Some(date.clone()),
+            },
+            AlbumDisplayMode::NameOnly => AlbumEntry::name_only(album),
+        })
+        .unique_by(|entry| entry.name.clone())
+        .collect()
 }
 
 fn list_titles(
     client: &mut impl MpdClient,
-    album: &str,
+    filter: &[Filter<'_>],
     sort_opts: &SortOptions,
 ) -> Result<impl Iterator<Item = DirOrSong>, MpdError> {
     Ok(client
-        .find(&[Filter::new(Tag::Album, album)])?
+        .find(filter)?
         .into_iter()
         .sorted_by(|a, b| a.with_custom_sort(sort_opts).cmp(&b.with_custom_sort(sort_opts)))
         .map(DirOrSong::Song))
@@ -212,18 +369,20 @@ fn list_titles(
 
 fn find_songs(
     client: &mut impl MpdClient,
-    album: &str,
+    album: &AlbumEntry,
     file: &str,
     sort_opts: &SortOptions,
 ) -> Result<Song, MpdError> {
+    let mut filter = album.filter();
+    filter.push(Filter::new(Tag::File, file));
     Ok(client
-        .find(&[Filter::new(Tag::File, file), Filter::new(Tag::Album, album)])?
+        .find(&filter)?
         .into_iter()
         .sorted_by(|a, b| a.with_custom_sort(sort_opts).cmp(&b.with_custom_sort(sort_opts)))
         .next()
         .context(anyhow!(
             "Expected to find exactly one song: album: '{}', current: '{}'",
-            album,
+            album.name,
             file
         ))?)
 }
@@ -249,8 +408,12 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
         &self,
         item: DirOrSong,
     ) -> impl FnOnce(&mut Client<'_>) -> Result<Vec<Song>> + 'static {
+        let filter = match &item {
+            DirOrSong::Dir { name, .. } => self.entry(name).filter(),
+            DirOrSong::Song(_) => Vec::new(),
+        };
         move |client| match item {
-            DirOrSong::Dir { name, .. } => Ok(client.find(&[Filter::new(Tag::Album, &name)])?),
+            DirOrSong::Dir { .. } => Ok(client.find(&filter)?),
             DirOrSong::Song(song) => Ok(vec![song.clone()]),
         }
     }
@@ -271,13 +434,11 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
     ) -> Result<()> {
         match self.stack.path() {
             [album] => {
-                let album = album.clone();
+                let mut filter = self.entry(album).filter();
                 let name = item.dir_name_or_file_name().into_owned();
+                filter.push(Filter::new(Tag::File, name.clone()));
                 context.command(move |client| {
-                    client.find_add(
-                        &[Filter::new(Tag::File, &name), Filter::new(Tag::Album, album.as_str())],
-                        position,
-                    )?;
+                    client.find_add(&filter, position)?;
 
                     status_info!("'{name}' added to queue");
                     Ok(())
@@ -285,8 +446,9 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
             }
             [] => {
                 let name = item.dir_name_or_file_name().into_owned();
+                let filter = self.entry(&name).filter();
                 context.command(move |client| {
-                    client.find_add(&[Filter::new(Tag::Album, &name)], position)?;
+                    client.find_add(&filter, position)?;
 
                     status_info!("Album '{name}' added to queue");
                     Ok(())
@@ -302,8 +464,9 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
         match self.stack.path() {
             [album] => {
                 let album = album.clone();
+                let filter = self.entry(&album).filter();
                 context.command(move |client| {
-                    client.find_add(&[Filter::new(Tag::Album, album.as_str())], position)?;
+                    client.find_add(&filter, position)?;
                     status_info!("Album '{}' added to queue", album);
                     Ok(())
                 });
@@ -331,7 +494,7 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
         self.stack_mut().clear_preview();
         match self.stack.path() {
             [album] => {
-                let album = album.clone();
+                let album = self.entry(album);
                 let sort_order = context.config.browser_song_sort.clone();
                 context
                     .query()
@@ -348,6 +511,7 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
                     });
             }
             [] => {
+                let filter = self.entry(&current).filter();
                 let sort_order = context.config.browser_song_sort.clone();
                 context
                     .query()
@@ -355,7 +519,7 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
                     .replace_id("albums_preview")
                     .target(PaneType::Albums)
                     .query(move |client| {
-                        let data = list_titles(client, &current, &sort_order)?
+                        let data = list_titles(client, &filter, &sort_order)?
                             .map(|v| v.to_list_item_simple(&config))
                             .collect_vec();
                         let data = PreviewGroup::from(None, None, data);
@@ -374,3 +538,51 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
         self.browser.areas
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn albums() -> Vec<(String, String)> {
+        [("B", "2001"), ("A", "2005"), ("A", ""), ("C", "1999")]
+            .into_iter()
+            .map(|(album, date)| (album.to_owned(), date.to_owned()))
+            .collect()
+    }
+
+    fn names(entries: &[AlbumEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn name_only_sorted_by_name_merges_dates() {
+        let result = group_albums(&albums(), AlbumSortMode::Name, AlbumDisplayMode::NameOnly);
+
+        assert_eq!(names(&result), vec!["A", "B", "C"]);
+        assert_eq!(result[0].filter().len(), 1);
+    }
+
+    #[test]
+    fn split_by_date_sorted_by_date() {
+        let result = group_albums(&albums(), AlbumSortMode::Date, AlbumDisplayMode::SplitByDate);
+
+        assert_eq!(names(&result), vec!["(<no date>) A", "(1999) C", "(2001) B", "(2005) A"]);
+        assert_eq!(result[3].album, "A");
+        assert_eq!(result[3].date.as_deref(), Some("2005"));
+        assert_eq!(result[3].filter().len(), 2);
+    }
+
+    #[test]
+    fn split_by_date_sorted_by_name() {
+        let result = group_albums(&albums(), AlbumSortMode::Name, AlbumDisplayMode::SplitByDate);
+
+        assert_eq!(names(&result), vec!["(<no date>) A", "(2005) A", "(2001) B", "(1999) C"]);
+    }
+
+    #[test]
+    fn name_only_sorted_by_date_keeps_earliest_date() {
+        let result = group_albums(&albums(), AlbumSortMode::Date, AlbumDisplayMode::NameOnly);
+
+        assert_eq!(names(&result), vec!["A", "C", "B"]);
+    }
+}
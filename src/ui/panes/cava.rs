@@ -10,14 +10,18 @@ use crossbeam::channel::{Receiver, RecvError, Sender, TryRecvError};
 use crossterm::{
     cursor::{MoveTo, RestorePosition, SavePosition},
     queue,
-    style::{Colors, PrintStyledContent, Stylize},
+    style::{Color as CrosstermColor, Colors, PrintStyledContent, Stylize},
     terminal::{BeginSynchronizedUpdate, EndSynchronizedUpdate},
 };
 use ratatui::{Frame, layout::Rect};
 
+use self::builtin::BuiltinVisualizer;
 use super::Pane;
 use crate::{
-    config::{cava::Cava, theme::cava::CavaTheme},
+    config::{
+        cava::{Cava, CavaBackend},
+        theme::cava::{CavaColor, CavaMode, CavaTheme},
+    },
     context::AppContext,
     mpd::commands::State,
     shared::{
@@ -30,6 +34,8 @@ use crate::{
     ui::{UiEvent, image::clear_area},
 };
 
+mod builtin;
+
 #[derive(Debug)]
 pub struct CavaPane {
     area: Rect,
@@ -43,7 +49,7 @@ enum CavaCommand {
     Start { area: Rect },
     Stop,
     Pause,
-    ConfigChanged { config: Cava, theme: CavaTheme },
+    ConfigChanged { config: Cava, theme: Box<CavaTheme> },
 }
 
 struct ProcessGuard {
@@ -62,6 +68,128 @@ impl Drop for ProcessGuard {
     }
 }
 
+/// Source of the visualiser data, either the external cava process or the
+/// builtin FFT reading directly from MPD's fifo output.
+enum CavaSource {
+    Process { process: ProcessGuard, buf: Vec<u8> },
+    Builtin(Box<BuiltinVisualizer>),
+}
+
+impl CavaSource {
+    /// Creates source producing `bars` values for each of the `channels`. The
+    /// channels are laid out one after another, left first.
+    fn new(bars: u16, channels: usize, config: &Cava) -> Result<Self> {
+        let total_bars = bars * u16::try_from(channels)?;
+        Ok(match config.backend {
+            CavaBackend::Cava => CavaSource::Process {
+                process: CavaPane::spawn_cava(total_bars, channels, config)?,
+                buf: vec![0_u8; 2 * total_bars as usize],
+            },
+            CavaBackend::Builtin => {
+                CavaSource::Builtin(Box::new(BuiltinVisualizer::new(bars, channels, config)?))
+            }
+        })
+    }
+
+    fn read(&mut self, max: u16, channels: usize, columns: &mut [f32]) -> Result<()> {
+        match self {
+            CavaSource::Process { process, buf } => {
+                let stdout =
+                    process.handle.stdout.as_mut().context("Failed to spawn cava. No stdout.")?;
+                let stderr =
+                    process.handle.stderr.as_mut().context("Failed to spawn cava. No stderr.")?;
+                CavaPane::read_cava_data(max, buf, columns, stdout, stderr)?;
+                if channels == 2 {
+                    // cava outputs the left channel reversed so the lowest
+                    // frequencies meet in the middle
+                    let half = columns.len() / 2;
+                    columns[..half].reverse();
+                }
+                Ok(())
+            }
+            CavaSource::Builtin(visualizer) => visualizer.read(max, columns),
+        }
+    }
+}
+
+/// Geometry of the visualiser inside its area. Depends on the render mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CavaLayout {
+    /// Number of bars per channel
+    pub bars: u16,
+    /// Offset of the first bar from the start of the area so that the bars
+    /// are centered
+    pub offset: u16,
+    /// Length of a fully filled bar in cells
+    pub max: u16,
+    pub channels: usize,
+}
+
+impl CavaLayout {
+    pub fn new(area: Rect, theme: &CavaTheme) -> Self {
+        let channels = theme.mode.channels();
+        let bar_size = theme.bar_width + theme.bar_spacing;
+        let centered = |len: u16| {
+            let bars = len / bar_size.max(1);
+            let total = (bars * bar_size).saturating_sub(theme.bar_spacing);
+            (bars, len.saturating_sub(total) / 2)
+        };
+
+        match theme.mode {
+            CavaMode::Bars => {
+                let (bars, offset) = centered(area.width);
+                Self { bars, offset, max: area.height, channels }
+            }
+            CavaMode::Mirrored { .. } | CavaMode::Stereo { .. } => {
+                let (bars, offset) = centered(area.width);
+                Self { bars, offset, max: area.height.div_ceil(2), channels }
+            }
+            CavaMode::Horizontal { .. } => {
+                let (bars, offset) = centered(area.height);
+                Self { bars, offset, max: area.width, channels }
+            }
+            CavaMode::Braille { .. } => {
+                Self { bars: area.width * 2, offset: 0, max: area.height, channels }
+            }
+        }
+    }
+}
+
+/// Picks the symbol which best represents the fill amount in range `0..1`.
+/// Returns `None` if the cell should be empty.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+#[inline]
+fn fill_symbol(fill_amount: f32, symbols: &[String]) -> Option<&str> {
+    if fill_amount < 0.01 {
+        return None;
+    }
+    let idx = (fill_amount.clamp(0.0, 0.99) * symbols.len() as f32).floor() as usize;
+    symbols.get(idx).map(String::as_str)
+}
+
+/// Symbols only exist for cells filled from the bottom or from the left. To
+/// fill a cell from the opposite side, the remaining part is drawn in the
+/// background color over a cell colored with the bar color.
+#[inline]
+fn queue_inverted_fill(
+    writer: &mut impl Write,
+    fill_amount: f32,
+    symbols: &[String],
+    empty_bar_symbol: &str,
+    color: CrosstermColor,
+    bg_color: CrosstermColor,
+) -> Result<()> {
+    if fill_amount < 0.01 {
+        queue!(writer, PrintStyledContent(empty_bar_symbol.on(bg_color)))?;
+    } else if let Some(symbol) = fill_symbol(1.0 - fill_amount, symbols) {
+        queue!(writer, PrintStyledContent(symbol.with(bg_color).on(color)))?;
+    } else {
+        let full = symbols.last().map_or(empty_bar_symbol, String::as_str);
+        queue!(writer, PrintStyledContent(full.with(color).on(bg_color)))?;
+    }
+    Ok(())
+}
+
 impl CavaPane {
     pub fn new(_context: &AppContext) -> Self {
         Self {
@@ -101,52 +229,264 @@ impl CavaPane {
         Ok(())
     }
 
-    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     #[inline]
     pub fn render_cava(
         writer: &TtyWriter,
         area: Rect,
         columns: &mut [f32],
-        x_offset: u16,
+        layout: &CavaLayout,
         empty_bar_symbol: &str,
         theme: &CavaTheme,
     ) -> Result<()> {
-        let height = area.height;
         let mut writer = writer.lock();
 
         queue!(writer, BeginSynchronizedUpdate, SavePosition)?;
 
+        let bars = layout.bars as usize;
+        match &theme.mode {
+            CavaMode::Bars => {
+                Self::queue_bars(&mut *writer, area, columns, layout, empty_bar_symbol, theme)?;
+            }
+            CavaMode::Mirrored { reflection_color } => {
+                let reflection_color = reflection_color.as_ref().unwrap_or(&theme.bar_color);
+                Self::queue_split_bars(
+                    &mut *writer,
+                    area,
+                    (columns, columns),
+                    layout,
+                    reflection_color,
+                    empty_bar_symbol,
+                    theme,
+                )?;
+            }
+            CavaMode::Stereo { right_color } => {
+                let right_color = right_color.as_ref().unwrap_or(&theme.bar_color);
+                let (left, right) = columns.split_at(bars.min(columns.len()));
+                Self::queue_split_bars(
+                    &mut *writer,
+                    area,
+                    (left, right),
+                    layout,
+                    right_color,
+                    empty_bar_symbol,
+                    theme,
+                )?;
+            }
+            CavaMode::Horizontal { bar_symbols, reverse } => {
+                Self::queue_horizontal_bars(
+                    &mut *writer,
+                    area,
+                    columns,
+                    layout,
+                    bar_symbols,
+                    *reverse,
+                    theme,
+                )?;
+            }
+            CavaMode::Braille { fill } => {
+                Self::queue_braille(&mut *writer, area, columns, *fill, theme)?;
+            }
+        }
+
+        queue!(writer, RestorePosition, EndSynchronizedUpdate)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn bar_position(area_start: u16, layout: &CavaLayout, idx: usize, theme: &CavaTheme) -> u16 {
+        #[allow(clippy::cast_possible_truncation)]
+        let idx = idx as u16;
+        area_start + layout.offset + idx * theme.bar_width + idx * theme.bar_spacing
+    }
+
+    #[inline]
+    fn queue_bars(
+        writer: &mut impl Write,
+        area: Rect,
+        columns: &[f32],
+        layout: &CavaLayout,
+        empty_bar_symbol: &str,
+        theme: &CavaTheme,
+    ) -> Result<()> {
+        let height = area.height;
         for (col_idx, column) in columns.iter().enumerate() {
-            let col_idx = col_idx as u16;
-            let x = area.x + x_offset + col_idx * theme.bar_width + col_idx * theme.bar_spacing;
+            let x = Self::bar_position(area.x, layout, col_idx, theme);
 
             for y in 0..height {
                 let h = area.y + (height - 1) - y;
                 let color = theme.bar_color.get_color(y as usize, area.height);
-                let fill_amount = (*column - f32::from(y)).clamp(0.0, 0.99);
                 queue!(writer, MoveTo(x, h))?;
-                if fill_amount < 0.01 {
-                    queue!(writer, PrintStyledContent(empty_bar_symbol.on(theme.bg_color)))?;
-                } else {
-                    let char_index =
-                        (fill_amount * theme.bar_symbols_count as f32).floor() as usize;
-                    let fill_char = theme.bar_symbols[char_index].as_str();
-                    queue!(writer, PrintStyledContent(fill_char.with(color).on(theme.bg_color)))?;
+                match fill_symbol(*column - f32::from(y), &theme.bar_symbols) {
+                    Some(fill_char) => queue!(
+                        writer,
+                        PrintStyledContent(fill_char.with(color).on(theme.bg_color))
+                    )?,
+                    None => {
+                        queue!(writer, PrintStyledContent(empty_bar_symbol.on(theme.bg_color)))?;
+                    }
                 }
             }
         }
+        Ok(())
+    }
 
-        queue!(writer, RestorePosition, EndSynchronizedUpdate)?;
-        writer.flush()?;
+    /// Draws bars growing up from the middle of the area using the `top`
+    /// values and bars growing down using the `bottom` values.
+    #[inline]
+    fn queue_split_bars(
+        writer: &mut impl Write,
+        area: Rect,
+        (top, bottom): (&[f32], &[f32]),
+        layout: &CavaLayout,
+        bottom_color: &CavaColor,
+        empty_bar_symbol: &str,
+        theme: &CavaTheme,
+    ) -> Result<()> {
+        let top_height = layout.max;
+        let bottom_height = area.height - top_height;
+        let bottom_scale = f32::from(bottom_height) / f32::from(top_height.max(1));
+
+        for (col_idx, (top_value, bottom_value)) in top.iter().zip(bottom).enumerate() {
+            let x = Self::bar_position(area.x, layout, col_idx, theme);
+
+            for y in 0..top_height {
+                let h = area.y + (top_height - 1) - y;
+                let color = theme.bar_color.get_color(y as usize, top_height);
+                queue!(writer, MoveTo(x, h))?;
+                match fill_symbol(*top_value - f32::from(y), &theme.bar_symbols) {
+                    Some(fill_char) => queue!(
+                        writer,
+                        PrintStyledContent(fill_char.with(color).on(theme.bg_color))
+                    )?,
+                    None => {
+                        queue!(writer, PrintStyledContent(empty_bar_symbol.on(theme.bg_color)))?;
+                    }
+                }
+            }
+
+            let bottom_value = bottom_value * bottom_scale;
+            for y in 0..bottom_height {
+                let h = area.y + top_height + y;
+                let color = bottom_color.get_color(y as usize, bottom_height);
+                queue!(writer, MoveTo(x, h))?;
+                queue_inverted_fill(
+                    writer,
+                    (bottom_value - f32::from(y)).clamp(0.0, 0.99),
+                    &theme.bar_symbols,
+                    empty_bar_symbol,
+                    color,
+                    theme.bg_color,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn queue_horizontal_bars(
+        writer: &mut impl Write,
+        area: Rect,
+        columns: &[f32],
+        layout: &CavaLayout,
+        bar_symbols: &[String],
+        reverse: bool,
+        theme: &CavaTheme,
+    ) -> Result<()> {
+        let width = area.width;
+        for (bar_idx, value) in columns.iter().enumerate() {
+            let y = Self::bar_position(area.y, layout, bar_idx, theme);
+
+            for row in y..y + theme.bar_width {
+                for x in 0..width {
+                    let color = theme.bar_color.get_color(x as usize, width);
+                    let fill_amount = (*value - f32::from(x)).clamp(0.0, 0.99);
+                    if reverse {
+                        queue!(writer, MoveTo(area.x + width - 1 - x, row))?;
+                        queue_inverted_fill(
+                            writer,
+                            fill_amount,
+                            bar_symbols,
+                            " ",
+                            color,
+                            theme.bg_color,
+                        )?;
+                    } else {
+                        queue!(writer, MoveTo(area.x + x, row))?;
+                        match fill_symbol(fill_amount, bar_symbols) {
+                            Some(symbol) => queue!(
+                                writer,
+                                PrintStyledContent(symbol.with(color).on(theme.bg_color))
+                            )?,
+                            None => queue!(writer, PrintStyledContent(" ".on(theme.bg_color)))?,
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the values as a line made of braille dots. Every cell contains
+    /// 2x4 dots so each column shows two values.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[inline]
+    fn queue_braille(
+        writer: &mut impl Write,
+        area: Rect,
+        columns: &[f32],
+        fill: bool,
+        theme: &CavaTheme,
+    ) -> Result<()> {
+        const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+        let width = area.width as usize;
+        let height = area.height as usize;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let max_dot = height * 4 - 1;
+        let mut cells = vec![0_u8; width * height];
+        let mut set_dot = |x: usize, dot_y: usize| {
+            let row = height - 1 - dot_y / 4;
+            cells[row * width + x / 2] |= DOTS[3 - dot_y % 4][x % 2];
+        };
 
+        let mut prev: Option<usize> = None;
+        for (x, value) in columns.iter().enumerate().take(width * 2) {
+            let dot_y = ((value * 4.0).max(0.0) as usize).min(max_dot);
+            if fill {
+                (0..=dot_y).for_each(|y| set_dot(x, y));
+            } else {
+                // connect to the previous point so the line has no gaps
+                let from = prev.map_or(dot_y, |p| p.min(dot_y));
+                let to = prev.map_or(dot_y, |p| p.max(dot_y));
+                (from..=to).for_each(|y| set_dot(x, y));
+            }
+            prev = Some(dot_y);
+        }
+
+        for row in 0..height {
+            let color = theme.bar_color.get_color(height - 1 - row, area.height);
+            queue!(writer, MoveTo(area.x, area.y + row as u16))?;
+            for mask in &cells[row * width..(row + 1) * width] {
+                if *mask == 0 {
+                    queue!(writer, PrintStyledContent(' '.on(theme.bg_color)))?;
+                } else {
+                    let symbol = char::from_u32(0x2800 + u32::from(*mask)).unwrap_or(' ');
+                    queue!(writer, PrintStyledContent(symbol.with(color).on(theme.bg_color)))?;
+                }
+            }
+        }
         Ok(())
     }
 
-    fn spawn_cava(bars: u16, config: &Cava) -> Result<ProcessGuard> {
+    fn spawn_cava(bars: u16, channels: usize, config: &Cava) -> Result<ProcessGuard> {
         let cfg_dir = std::env::temp_dir().join("rmpc");
         std::fs::create_dir_all(&cfg_dir)?;
         let cfg_path = cfg_dir.join(format!("cava-{}.conf", rustix::process::geteuid().as_raw()));
-        let config = config.to_cava_config_file(bars)?;
+        let config = config.to_cava_config_file(bars, channels)?;
         std::fs::write(&cfg_path, config)?;
 
         Ok(ProcessGuard {
@@ -188,7 +528,7 @@ impl CavaPane {
                 Ok(CavaCommand::ConfigChanged { config, theme }) => {
                     log::trace!("Cava config changed, updating");
                     cava_config = config;
-                    cava_theme = theme;
+                    cava_theme = *theme;
                     continue 'outer;
                 }
                 Err(RecvError) => {
@@ -196,35 +536,21 @@ impl CavaPane {
                     break 'outer;
                 }
             }
-            let bar_width = cava_theme.bar_width;
-            let bar_spacing = cava_theme.bar_spacing;
-            let bars = area.width / (bar_width + bar_spacing);
-
-            let total_bar_width = bars * bar_width;
-            let total_spacing_width = (bars - 1) * bar_spacing;
-            let total_width = total_bar_width + total_spacing_width;
-            let empty_bar_symbol = " ".repeat(bar_width as usize);
+            let layout = CavaLayout::new(area, &cava_theme);
+            let empty_bar_symbol = " ".repeat(cava_theme.bar_width as usize);
 
-            let x_offset = (area.width - total_width) / 2;
+            log::debug!(cava_theme:?, layout:?; "theme");
 
-            log::debug!(cava_theme:?; "theme");
-
-            let mut process = Self::spawn_cava(bars, &cava_config)?;
-            let stdout =
-                process.handle.stdout.as_mut().context("Failed to spawn cava. No stdout.")?;
-            let stderr =
-                process.handle.stderr.as_mut().context("Failed to spawn cava. No stderr.")?;
-
-            let mut columns = vec![0_f32; bars as usize];
-            let mut buf = vec![0_u8; 2 * bars as usize];
+            let mut source = CavaSource::new(layout.bars, layout.channels, &cava_config)?;
+            let mut columns = vec![0_f32; layout.bars as usize * layout.channels];
 
             'inner: loop {
-                Self::read_cava_data(area.height, &mut buf, &mut columns, stdout, stderr)?;
+                source.read(layout.max, layout.channels, &mut columns)?;
                 Self::render_cava(
                     writer,
                     area,
                     &mut columns,
-                    x_offset,
+                    &layout,
                     &empty_bar_symbol,
                     &cava_theme,
                 )?;
@@ -265,9 +591,9 @@ impl CavaPane {
             log::debug!("Cava already running, skipping spawn");
             return Ok(());
         }
-        if !CAVA.installed {
+        if cava_config.backend == CavaBackend::Cava && !CAVA.installed {
             status_warn!(
-                "Cava has not been found on your system. Please install it to use the visualiser."
+                "Cava has not been found on your system. Please install it or use the builtin backend to use the visualiser."
             );
             return Ok(());
         }
@@ -373,7 +699,7 @@ impl Pane for CavaPane {
             UiEvent::ConfigChanged => {
                 self.command(CavaCommand::ConfigChanged {
                     config: ctx.config.cava.clone(),
-                    theme: ctx.config.theme.cava.clone(),
+                    theme: Box::new(ctx.config.theme.cava.clone()),
                 })?;
 
                 if is_visible && !self.is_modal_open && matches!(ctx.status.state, State::Play) {
@@ -424,3 +750,65 @@ impl Pane for CavaPane {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::{CavaLayout, fill_symbol};
+    use crate::config::theme::cava::{CavaMode, CavaTheme};
+
+    fn theme(mode: CavaMode) -> CavaTheme {
+        CavaTheme { mode, bar_width: 2, bar_spacing: 1, ..Default::default() }
+    }
+
+    #[test]
+    fn bars_layout_is_centered() {
+        let layout = CavaLayout::new(Rect::new(0, 0, 10, 5), &theme(CavaMode::Bars));
+
+        assert_eq!(layout, CavaLayout { bars: 3, offset: 1, max: 5, channels: 1 });
+    }
+
+    #[test]
+    fn stereo_layout_splits_height() {
+        let layout =
+            CavaLayout::new(Rect::new(0, 0, 10, 5), &theme(CavaMode::Stereo { right_color: None }));
+
+        assert_eq!(layout, CavaLayout { bars: 3, offset: 1, max: 3, channels: 2 });
+    }
+
+    #[test]
+    fn horizontal_layout_uses_rows() {
+        let layout = CavaLayout::new(
+            Rect::new(0, 0, 10, 7),
+            &theme(CavaMode::Horizontal { bar_symbols: Vec::new(), reverse: false }),
+        );
+
+        assert_eq!(layout, CavaLayout { bars: 2, offset: 1, max: 10, channels: 1 });
+    }
+
+    #[test]
+    fn braille_layout_has_two_points_per_column() {
+        let layout =
+            CavaLayout::new(Rect::new(0, 0, 10, 7), &theme(CavaMode::Braille { fill: false }));
+
+        assert_eq!(layout, CavaLayout { bars: 20, offset: 0, max: 7, channels: 1 });
+    }
+
+    #[test]
+    fn layout_does_not_underflow_in_tiny_area() {
+        let layout = CavaLayout::new(Rect::new(0, 0, 1, 1), &theme(CavaMode::Bars));
+
+        assert_eq!(layout, CavaLayout { bars: 0, offset: 0, max: 1, channels: 1 });
+    }
+
+    #[test]
+    fn fill_symbol_picks_by_fraction() {
+        let symbols = ["a", "b", "c", "d"].map(String::from);
+
+        assert_eq!(fill_symbol(0.0, &symbols), None);
+        assert_eq!(fill_symbol(0.1, &symbols), Some("a"));
+        assert_eq!(fill_symbol(0.6, &symbols), Some("c"));
+        assert_eq!(fill_symbol(5.0, &symbols), Some("d"));
+    }
+}
@@ -0,0 +1,489 @@
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    clippy::cast_lossless,
+    clippy::cast_possible_wrap
+)]
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    fs::File,
+    io::{ErrorKind, Read},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail};
+use rustix::fs::{Mode, OFlags};
+
+use crate::config::cava::{Cava, CavaInputMethod};
+
+const DEFAULT_SAMPLE_RATE: u32 = 44100;
+const DEFAULT_SAMPLE_BITS: u32 = 16;
+const DEFAULT_CHANNELS: u32 = 2;
+const DEFAULT_LOWER_CUTOFF: f32 = 50.0;
+const DEFAULT_HIGHER_CUTOFF: f32 = 10000.0;
+/// Gain applied to the bar values when autosens is disabled. Chosen so that
+/// `sensitivity: 100` gives roughly the same output as cava does.
+const MANUAL_GAIN: f32 = 4.0;
+
+/// Reads raw PCM from MPD's fifo output and turns it into bar heights,
+/// replacing the external cava process.
+pub struct BuiltinVisualizer {
+    fifo: File,
+    /// Number of channels in the PCM data
+    channels: usize,
+    /// Number of channels the bars are computed for, either mono mix or
+    /// left and right separately
+    output_channels: usize,
+    sample_bytes: usize,
+    samples_per_frame: usize,
+    frame_duration: Duration,
+    next_frame: Instant,
+    read_buf: Vec<u8>,
+    pending: Vec<u8>,
+    samples: Vec<VecDeque<f32>>,
+    fft: Fft,
+    bands: Vec<Band>,
+    values: Vec<f32>,
+    gain: f32,
+    autosens: bool,
+    noise_reduction: f32,
+    monstercat: bool,
+    waves: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Band {
+    start: usize,
+    end: usize,
+    weight: f32,
+}
+
+impl std::fmt::Debug for BuiltinVisualizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BuiltinVisualizer")
+            .field("channels", &self.channels)
+            .field("output_channels", &self.output_channels)
+            .field("sample_bytes", &self.sample_bytes)
+            .field("bars", &self.bands.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl BuiltinVisualizer {
+    pub fn new(bars: u16, output_channels: usize, config: &Cava) -> Result<Self> {
+        if config.input.method != CavaInputMethod::Fifo {
+            bail!(
+                "Builtin visualiser backend only supports the 'Fifo' input method, got '{}'",
+                config.input.method
+            );
+        }
+
+        let sample_rate = config.input.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+        let sample_bits = config.input.sample_bits.unwrap_or(DEFAULT_SAMPLE_BITS);
+        let channels = config.input.channels.unwrap_or(DEFAULT_CHANNELS).max(1) as usize;
+        if !matches!(sample_bits, 8 | 16 | 24 | 32) {
+            bail!("Unsupported sample_bits '{sample_bits}', expected one of 8, 16, 24 or 32");
+        }
+
+        let fd = rustix::fs::open(
+            config.input.source.as_str(),
+            OFlags::RDONLY | OFlags::NONBLOCK | OFlags::CLOEXEC,
+            Mode::empty(),
+        )
+        .with_context(|| format!("Failed to open visualiser fifo '{}'", config.input.source))?;
+
+        let framerate = config.framerate.max(1);
+        // Roughly 50ms worth of samples, enough resolution for the low end
+        // while still reacting fast enough
+        let fft_size = (sample_rate as usize / 20).next_power_of_two().clamp(512, 16384);
+        let lower = config.lower_cutoff_freq.map_or(DEFAULT_LOWER_CUTOFF, f32::from);
+        let higher = config.higher_cutoff_freq.map_or(DEFAULT_HIGHER_CUTOFF, |v| v as f32);
+
+        let bands = Self::calculate_bands(bars as usize, fft_size, sample_rate, lower, higher)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, band)| Band {
+                weight: band.weight * eq_for_bar(&config.eq, idx, bars as usize),
+                ..band
+            })
+            .collect();
+
+        Ok(Self {
+            fifo: File::from(fd),
+            channels,
+            output_channels: output_channels.clamp(1, 2),
+            sample_bytes: sample_bits as usize / 8,
+            samples_per_frame: (sample_rate / u32::from(framerate)) as usize,
+            frame_duration: Duration::from_secs_f64(1.0 / f64::from(framerate)),
+            next_frame: Instant::now(),
+            read_buf: vec![0; 16 * 1024],
+            pending: Vec::new(),
+            samples: vec![VecDeque::from(vec![0.0; fft_size]); output_channels.clamp(1, 2)],
+            fft: Fft::new(fft_size),
+            bands,
+            values: vec![0.0; bars as usize * output_channels.clamp(1, 2)],
+            gain: if config.autosens { 1.0 } else { f32::from(config.sensitivity) / 100.0 },
+            autosens: config.autosens,
+            noise_reduction: f32::from(config.smoothing.noise_reduction.min(100)) / 100.0,
+            monstercat: config.smoothing.monstercat,
+            waves: config.smoothing.waves,
+        })
+    }
+
+    /// Splits the spectrum between the cutoff frequencies into logarithmically
+    /// spaced bands, one per bar. Every band covers at least one FFT bin.
+    fn calculate_bands(
+        bars: usize,
+        fft_size: usize,
+        sample_rate: u32,
+        lower: f32,
+        higher: f32,
+    ) -> Vec<Band> {
+        let nyquist = sample_rate as f32 / 2.0;
+        let bin_width = sample_rate as f32 / fft_size as f32;
+        let max_bin = fft_size / 2;
+        let lower = lower.clamp(bin_width, nyquist);
+        let higher = higher.clamp(lower, nyquist);
+        let ratio = higher / lower;
+
+        let mut result = Vec::with_capacity(bars);
+        let mut prev_end = (lower / bin_width).floor() as usize;
+        for i in 0..bars {
+            let freq = lower * ratio.powf((i + 1) as f32 / bars as f32);
+            let start = prev_end.min(max_bin - 1);
+            let end = ((freq / bin_width).round() as usize).clamp(start + 1, max_bin);
+            // Higher frequencies carry less energy, compensate for it so that
+            // the right side of the visualiser is not permanently flat
+            let center = (start + end) as f32 / 2.0 * bin_width;
+            let weight = (center / lower).log2().max(1.0);
+            result.push(Band { start, end, weight });
+            prev_end = end;
+        }
+        result
+    }
+
+    /// Blocks until the next frame is due and then fills `columns` with values
+    /// in the range of `0..max`, same as the cava backend. Values of multiple
+    /// channels follow each other, left channel first.
+    pub fn read(&mut self, max: u16, columns: &mut [f32]) -> Result<()> {
+        let now = Instant::now();
+        if let Some(wait) = self.next_frame.checked_duration_since(now) {
+            std::thread::sleep(wait);
+        }
+        self.next_frame = self.next_frame.max(now) + self.frame_duration;
+
+        if self.drain_fifo()? == 0 {
+            // Nothing is playing, feed silence so the bars fall down instead of
+            // freezing in place
+            let silence = vec![0.0; self.output_channels];
+            for _ in 0..self.samples_per_frame {
+                self.push_frame(&silence);
+            }
+        }
+
+        let bars = self.bands.len();
+        for (channel, values) in self.samples.iter_mut().zip(self.values.chunks_mut(bars.max(1))) {
+            let spectrum = self.fft.magnitudes(channel.make_contiguous());
+            for (value, band) in values.iter_mut().zip(&self.bands) {
+                let bins = &spectrum[band.start..band.end];
+                let magnitude = bins.iter().copied().fold(0.0_f32, f32::max);
+                let new_value = magnitude.sqrt() * band.weight * self.gain * MANUAL_GAIN;
+
+                *value = if new_value >= *value {
+                    new_value
+                } else {
+                    *value * self.noise_reduction + new_value * (1.0 - self.noise_reduction)
+                };
+            }
+        }
+
+        // Filters are applied only to the output so that they do not compound
+        // over multiple frames
+        for (column, value) in columns.iter_mut().zip(&self.values) {
+            *column = *value;
+        }
+        for channel in columns.chunks_mut(bars.max(1)) {
+            if self.monstercat {
+                monstercat_filter(channel);
+            }
+            if self.waves {
+                waves_filter(channel);
+            }
+        }
+
+        if self.autosens {
+            if columns.iter().any(|v| *v > 1.0) {
+                self.gain *= 0.98;
+            } else if columns.iter().any(|v| *v > 0.01) {
+                self.gain *= 1.002;
+            }
+        }
+
+        for column in columns.iter_mut() {
+            *column = column.clamp(0.0, 1.0) * f32::from(max);
+        }
+
+        Ok(())
+    }
+
+    /// Reads everything currently available in the fifo without blocking.
+    /// Returns number of bytes read.
+    fn drain_fifo(&mut self) -> Result<usize> {
+        let mut total = 0;
+        loop {
+            match self.fifo.read(&mut self.read_buf) {
+                // No writer is connected, MPD is most likely paused or stopped
+                Ok(0) => break,
+                Ok(len) => {
+                    total += len;
+                    self.pending.extend_from_slice(&self.read_buf[..len]);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err).context("Failed to read from visualiser fifo"),
+            }
+        }
+
+        let frame_bytes = self.sample_bytes * self.channels;
+        let complete = self.pending.len() - self.pending.len() % frame_bytes;
+        let pending = std::mem::take(&mut self.pending);
+        let (frames, rest) = pending.split_at(complete);
+        let mut decoded = vec![0.0; self.channels];
+        let mut output = vec![0.0; self.output_channels];
+        for frame in frames.chunks_exact(frame_bytes) {
+            for (sample, bytes) in decoded.iter_mut().zip(frame.chunks_exact(self.sample_bytes)) {
+                *sample = decode_sample(bytes);
+            }
+            if self.output_channels == 1 {
+                output[0] = decoded.iter().sum::<f32>() / self.channels as f32;
+            } else {
+                // mono input is shown the same in both of the channels
+                output[0] = decoded[0];
+                output[1] = decoded.get(1).copied().unwrap_or(decoded[0]);
+            }
+            self.push_frame(&output);
+        }
+        self.pending = rest.to_vec();
+
+        Ok(total)
+    }
+
+    /// Pushes one sample per output channel.
+    fn push_frame(&mut self, frame: &[f32]) {
+        let capacity = self.fft.size;
+        for (channel, sample) in self.samples.iter_mut().zip(frame) {
+            if channel.len() == capacity {
+                channel.pop_front();
+            }
+            channel.push_back(*sample);
+        }
+    }
+}
+
+/// Decodes a single signed little endian sample into the range of `-1..1`.
+fn decode_sample(bytes: &[u8]) -> f32 {
+    match *bytes {
+        [a] => f32::from(a as i8) / 128.0,
+        [a, b] => f32::from(i16::from_le_bytes([a, b])) / 32768.0,
+        [a, b, c] => (i32::from_le_bytes([0, a, b, c]) >> 8) as f32 / 8_388_608.0,
+        [a, b, c, d] => i32::from_le_bytes([a, b, c, d]) as f32 / 2_147_483_648.0,
+        _ => 0.0,
+    }
+}
+
+/// Eq values are spread evenly over all of the bars the same way cava does it.
+fn eq_for_bar(eq: &[f64], bar: usize, bars: usize) -> f32 {
+    if eq.is_empty() || bars == 0 {
+        return 1.0;
+    }
+    eq[bar * eq.len() / bars] as f32
+}
+
+fn monstercat_filter(values: &mut [f32]) {
+    for i in 0..values.len() {
+        for j in 0..values.len() {
+            if i == j {
+                continue;
+            }
+            let falloff = values[i] / 1.5_f32.powi(i.abs_diff(j) as i32);
+            values[j] = values[j].max(falloff);
+        }
+    }
+}
+
+fn waves_filter(values: &mut [f32]) {
+    for i in 0..values.len() {
+        for j in 0..values.len() {
+            if i == j {
+                continue;
+            }
+            let distance = i.abs_diff(j) as f32;
+            values[j] = values[j].max(values[i] - distance * distance * 0.01);
+        }
+    }
+}
+
+/// Minimal iterative radix-2 FFT over real input with a Hann window applied.
+struct Fft {
+    size: usize,
+    window: Vec<f32>,
+    twiddles: Vec<(f32, f32)>,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl Fft {
+    fn new(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+        let window = (0..size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / (size - 1) as f32).cos())
+            .collect();
+        let twiddles = (0..size / 2)
+            .map(|i| {
+                let angle = -2.0 * PI * i as f32 / size as f32;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+
+        Self { size, window, twiddles, re: vec![0.0; size], im: vec![0.0; size] }
+    }
+
+    /// Returns normalized magnitudes of the first `size / 2` bins.
+    fn magnitudes(&mut self, input: &[f32]) -> Vec<f32> {
+        let n = self.size;
+        let bits = n.trailing_zeros();
+        for i in 0..n {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            self.re[j] = input.get(i).copied().unwrap_or_default() * self.window[i];
+            self.im[j] = 0.0;
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let half = len / 2;
+            let step = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..half {
+                    let (w_re, w_im) = self.twiddles[k * step];
+                    let a = start + k;
+                    let b = a + half;
+                    let t_re = self.re[b] * w_re - self.im[b] * w_im;
+                    let t_im = self.re[b] * w_im + self.im[b] * w_re;
+                    self.re[b] = self.re[a] - t_re;
+                    self.im[b] = self.im[a] - t_im;
+                    self.re[a] += t_re;
+                    self.im[a] += t_im;
+                }
+            }
+            len *= 2;
+        }
+
+        let norm = 2.0 / n as f32;
+        (0..n / 2).map(|i| self.re[i].hypot(self.im[i]) * norm).collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::{BuiltinVisualizer, Fft, decode_sample, eq_for_bar};
+    use crate::config::cava::{Cava, CavaInput, CavaInputMethod};
+
+    #[test]
+    fn reads_pcm_and_produces_bars() {
+        let path = std::env::temp_dir().join(format!("rmpc-visualizer-{}.pcm", std::process::id()));
+        let samples: Vec<u8> = (0..44100)
+            .flat_map(|i| {
+                let value = (2.0 * PI * 1000.0 * i as f32 / 44100.0).sin() * 16000.0;
+                let bytes = (value as i16).to_le_bytes();
+                [bytes[0], bytes[1], bytes[0], bytes[1]]
+            })
+            .collect();
+        std::fs::write(&path, samples).unwrap();
+
+        let config = Cava {
+            framerate: 1000,
+            autosens: false,
+            sensitivity: 100,
+            input: CavaInput {
+                method: CavaInputMethod::Fifo,
+                source: path.to_string_lossy().into_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut visualizer = BuiltinVisualizer::new(16, 1, &config).unwrap();
+        let mut columns = vec![0.0; 16];
+        visualizer.read(10, &mut columns).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let peak = columns
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(idx, _)| idx)
+            .unwrap();
+        assert!(columns[peak] > 0.0);
+        assert!(columns.iter().all(|c| (0.0..=10.0).contains(c)));
+        assert!((4..12).contains(&peak), "unexpected peak at {peak}, {columns:?}");
+    }
+
+    #[test]
+    fn only_fifo_input_is_supported() {
+        let config = Cava {
+            input: CavaInput { method: CavaInputMethod::Pulse, ..Default::default() },
+            ..Default::default()
+        };
+
+        assert!(BuiltinVisualizer::new(16, 1, &config).is_err());
+    }
+
+    #[test]
+    fn fft_finds_sine_peak() {
+        let size = 1024;
+        let bin = 64;
+        let input: Vec<f32> =
+            (0..size).map(|i| (2.0 * PI * bin as f32 * i as f32 / size as f32).sin()).collect();
+
+        let magnitudes = Fft::new(size).magnitudes(&input);
+        let peak =
+            magnitudes.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).map(|(idx, _)| idx);
+
+        assert_eq!(peak, Some(bin));
+    }
+
+    #[test]
+    fn bands_are_contiguous_and_non_empty() {
+        let bands = BuiltinVisualizer::calculate_bands(64, 2048, 44100, 50.0, 10000.0);
+
+        assert_eq!(bands.len(), 64);
+        for band in &bands {
+            assert!(band.end > band.start);
+        }
+        for (a, b) in bands.iter().zip(bands.iter().skip(1)) {
+            assert_eq!(a.end, b.start);
+        }
+    }
+
+    #[test]
+    fn decodes_samples() {
+        assert!((decode_sample(&[0x00, 0x80]) + 1.0).abs() < f32::EPSILON);
+        assert!(decode_sample(&[0x00, 0x00]).abs() < f32::EPSILON);
+        assert!((decode_sample(&[0xFF, 0xFF, 0x7F]) - 1.0).abs() < 0.001);
+        assert!((decode_sample(&[0x80]) + 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn eq_is_spread_over_bars() {
+        let eq = [1.0, 2.0];
+
+        assert!((eq_for_bar(&eq, 0, 4) - 1.0).abs() < f32::EPSILON);
+        assert!((eq_for_bar(&eq, 1, 4) - 1.0).abs() < f32::EPSILON);
+        assert!((eq_for_bar(&eq, 2, 4) - 2.0).abs() < f32::EPSILON);
+        assert!((eq_for_bar(&[], 2, 4) - 1.0).abs() < f32::EPSILON);
+    }
+}
@@ -1,11 +1,17 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use itertools::Itertools;
 use ratatui::{Frame, prelude::Rect};
 
-use super::Pane;
+use super::{Pane, PaneRemoteState};
 use crate::{
     MpdQueryResult,
-    config::tabs::PaneType,
+    config::{
+        keys::DirectoriesActions,
+        sort_mode::{SortMode, SortOptions},
+        tabs::PaneType,
+    },
     context::AppContext,
     mpd::{
         QueuePosition,
@@ -35,6 +41,7 @@ pub struct DirectoriesPane {
     filter_input_mode: bool,
     browser: Browser<DirOrSong>,
     initialized: bool,
+    sort: Arc<SortOptions>,
 }
 
 const INIT: &str = "init";
@@ -42,13 +49,65 @@ const OPEN_OR_PLAY: &str = "open_or_play";
 const PREVIEW: &str = "preview";
 
 impl DirectoriesPane {
-    pub fn new(_context: &AppContext) -> Self {
+    pub fn new(context: &AppContext) -> Self {
         Self {
             stack: DirStack::default(),
             filter_input_mode: false,
             browser: Browser::new(),
             initialized: false,
+            sort: context.config.directories_sort.clone(),
+        }
+    }
+
+    fn handle_directories_action(
+        &mut self,
+        event: &mut KeyEvent,
+        context: &AppContext,
+    ) -> Result<()> {
+        let Some(action) = event.as_directories_action(context) else {
+            return Ok(());
+        };
+
+        let sort = Arc::make_mut(&mut self.sort);
+        match action {
+            DirectoriesActions::CycleSortMode => {
+                sort.mode = match sort.mode {
+                    SortMode::Format(_) => {
+                        status_info!("Sorting by modification time");
+                        SortMode::ModifiedTime
+                    }
+                    SortMode::ModifiedTime => {
+                        status_info!("Sorting by song format");
+                        match &context.config.directories_sort.mode {
+                            mode @ SortMode::Format(_) => mode.clone(),
+                            SortMode::ModifiedTime => SortMode::default(),
+                        }
+                    }
+                };
+            }
+            DirectoriesActions::ToggleGroupByType => {
+                sort.group_by_type = !sort.group_by_type;
+                if sort.group_by_type {
+                    status_info!("Listing directories before songs");
+                } else {
+                    status_info!("Listing directories together with songs");
+                }
+            }
+            DirectoriesActions::ToggleReverseSort => {
+                sort.reverse = !sort.reverse;
+                status_info!("Sort order reversed");
+            }
+        }
+
+        let sort = &self.sort;
+        let scrolloff = context.config.scrolloff;
+        for dir in self.stack.dirs_mut() {
+            dir.sort_by(|a, b| a.with_custom_sort(sort).cmp(&b.with_custom_sort(sort)), scrolloff);
         }
+        self.prepare_preview(context)?;
+        context.render()?;
+
+        Ok(())
     }
 
     fn open_or_play(&mut self, autoplay: bool, context: &AppContext) -> Result<()> {
@@ -61,7 +120,7 @@ impl DirectoriesPane {
             return Ok(());
         };
 
-        let sort = context.config.directories_sort.clone();
+        let sort = self.sort.clone();
         match selected {
             DirOrSong::Dir { playlist: is_playlist, .. } => {
                 let is_playlist = *is_playlist;
@@ -130,7 +189,7 @@ impl Pane for DirectoriesPane {
 
     fn before_show(&mut self, context: &AppContext) -> Result<()> {
         if !self.initialized {
-            let sort = context.config.directories_sort.clone();
+            let sort = self.sort.clone();
             let playlist_display_mode = context.config.show_playlists_in_browser;
             context.query().id(INIT).replace_id(INIT).target(PaneType::Directories).query(
                 move |client| {
@@ -157,7 +216,7 @@ impl Pane for DirectoriesPane {
     ) -> Result<()> {
         match event {
             UiEvent::Database => {
-                let sort = context.config.directories_sort.clone();
+                let sort = self.sort.clone();
                 let playlist_display_mode = context.config.show_playlists_in_browser;
                 context.query().id(INIT).replace_id(INIT).target(PaneType::Directories).query(
                     move |client| {
@@ -177,6 +236,9 @@ impl Pane for DirectoriesPane {
                 self.initialized = false;
                 self.before_show(context)?;
             }
+            UiEvent::ConfigChanged => {
+                self.sort = context.config.directories_sort.clone();
+            }
             _ => {}
         }
         Ok(())
@@ -188,6 +250,7 @@ impl Pane for DirectoriesPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, context: &mut AppContext) -> Result<()> {
         self.handle_filter_input(event, context)?;
+        self.handle_directories_action(event, context)?;
         self.handle_common_action(event, context)?;
         self.handle_global_action(event, context)?;
         Ok(())
@@ -231,6 +294,10 @@ impl Pane for DirectoriesPane {
         }
         Ok(())
     }
+
+    fn remote_state(&mut self, _context: &AppContext) -> Result<PaneRemoteState> {
+        Ok(PaneRemoteState::from_dir(self.stack().current()))
+    }
 }
 
 impl BrowserPane<DirOrSong> for DirectoriesPane {
@@ -340,7 +407,7 @@ impl BrowserPane<DirOrSong> for DirectoriesPane {
                 };
                 let next_path = next_path.join("/").to_string();
                 let config = std::sync::Arc::clone(&context.config);
-                let sort = context.config.directories_sort.clone();
+                let sort = self.sort.clone();
                 let is_playlist = *is_playlist;
                 let playlist_display_mode = context.config.show_playlists_in_browser;
 
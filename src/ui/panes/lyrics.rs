@@ -8,6 +8,7 @@ use ratatui::{
 
 use super::Pane;
 use crate::{
+    AppEvent,
     context::AppContext,
     shared::{
         ext::duration::DurationExt,
@@ -24,11 +25,18 @@ pub struct LyricsPane {
     current_lyrics: Option<Lrc>,
     initialized: bool,
     last_requested_line_idx: usize,
+    /// Line last reported to `rmpc remote subscribe` clients
+    last_emitted_line_idx: Option<usize>,
 }
 
 impl LyricsPane {
     pub fn new(_context: &AppContext) -> Self {
-        Self { current_lyrics: None, initialized: false, last_requested_line_idx: 0 }
+        Self {
+            current_lyrics: None,
+            initialized: false,
+            last_requested_line_idx: 0,
+            last_emitted_line_idx: None,
+        }
     }
 }
 
@@ -129,6 +137,15 @@ impl Pane for LyricsPane {
             }
         }
 
+        if first_line_reached && self.last_emitted_line_idx != Some(current_line_idx) {
+            self.last_emitted_line_idx = Some(current_line_idx);
+            context.app_event_sender.send(AppEvent::LyricsLineChanged {
+                index: current_line_idx,
+                time: current_line.time,
+                line: current_line.content.clone(),
+            })?;
+        }
+
         // Try to schedule the next line to be displayed on time
         if self.last_requested_line_idx != current_line_idx + 1 {
             if let Some(line) = lrc.lines.get(current_line_idx + 1) {
@@ -154,6 +171,7 @@ impl Pane for LyricsPane {
                 }
             }
             self.last_requested_line_idx = 0;
+            self.last_emitted_line_idx = None;
             self.initialized = true;
         }
 
@@ -179,6 +197,7 @@ impl Pane for LyricsPane {
                     }
                 }
                 self.last_requested_line_idx = 0;
+                self.last_emitted_line_idx = None;
             }
             UiEvent::LyricsIndexed if self.current_lyrics.is_none() => {
                 match context.find_lrc() {
@@ -192,6 +211,7 @@ impl Pane for LyricsPane {
                     }
                 }
                 self.last_requested_line_idx = 0;
+                self.last_emitted_line_idx = None;
             }
             _ => {}
         }
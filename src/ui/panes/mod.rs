@@ -1,4 +1,9 @@
-use std::{borrow::Cow, cmp::Ordering, collections::HashMap, time::Duration};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 use album_art::AlbumArtPane;
 use albums::AlbumsPane;
@@ -20,6 +25,7 @@ use ratatui::{
     widgets::Block,
 };
 use search::SearchPane;
+use serde::Serialize;
 use strum::Display;
 use tabs::TabsPane;
 use tag_browser::TagBrowserPane;
@@ -29,6 +35,8 @@ use unicase::UniCase;
 use self::{frame_count::FrameCountPane, logs::LogsPane};
 use super::{
     UiEvent,
+    dir_or_song::DirOrSong,
+    dirstack::{Dir, DirStackItem},
     widgets::{scan_status::ScanStatus, volume::Volume},
 };
 use crate::{
@@ -51,7 +59,7 @@ use crate::{
     },
     context::AppContext,
     mpd::{
-        commands::{Song, State, status::OnOffOneshot, volume::Bound},
+        commands::{Song, State, Status, status::OnOffOneshot, volume::Bound},
         mpd_client::Tag,
     },
     shared::{
@@ -235,7 +243,7 @@ macro_rules! pane_call {
             Panes::ProgressBar(s) => s.$fn($($param),+),
             Panes::Header(s) => s.$fn($($param),+),
             Panes::Tabs(s) => s.$fn($($param),+),
-            Panes::TabContent => Ok(()),
+            Panes::TabContent => Ok(Default::default()),
             #[cfg(debug_assertions)]
             Panes::FrameCount(s) => s.$fn($($param),+),
             Panes::Property(s) => s.$fn($($param),+),
@@ -293,6 +301,62 @@ pub(crate) trait Pane {
     fn resize(&mut self, area: Rect, context: &AppContext) -> Result<()> {
         Ok(())
     }
+
+    /// State of the pane which can be read by scripts, ie. with `rmpc remote
+    /// get selected`
+    fn remote_state(&mut self, context: &AppContext) -> Result<PaneRemoteState> {
+        Ok(PaneRemoteState::default())
+    }
+}
+
+/// Item of a pane as reported over the socket
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RemoteItem {
+    Dir { name: String, path: String },
+    Playlist { name: String, path: String },
+    Song(Song),
+}
+
+impl From<&DirOrSong> for RemoteItem {
+    fn from(value: &DirOrSong) -> Self {
+        match value {
+            DirOrSong::Dir { name, full_path, playlist: false, .. } => {
+                RemoteItem::Dir { name: name.clone(), path: full_path.clone() }
+            }
+            DirOrSong::Dir { name, full_path, playlist: true, .. } => {
+                RemoteItem::Playlist { name: name.clone(), path: full_path.clone() }
+            }
+            DirOrSong::Song(song) => RemoteItem::Song(song.clone()),
+        }
+    }
+}
+
+impl From<&Song> for RemoteItem {
+    fn from(value: &Song) -> Self {
+        RemoteItem::Song(value.clone())
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PaneRemoteState {
+    pub selected: Option<RemoteItem>,
+    pub marked: Vec<RemoteItem>,
+    pub filter: Option<String>,
+}
+
+impl PaneRemoteState {
+    pub fn from_dir<T>(dir: &Dir<T>) -> Self
+    where
+        T: DirStackItem + std::fmt::Debug + Clone + Send,
+        for<'a> &'a T: Into<RemoteItem>,
+    {
+        Self {
+            selected: dir.selected().map(Into::into),
+            marked: dir.marked_items().map(Into::into).collect(),
+            filter: dir.filter().map(ToOwned::to_owned),
+        }
+    }
 }
 
 pub(crate) mod browser {
@@ -768,47 +832,85 @@ impl Property<SongProperty> {
     }
 }
 
+/// State which [`PropertyKind`] properties are resolved from. Inside of the UI
+/// it is the whole [`AppContext`], the CLI has only what MPD reports.
+pub struct PropertySource<'a> {
+    status: &'a Status,
+    queue: &'a [Song],
+    db_update_start: Option<Instant>,
+    context: Option<&'a AppContext>,
+}
+
+impl<'a> PropertySource<'a> {
+    pub fn new(status: &'a Status, queue: &'a [Song]) -> Self {
+        Self {
+            status,
+            queue,
+            db_update_start: status.updating_db.map(|_| Instant::now()),
+            context: None,
+        }
+    }
+}
+
+impl<'a> From<&'a AppContext> for PropertySource<'a> {
+    fn from(context: &'a AppContext) -> Self {
+        Self {
+            status: &context.status,
+            queue: &context.queue,
+            db_update_start: context.db_update_start,
+            context: Some(context),
+        }
+    }
+}
+
 impl Property<PropertyKind> {
-    fn default_as_span<'song: 's, 's>(
+    pub fn as_span<'song: 's, 's>(
         &'s self,
         song: Option<&'song Song>,
         context: &'song AppContext,
         tag_separator: &str,
         strategy: TagResolutionStrategy,
     ) -> Option<Either<Span<'s>, Vec<Span<'s>>>> {
-        self.default.as_ref().and_then(|p| p.as_span(song, context, tag_separator, strategy))
+        self.resolve(song, &PropertySource::from(context), tag_separator, strategy)
     }
 
-    pub fn as_span<'song: 's, 's>(
+    /// Plain text of the property, ie. for the CLI or notifications.
+    /// Properties which only make sense inside of the UI, like the active tab,
+    /// resolve to their default when there is no [`AppContext`].
+    pub fn as_string(
+        &self,
+        song: Option<&Song>,
+        source: &PropertySource<'_>,
+        tag_separator: &str,
+        strategy: TagResolutionStrategy,
+    ) -> Option<String> {
+        self.resolve(song, source, tag_separator, strategy).map(|value| match value {
+            Either::Left(span) => span.content.into_owned(),
+            Either::Right(spans) => spans.iter().map(|span| span.content.as_ref()).collect(),
+        })
+    }
+
+    fn resolve<'s>(
         &'s self,
-        song: Option<&'song Song>,
-        context: &'song AppContext,
+        song: Option<&'s Song>,
+        source: &PropertySource<'s>,
         tag_separator: &str,
         strategy: TagResolutionStrategy,
     ) -> Option<Either<Span<'s>, Vec<Span<'s>>>> {
         let style = self.style.unwrap_or_default();
-        let status = &context.status;
+        let status = source.status;
+        let default =
+            || self.default.as_ref().and_then(|p| p.resolve(song, source, tag_separator, strategy));
+        let text = |value| Some(Either::Left(Span::styled(value, style)));
+
         match &self.kind {
-            PropertyKindOrText::Text(value) => Some(Either::Left(Span::styled(value, style))),
-            PropertyKindOrText::Sticker(key) => {
-                if let Some(sticker) =
-                    song.and_then(|s| s.stickers.as_ref().and_then(|stickers| stickers.get(key)))
-                {
-                    Some(Either::Left(Span::styled(sticker, style)))
-                } else {
-                    self.default_as_span(song, context, tag_separator, strategy)
-                }
-            }
-            PropertyKindOrText::Property(PropertyKind::Song(property)) => {
-                if let Some(song) = song {
-                    song.format(property, tag_separator, strategy).map_or_else(
-                        || self.default_as_span(Some(song), context, tag_separator, strategy),
-                        |s| Some(Either::Left(Span::styled(s, style))),
-                    )
-                } else {
-                    self.default_as_span(song, context, tag_separator, strategy)
-                }
-            }
+            PropertyKindOrText::Text(value) => text(value.into()),
+            PropertyKindOrText::Sticker(key) => song
+                .and_then(|s| s.stickers.as_ref().and_then(|stickers| stickers.get(key)))
+                .map_or_else(default, |sticker| text(sticker.into())),
+            PropertyKindOrText::Property(PropertyKind::Song(property)) => song
+                .and_then(|song| song.format(property, tag_separator, strategy))
+                .map_or_else(default, text),
             PropertyKindOrText::Property(PropertyKind::Status(s)) => match s {
                 StatusProperty::State {
                     playing_label,
@@ -830,15 +932,9 @@ impl Property<PropertyKind> {
                     }
                     .unwrap_or(style),
                 ))),
-                StatusProperty::Duration => {
-                    Some(Either::Left(Span::styled(status.duration.to_string(), style)))
-                }
-                StatusProperty::Elapsed => {
-                    Some(Either::Left(Span::styled(status.elapsed.to_string(), style)))
-                }
-                StatusProperty::Volume => {
-                    Some(Either::Left(Span::styled(status.volume.value().to_string(), style)))
-                }
+                StatusProperty::Duration => text(status.duration.to_string().into()),
+                StatusProperty::Elapsed => text(status.elapsed.to_string().into()),
+                StatusProperty::Volume => text(status.volume.value().to_string().into()),
                 StatusProperty::Repeat { on_label, off_label, on_style, off_style } => {
                     Some(Either::Left(Span::styled(
                         if status.repeat { on_label } else { off_label },
@@ -891,47 +987,37 @@ impl Property<PropertyKind> {
                     }
                     .unwrap_or(style),
                 ))),
-                StatusProperty::Bitrate => status.bitrate.as_ref().map_or_else(
-                    || self.default_as_span(song, context, tag_separator, strategy),
-                    |v| Some(Either::Left(Span::styled(v.to_string(), style))),
-                ),
-                StatusProperty::Crossfade => status.xfade.as_ref().map_or_else(
-                    || self.default_as_span(song, context, tag_separator, strategy),
-                    |v| Some(Either::Left(Span::styled(v.to_string(), style))),
-                ),
+                StatusProperty::Bitrate => {
+                    status.bitrate.as_ref().map_or_else(default, |v| text(v.to_string().into()))
+                }
+                StatusProperty::Crossfade => {
+                    status.xfade.as_ref().map_or_else(default, |v| text(v.to_string().into()))
+                }
                 StatusProperty::QueueLength { thousands_separator } => {
-                    Some(Either::Left(Span::styled(
-                        context.queue.len().with_thousands_separator(thousands_separator),
-                        style,
-                    )))
+                    text(source.queue.len().with_thousands_separator(thousands_separator).into())
                 }
                 StatusProperty::QueueTimeTotal { separator } => {
-                    let sum: Duration = context.queue.iter().filter_map(|s| s.duration).sum();
-                    Some(Either::Left(Span::styled(sum.format_to_duration(separator), style)))
+                    let sum: Duration = source.queue.iter().filter_map(|s| s.duration).sum();
+                    text(sum.format_to_duration(separator).into())
                 }
                 StatusProperty::QueueTimeRemaining { separator } => {
-                    let sum = context.find_current_song_in_queue().map_or(
-                        Duration::default(),
-                        |(current_song_idx, _)| {
-                            context
-                                .queue
-                                .iter()
-                                .skip(current_song_idx)
-                                .filter_map(|s| s.duration)
-                                .sum()
-                        },
-                    );
-                    Some(Either::Left(Span::styled(sum.format_to_duration(separator), style)))
-                }
-                StatusProperty::ActiveTab => {
-                    Some(Either::Left(Span::styled(context.active_tab.0.as_ref(), style)))
+                    let current_song_idx =
+                        status.find_current_song_in(source.queue).map(|(idx, _)| idx);
+                    let sum: Duration = current_song_idx
+                        .map(|idx| source.queue.iter().skip(idx).filter_map(|s| s.duration).sum())
+                        .unwrap_or_default();
+                    text(sum.format_to_duration(separator).into())
                 }
+                StatusProperty::ActiveTab => source
+                    .context
+                    .map_or_else(default, |context| text(context.active_tab.0.as_str().into())),
             },
             PropertyKindOrText::Property(PropertyKind::Widget(w)) => match w {
-                WidgetProperty::Volume => {
-                    Some(Either::Left(Span::styled(Volume::get_str(*status.volume.value()), style)))
-                }
+                WidgetProperty::Volume => text(Volume::get_str(*status.volume.value()).into()),
                 WidgetProperty::States { active_style, separator_style } => {
+                    if source.context.is_none() {
+                        return default();
+                    }
                     let separator = Span::styled(" / ", *separator_style);
                     Some(Either::Right(vec![
                         Span::styled("Repeat", if status.repeat { *active_style } else { style }),
@@ -951,26 +1037,44 @@ impl Property<PropertyKind> {
                         },
                     ]))
                 }
-                WidgetProperty::ScanStatus => context.db_update_start.map(|update_start| {
-                    Either::Left(Span::styled(
+                WidgetProperty::PendingKeys => {
+                    let context = source.context?;
+                    context
+                        .pending_keys
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .or_else(|| {
+                            context
+                                .macros
+                                .recording_register()
+                                .map(|register| format!("recording @{register}"))
+                        })
+                        .and_then(|value| text(value.into()))
+                }
+                WidgetProperty::ScanStatus => source.db_update_start.and_then(|update_start| {
+                    text(
                         ScanStatus::new(Some(update_start))
                             .get_str()
                             .unwrap_or_default()
-                            .to_string(),
-                        style,
-                    ))
+                            .to_owned()
+                            .into(),
+                    )
                 }),
             },
+            PropertyKindOrText::Property(PropertyKind::Script(name)) => source
+                .context
+                .and_then(|context| context.scripts.property(name, context))
+                .map_or_else(default, |value| text(value.into())),
             PropertyKindOrText::Group(group) => {
                 let mut buf = Vec::new();
                 for format in group {
-                    match format.as_span(song, context, tag_separator, strategy) {
+                    match format.resolve(song, source, tag_separator, strategy) {
                         Some(Either::Left(span)) => buf.push(span),
                         Some(Either::Right(spans)) => buf.extend(spans),
-                        None => return None,
+                        None => return default(),
                     }
                 }
-                return Some(Either::Right(buf));
+                Some(Either::Right(buf))
             }
         }
     }
@@ -1651,4 +1755,181 @@ mod format_tests {
             assert_eq!(result, Some("innerfallbackouter".to_owned()));
         }
     }
+
+    mod plain_string {
+        use std::time::Duration;
+
+        use either::Either;
+        use rstest::rstest;
+
+        use super::*;
+        use crate::{
+            config::theme::{
+                TagResolutionStrategy,
+                properties::{PropertyKind, StatusProperty},
+            },
+            context::AppContext,
+            mpd::commands::{State, Status, Volume, status::OnOffOneshot},
+            tests::fixtures::app_context,
+            ui::panes::PropertySource,
+        };
+
+        fn status() -> Status {
+            Status {
+                volume: Volume::new(80),
+                repeat: true,
+                single: OnOffOneshot::Oneshot,
+                elapsed: Duration::from_secs(65),
+                state: State::Pause,
+                songid: Some(2),
+                ..Default::default()
+            }
+        }
+
+        fn queue() -> Vec<Song> {
+            (1..=3)
+                .map(|id| Song {
+                    id,
+                    duration: Some(Duration::from_secs(60)),
+                    ..Default::default()
+                })
+                .collect()
+        }
+
+        fn property(kind: PropertyKindOrText<PropertyKind>) -> Property<PropertyKind> {
+            Property { kind, style: None, default: None }
+        }
+
+        #[rstest]
+        #[case(StatusProperty::Volume, "80")]
+        #[case(StatusProperty::Elapsed, "1:05")]
+        #[case(StatusProperty::Repeat {
+            on_label: "on".to_owned(),
+            off_label: "off".to_owned(),
+            on_style: None,
+            off_style: None,
+        }, "on")]
+        #[case(StatusProperty::Single {
+            on_label: "on".to_owned(),
+            off_label: "off".to_owned(),
+            oneshot_label: "once".to_owned(),
+            on_style: None,
+            off_style: None,
+            oneshot_style: None,
+        }, "once")]
+        #[case(StatusProperty::State {
+            playing_label: "play".to_owned(),
+            paused_label: "pause".to_owned(),
+            stopped_label: "stop".to_owned(),
+            playing_style: None,
+            paused_style: None,
+            stopped_style: None,
+        }, "pause")]
+        #[case(StatusProperty::QueueLength { thousands_separator: ",".to_owned() }, "3")]
+        #[case(StatusProperty::QueueTimeRemaining { separator: ",".to_owned() }, "2m")]
+        fn status_property_resolves_without_context(
+            #[case] prop: StatusProperty,
+            #[case] expected: &str,
+        ) {
+            let format = property(PropertyKindOrText::Property(PropertyKind::Status(prop)));
+
+            let result = format.as_string(
+                None,
+                &PropertySource::new(&status(), &queue()),
+                "",
+                TagResolutionStrategy::All,
+            );
+
+            assert_eq!(result, Some(expected.to_owned()));
+        }
+
+        #[test]
+        fn group_and_ui_only_properties_fall_back_to_default() {
+            let mut group = property(PropertyKindOrText::Group(vec![
+                property(PropertyKindOrText::Text("tab: ".to_owned())),
+                property(PropertyKindOrText::Property(PropertyKind::Status(
+                    StatusProperty::ActiveTab,
+                ))),
+            ]));
+            group.default = Some(Box::new(property(PropertyKindOrText::Text("no tab".to_owned()))));
+
+            let result = group.as_string(
+                None,
+                &PropertySource::new(&status(), &[]),
+                "",
+                TagResolutionStrategy::All,
+            );
+
+            assert_eq!(result, Some("no tab".to_owned()));
+        }
+
+        #[rstest]
+        fn string_matches_span_with_context(app_context: AppContext) {
+            let format = property(PropertyKindOrText::Group(vec![
+                property(PropertyKindOrText::Text("tab: ".to_owned())),
+                property(PropertyKindOrText::Property(PropertyKind::Status(
+                    StatusProperty::ActiveTab,
+                ))),
+            ]));
+
+            let string = format.as_string(
+                None,
+                &PropertySource::from(&app_context),
+                "",
+                TagResolutionStrategy::All,
+            );
+            let span = format.as_span(None, &app_context, "", TagResolutionStrategy::All);
+
+            let Some(Either::Right(spans)) = span else {
+                panic!("Expected a group of spans, got {span:?}");
+            };
+            let span_text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+            assert_eq!(string, Some(span_text));
+            assert_eq!(string, Some(format!("tab: {}", app_context.active_tab)));
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod remote_item_tests {
+    use serde_json::json;
+
+    use super::RemoteItem;
+    use crate::{mpd::commands::Song, ui::dir_or_song::DirOrSong};
+
+    #[test]
+    fn dir_and_playlist_are_tagged_by_type() {
+        let dir = DirOrSong::Dir {
+            name: "Album".to_owned(),
+            full_path: "Artist/Album".to_owned(),
+            last_modified: chrono::DateTime::default(),
+            playlist: false,
+        };
+        let playlist = DirOrSong::Dir {
+            name: "Favourites".to_owned(),
+            full_path: "Favourites".to_owned(),
+            last_modified: chrono::DateTime::default(),
+            playlist: true,
+        };
+
+        assert_eq!(
+            serde_json::to_value(RemoteItem::from(&dir)).unwrap(),
+            json!({ "type": "dir", "name": "Album", "path": "Artist/Album" })
+        );
+        assert_eq!(
+            serde_json::to_value(RemoteItem::from(&playlist)).unwrap(),
+            json!({ "type": "playlist", "name": "Favourites", "path": "Favourites" })
+        );
+    }
+
+    #[test]
+    fn song_is_flattened_next_to_its_type() {
+        let song = Song { file: "Artist/Album/01.flac".to_owned(), ..Default::default() };
+
+        let value = serde_json::to_value(RemoteItem::from(&song)).unwrap();
+
+        assert_eq!(value["type"], "song");
+        assert_eq!(value["file"], "Artist/Album/01.flac");
+    }
 }
@@ -2,16 +2,17 @@ use anyhow::{Context, Result, anyhow};
 use itertools::Itertools;
 use ratatui::{Frame, prelude::Rect};
 
-use super::Pane;
+use super::{Pane, PaneRemoteState};
 use crate::{
     MpdQueryResult,
-    config::tabs::PaneType,
+    config::{keys::PlaylistsActions, tabs::PaneType, utils::tilde_expand},
     context::AppContext,
     mpd::{
         QueuePosition,
+        SingleOrRange,
         client::Client,
-        commands::{Song, lsinfo::LsInfoEntry},
-        mpd_client::{Filter, MpdClient, SingleOrRange, Tag},
+        commands::{Song, lsinfo::LsInfoEntry, mpd_config::MpdConfig},
+        mpd_client::{Filter, MpdClient, Tag},
     },
     shared::{
         ext::mpd_client::MpdClientExt,
@@ -104,6 +105,147 @@ impl PlaylistsPane {
 
         Ok(())
     }
+
+    /// Name of the highlighted playlist, or of the opened one when browsing
+    /// its songs
+    fn playlist_under_cursor(&self) -> Option<String> {
+        match self.stack.path() {
+            [] => match self.stack.current().selected()? {
+                DirOrSong::Dir { name, .. } => Some(name.clone()),
+                DirOrSong::Song(_) => None,
+            },
+            [playlist, ..] => Some(playlist.clone()),
+        }
+    }
+
+    fn handle_playlists_action(
+        &mut self,
+        event: &mut KeyEvent,
+        context: &AppContext,
+    ) -> Result<()> {
+        let Some(action) = event.as_playlists_action(context) else {
+            return Ok(());
+        };
+        let Some(playlist) = self.playlist_under_cursor() else {
+            return Ok(());
+        };
+
+        match action {
+            PlaylistsActions::Duplicate => {
+                modal!(
+                    context,
+                    InputModal::new(context)
+                        .title("Duplicate playlist")
+                        .confirm_label("Save")
+                        .input_label("New name:")
+                        .initial_value(format!("{playlist} (copy)"))
+                        .on_confirm(move |context, new_name| {
+                            let playlist = playlist.clone();
+                            let new_name = new_name.to_owned();
+                            context.command(move |client| {
+                                if client.list_playlists()?.iter().any(|p| p.name == new_name) {
+                                    status_error!("Playlist '{new_name}' already exists");
+                                    return Ok(());
+                                }
+                                for file in client.list_playlist(&playlist)?.0 {
+                                    client.add_to_playlist(&new_name, &file, None)?;
+                                }
+                                status_info!("Playlist '{playlist}' saved as '{new_name}'");
+                                Ok(())
+                            });
+                            Ok(())
+                        })
+                );
+            }
+            PlaylistsActions::Export => {
+                modal!(
+                    context,
+                    InputModal::new(context)
+                        .title("Export playlist")
+                        .confirm_label("Export")
+                        .input_label("File:")
+                        .initial_value(format!("~/{playlist}.m3u"))
+                        .on_confirm(move |context, path| {
+                            let playlist = playlist.clone();
+                            let path = tilde_expand(path).into_owned();
+                            context.command(move |client| {
+                                let songs = client.list_playlist_info(&playlist, None)?;
+                                let music_directory = client
+                                    .config()
+                                    .map(|MpdConfig { music_directory, .. }| music_directory.clone());
+                                let m3u = to_m3u(&songs, music_directory.as_deref());
+                                match std::fs::write(&path, m3u) {
+                                    Ok(()) => status_info!("Playlist '{playlist}' exported to '{path}'"),
+                                    Err(err) => status_error!(err:?; "Failed to export playlist '{playlist}' to '{path}'"),
+                                }
+                                Ok(())
+                            });
+                            Ok(())
+                        })
+                );
+            }
+            PlaylistsActions::Sort => {
+                let sort = context.config.browser_song_sort.clone();
+                context.command(move |client| {
+                    let songs = client.list_playlist_info(&playlist, None)?;
+                    let order = (0..songs.len())
+                        .sorted_by(|a, b| {
+                            songs[*a]
+                                .with_custom_sort(&sort)
+                                .cmp(&songs[*b].with_custom_sort(&sort))
+                        })
+                        .collect_vec();
+                    for (from, to) in sort_moves(&order) {
+                        client.move_in_playlist(&playlist, &SingleOrRange::single(from), to)?;
+                    }
+                    status_info!("Playlist '{playlist}' sorted");
+                    Ok(())
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates an extended m3u playlist. Paths are made absolute when the music
+/// directory is known, otherwise they are relative to it.
+fn to_m3u(songs: &[Song], music_directory: Option<&str>) -> String {
+    let mut lines = vec!["#EXTM3U".to_owned()];
+    for song in songs {
+        let duration = song.duration.map_or(-1, |d| i64::try_from(d.as_secs()).unwrap_or(-1));
+        let title = song.metadata.get("title").map_or(song.file.as_str(), |v| v.first());
+        lines.push(match song.metadata.get("artist") {
+            Some(artist) => format!("#EXTINF:{duration},{} - {title}", artist.first()),
+            None => format!("#EXTINF:{duration},{title}"),
+        });
+        lines.push(match music_directory {
+            Some(dir) if !song.file.contains("://") => {
+                std::path::Path::new(dir).join(&song.file).to_string_lossy().into_owned()
+            }
+            _ => song.file.clone(),
+        });
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Returns the `(from, to)` moves which reorder a playlist so that the song
+/// originally at `order[i]` ends up at position `i`.
+fn sort_moves(order: &[usize]) -> Vec<(usize, usize)> {
+    let mut current = (0..order.len()).collect_vec();
+    let mut moves = Vec::new();
+    for (target, original) in order.iter().enumerate() {
+        let Some(from) = current.iter().position(|idx| idx == original) else {
+            continue;
+        };
+        if from != target {
+            let idx = current.remove(from);
+            current.insert(target, idx);
+            moves.push((from, target));
+        }
+    }
+    moves
 }
 
 impl Pane for PlaylistsPane {
@@ -182,6 +324,7 @@ impl Pane for PlaylistsPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, context: &mut AppContext) -> Result<()> {
         self.handle_filter_input(event, context)?;
+        self.handle_playlists_action(event, context)?;
         self.handle_common_action(event, context)?;
         self.handle_global_action(event, context)?;
         Ok(())
@@ -326,6 +469,10 @@ impl Pane for PlaylistsPane {
         }
         Ok(())
     }
+
+    fn remote_state(&mut self, _context: &AppContext) -> Result<PaneRemoteState> {
+        Ok(PaneRemoteState::from_dir(self.stack().current()))
+    }
 }
 
 impl BrowserPane<DirOrSong> for PlaylistsPane {
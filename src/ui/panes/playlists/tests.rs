@@ -501,6 +501,48 @@ mod on_idle_event {
     }
 }
 
+mod sort_moves {
+    use crate::ui::panes::playlists::sort_moves;
+
+    #[test]
+    fn reorders_to_the_given_order() {
+        let order = [2, 0, 3, 1];
+        let mut playlist = vec!["a", "b", "c", "d"];
+
+        for (from, to) in sort_moves(&order) {
+            let song = playlist.remove(from);
+            playlist.insert(to, song);
+        }
+
+        assert_eq!(playlist, vec!["c", "a", "d", "b"]);
+    }
+
+    #[test]
+    fn does_nothing_when_already_sorted() {
+        assert!(sort_moves(&[0, 1, 2]).is_empty());
+    }
+}
+
+mod to_m3u {
+    use super::*;
+    use crate::ui::panes::playlists::to_m3u;
+
+    #[test]
+    fn writes_extended_m3u() {
+        let mut first = song("artist/album/01.flac");
+        first.metadata.insert("title".to_string(), "First".to_string().into());
+        first.metadata.insert("artist".to_string(), "Artist".to_string().into());
+        let second = song("http://radio.example/stream");
+
+        let result = to_m3u(&[first, second], Some("/music"));
+
+        assert_eq!(
+            result,
+            "#EXTM3U\n#EXTINF:1,Artist - First\n/music/artist/album/01.flac\n#EXTINF:1,http://radio.example/stream\nhttp://radio.example/stream\n"
+        );
+    }
+}
+
 static LAST_ID: AtomicU32 = AtomicU32::new(1);
 static NOW: LazyLock<chrono::DateTime<chrono::Utc>> = LazyLock::new(chrono::Utc::now);
 
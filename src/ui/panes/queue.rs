@@ -12,7 +12,7 @@ use ratatui::{
     widgets::{Block, Borders, Cell, Row, Table, TableState},
 };
 
-use super::{CommonAction, Pane};
+use super::{CommonAction, Pane, PaneRemoteState};
 use crate::{
     MpdQueryResult,
     config::{
@@ -22,7 +22,7 @@ use crate::{
     },
     context::AppContext,
     core::command::{create_env, run_external},
-    mpd::{QueuePosition, commands::Song, mpd_client::MpdClient},
+    mpd::{QueuePosition, SingleOrRange, commands::Song, mpd_client::MpdClient},
     shared::{
         ext::{btreeset_ranges::BTreeSetRanges, rect::RectExt},
         key_event::KeyEvent,
@@ -529,6 +529,19 @@ impl Pane for QueuePane {
                     status_info!("Marked songs removed from queue");
                     context.render()?;
                 }
+                QueueActions::Delete if event.explicit_count().is_some() => {
+                    if let Some(idx) =
+                        self.scrolling_state.get_selected().filter(|idx| *idx < context.queue.len())
+                    {
+                        let end = (idx + event.count()).min(context.queue.len());
+                        context.command(move |client| {
+                            client.delete_from_queue(SingleOrRange::range(idx, end))?;
+                            Ok(())
+                        });
+                    } else {
+                        status_error!("No song selected");
+                    }
+                }
                 QueueActions::Delete => {
                     if let Some(selected_song) =
                         self.scrolling_state.get_selected().and_then(|idx| context.queue.get(idx))
@@ -642,93 +655,41 @@ impl Pane for QueuePane {
             match action {
                 CommonAction::Up => {
                     if !context.queue.is_empty() {
-                        self.scrolling_state
-                            .prev(context.config.scrolloff, context.config.wrap_navigation);
+                        for _ in 0..event.count() {
+                            self.scrolling_state
+                                .prev(context.config.scrolloff, context.config.wrap_navigation);
+                        }
                     }
 
                     context.render()?;
                 }
                 CommonAction::Down => {
                     if !context.queue.is_empty() {
-                        self.scrolling_state
-                            .next(context.config.scrolloff, context.config.wrap_navigation);
+                        for _ in 0..event.count() {
+                            self.scrolling_state
+                                .next(context.config.scrolloff, context.config.wrap_navigation);
+                        }
                     }
 
                     context.render()?;
                 }
                 CommonAction::MoveUp if !self.scrolling_state.get_marked().is_empty() => {
-                    if context.queue.is_empty() {
-                        return Ok(());
-                    }
-
-                    if let Some(0) = self.scrolling_state.marked.first() {
-                        return Ok(());
-                    }
-
-                    for range in self.scrolling_state.marked.ranges() {
-                        for idx in range.clone() {
-                            let new_idx = idx.saturating_sub(1);
-                            context.queue.swap(idx, new_idx);
+                    for _ in 0..event.count() {
+                        if !self.move_marked_up(context) {
+                            break;
                         }
-
-                        let new_start_idx = range.start().saturating_sub(1);
-                        context.command(move |client| {
-                            client.move_in_queue(
-                                range.into(),
-                                QueuePosition::Absolute(new_start_idx),
-                            )?;
-                            Ok(())
-                        });
                     }
 
-                    if let Some(start) = self.scrolling_state.marked.first() {
-                        let new_idx = start.saturating_sub(1);
-                        self.scrolling_state.select(Some(new_idx), context.config.scrolloff);
-                    }
-
-                    let mut new_marked =
-                        self.scrolling_state.marked.iter().map(|i| i.saturating_sub(1)).collect();
-                    std::mem::swap(&mut self.scrolling_state.marked, &mut new_marked);
-
                     context.render()?;
                     return Ok(());
                 }
                 CommonAction::MoveDown if !self.scrolling_state.get_marked().is_empty() => {
-                    if context.queue.is_empty() {
-                        return Ok(());
-                    }
-
-                    if let Some(last_idx) = self.scrolling_state.marked.last() {
-                        if *last_idx == context.queue.len() - 1 {
-                            return Ok(());
+                    for _ in 0..event.count() {
+                        if !self.move_marked_down(context) {
+                            break;
                         }
                     }
 
-                    for range in self.scrolling_state.marked.ranges().rev() {
-                        for idx in range.clone().rev() {
-                            let new_idx = idx.saturating_add(1);
-                            context.queue.swap(idx, new_idx);
-                        }
-
-                        let new_start_idx = range.start().saturating_add(1);
-                        context.command(move |client| {
-                            client.move_in_queue(
-                                range.into(),
-                                QueuePosition::Absolute(new_start_idx),
-                            )?;
-                            Ok(())
-                        });
-                    }
-
-                    if let Some(start) = self.scrolling_state.marked.last() {
-                        let new_idx = start.saturating_add(1);
-                        self.scrolling_state.select(Some(new_idx), context.config.scrolloff);
-                    }
-
-                    let mut new_marked =
-                        self.scrolling_state.marked.iter().map(|i| i.saturating_add(1)).collect();
-                    std::mem::swap(&mut self.scrolling_state.marked, &mut new_marked);
-
                     context.render()?;
                     return Ok(());
                 }
@@ -747,14 +708,15 @@ impl Pane for QueuePane {
                         return Ok(());
                     };
 
-                    let new_idx = idx.saturating_sub(1);
+                    let new_idx = idx.saturating_sub(event.count());
                     let id = selected.id;
                     context.command(move |client| {
                         client.move_id(id, QueuePosition::Absolute(new_idx))?;
                         Ok(())
                     });
                     self.scrolling_state.select(Some(new_idx), context.config.scrolloff);
-                    context.queue.swap(idx, new_idx);
+                    let song = context.queue.remove(idx);
+                    context.queue.insert(new_idx, song);
                     context.render()?;
                 }
                 CommonAction::MoveDown => {
@@ -771,53 +733,66 @@ impl Pane for QueuePane {
                         return Ok(());
                     };
 
-                    let new_idx = (idx + 1).min(context.queue.len() - 1);
+                    let new_idx = (idx + event.count()).min(context.queue.len() - 1);
                     let id = selected.id;
                     context.command(move |client| {
                         client.move_id(id, QueuePosition::Absolute(new_idx))?;
                         Ok(())
                     });
                     self.scrolling_state.select(Some(new_idx), context.config.scrolloff);
-                    context.queue.swap(idx, new_idx);
+                    let song = context.queue.remove(idx);
+                    context.queue.insert(new_idx, song);
                     context.render()?;
                 }
                 CommonAction::DownHalf => {
                     if !context.queue.is_empty() {
-                        self.scrolling_state.next_half_viewport(context.config.scrolloff);
+                        for _ in 0..event.count() {
+                            self.scrolling_state.next_half_viewport(context.config.scrolloff);
+                        }
                     }
 
                     context.render()?;
                 }
                 CommonAction::UpHalf => {
                     if !context.queue.is_empty() {
-                        self.scrolling_state.prev_half_viewport(context.config.scrolloff);
+                        for _ in 0..event.count() {
+                            self.scrolling_state.prev_half_viewport(context.config.scrolloff);
+                        }
                     }
 
                     context.render()?;
                 }
                 CommonAction::PageDown => {
                     if !context.queue.is_empty() {
-                        self.scrolling_state.next_viewport(context.config.scrolloff);
+                        for _ in 0..event.count() {
+                            self.scrolling_state.next_viewport(context.config.scrolloff);
+                        }
                     }
 
                     context.render()?;
                 }
                 CommonAction::PageUp => {
                     if !context.queue.is_empty() {
-                        self.scrolling_state.prev_viewport(context.config.scrolloff);
+                        for _ in 0..event.count() {
+                            self.scrolling_state.prev_viewport(context.config.scrolloff);
+                        }
                     }
 
                     context.render()?;
                 }
                 CommonAction::Bottom => {
-                    if !context.queue.is_empty() {
+                    if let Some(count) = event.explicit_count() {
+                        self.select_nth(count, context);
+                    } else if !context.queue.is_empty() {
                         self.scrolling_state.last();
                     }
 
                     context.render()?;
                 }
                 CommonAction::Top => {
-                    if !context.queue.is_empty() {
+                    if let Some(count) = event.explicit_count() {
+                        self.select_nth(count, context);
+                    } else if !context.queue.is_empty() {
                         self.scrolling_state.first();
                     }
 
@@ -917,9 +892,109 @@ impl Pane for QueuePane {
 
         Ok(())
     }
+
+    fn remote_state(&mut self, context: &AppContext) -> Result<PaneRemoteState> {
+        Ok(PaneRemoteState {
+            selected: self
+                .scrolling_state
+                .get_selected()
+                .and_then(|idx| context.queue.get(idx))
+                .map(Into::into),
+            marked: self
+                .scrolling_state
+                .get_marked()
+                .iter()
+                .filter_map(|idx| context.queue.get(*idx))
+                .map(Into::into)
+                .collect(),
+            filter: self.filter.clone(),
+        })
+    }
 }
 
 impl QueuePane {
+    /// Selects the n-th song in the queue, counted from one, or the last one if
+    /// the queue is shorter.
+    fn select_nth(&mut self, n: usize, context: &AppContext) {
+        if context.queue.is_empty() {
+            return;
+        }
+        let idx = n.saturating_sub(1).min(context.queue.len() - 1);
+        self.scrolling_state.select(Some(idx), context.config.scrolloff);
+    }
+
+    /// Moves all marked songs one position up. Returns false if they cannot
+    /// move any further.
+    fn move_marked_up(&mut self, context: &mut AppContext) -> bool {
+        if context.queue.is_empty() {
+            return false;
+        }
+
+        if let Some(0) = self.scrolling_state.marked.first() {
+            return false;
+        }
+
+        for range in self.scrolling_state.marked.ranges() {
+            for idx in range.clone() {
+                let new_idx = idx.saturating_sub(1);
+                context.queue.swap(idx, new_idx);
+            }
+
+            let new_start_idx = range.start().saturating_sub(1);
+            context.command(move |client| {
+                client.move_in_queue(range.into(), QueuePosition::Absolute(new_start_idx))?;
+                Ok(())
+            });
+        }
+
+        if let Some(start) = self.scrolling_state.marked.first() {
+            let new_idx = start.saturating_sub(1);
+            self.scrolling_state.select(Some(new_idx), context.config.scrolloff);
+        }
+
+        let mut new_marked =
+            self.scrolling_state.marked.iter().map(|i| i.saturating_sub(1)).collect();
+        std::mem::swap(&mut self.scrolling_state.marked, &mut new_marked);
+        true
+    }
+
+    /// Moves all marked songs one position down. Returns false if they cannot
+    /// move any further.
+    fn move_marked_down(&mut self, context: &mut AppContext) -> bool {
+        if context.queue.is_empty() {
+            return false;
+        }
+
+        if let Some(last_idx) = self.scrolling_state.marked.last() {
+            if *last_idx == context.queue.len() - 1 {
+                return false;
+            }
+        }
+
+        for range in self.scrolling_state.marked.ranges().rev() {
+            for idx in range.clone().rev() {
+                let new_idx = idx.saturating_add(1);
+                context.queue.swap(idx, new_idx);
+            }
+
+            let new_start_idx = range.start().saturating_add(1);
+            context.command(move |client| {
+                client.move_in_queue(range.into(), QueuePosition::Absolute(new_start_idx))?;
+                Ok(())
+            });
+        }
+
+        if let Some(start) = self.scrolling_state.marked.last() {
+            let new_idx = start.saturating_add(1);
+            self.scrolling_state.select(Some(new_idx), context.config.scrolloff);
+        }
+
+        let mut new_marked =
+            self.scrolling_state.marked.iter().map(|i| i.saturating_add(1)).collect();
+        std::mem::swap(&mut self.scrolling_state.marked, &mut new_marked);
+        true
+    }
+
     pub fn jump_forward(&mut self, queue: &[Song], scrolloff: usize) {
         let Some(filter) = self.filter.as_ref() else {
             status_warn!("No filter set");
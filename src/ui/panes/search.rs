@@ -10,10 +10,15 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, Padding},
 };
 
-use super::{CommonAction, Pane};
+use super::{CommonAction, Pane, PaneRemoteState};
 use crate::{
     MpdQueryResult,
-    config::{Config, Search, keys::GlobalAction, tabs::PaneType},
+    config::{
+        Config,
+        Search,
+        keys::{GlobalAction, SearchActions},
+        tabs::PaneType,
+    },
     context::AppContext,
     core::command::{create_env, run_external},
     mpd::{
@@ -24,13 +29,14 @@ use crate::{
     shared::{
         ext::mpd_client::MpdClientExt,
         key_event::KeyEvent,
-        macros::{status_info, status_warn},
+        macros::{modal, status_error, status_info, status_warn},
         mouse_event::{MouseEvent, MouseEventKind},
         mpd_query::PreviewGroup,
     },
     ui::{
         UiEvent,
         dirstack::{Dir, DirStackItem},
+        modals::input_modal::InputModal,
         widgets::{button::Button, input::Input},
     },
 };
@@ -414,6 +420,52 @@ impl SearchPane {
         }
     }
 
+    fn handle_search_action(&mut self, action: SearchActions, context: &AppContext) -> Result<()> {
+        match action {
+            SearchActions::ResetQuery => {
+                self.reset(&context.config.search);
+                self.songs_dir = Dir::default();
+                self.phase = Phase::Search;
+                self.prepare_preview(context);
+
+                context.render()?;
+            }
+            SearchActions::SaveQuery => {
+                if self.songs_dir.items.is_empty() {
+                    status_warn!("No songs found, nothing to save");
+                    return Ok(());
+                }
+
+                let files = self.songs_dir.items.iter().map(|song| song.file.clone()).collect_vec();
+                modal!(
+                    context,
+                    InputModal::new(context)
+                        .title("Save search results")
+                        .confirm_label("Save")
+                        .input_label("Playlist name:")
+                        .on_confirm(move |context, name| {
+                            let files = files.clone();
+                            let name = name.to_owned();
+                            context.command(move |client| {
+                                if client.list_playlists()?.iter().any(|p| p.name == name) {
+                                    status_error!("Playlist '{name}' already exists");
+                                    return Ok(());
+                                }
+                                for file in &files {
+                                    client.add_to_playlist(&name, file, None)?;
+                                }
+                                status_info!("{} songs saved to playlist '{name}'", files.len());
+                                Ok(())
+                            });
+                            Ok(())
+                        })
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     fn activate_input(&mut self, context: &AppContext) {
         match self.inputs.focused_mut() {
             FocusedInputGroup::Textboxes(_) => self.phase = Phase::SearchTextboxInput,
@@ -764,6 +816,15 @@ impl Pane for SearchPane {
     }
 
     fn handle_action(&mut self, event: &mut KeyEvent, context: &mut AppContext) -> Result<()> {
+        if !matches!(
+            self.phase,
+            Phase::SearchTextboxInput | Phase::BrowseResults { filter_input_on: true }
+        ) {
+            if let Some(action) = event.as_search_action(context) {
+                return self.handle_search_action(action, context);
+            }
+        }
+
         let config = &context.config;
         match &mut self.phase {
             Phase::SearchTextboxInput => match event.as_common_action(context) {
@@ -974,15 +1035,19 @@ impl Pane for SearchPane {
                 } else if let Some(action) = event.as_common_action(context) {
                     match action {
                         CommonAction::Down => {
-                            self.songs_dir
-                                .next(context.config.scrolloff, context.config.wrap_navigation);
+                            for _ in 0..event.count() {
+                                self.songs_dir
+                                    .next(context.config.scrolloff, context.config.wrap_navigation);
+                            }
                             self.prepare_preview(context);
 
                             context.render()?;
                         }
                         CommonAction::Up => {
-                            self.songs_dir
-                                .prev(context.config.scrolloff, context.config.wrap_navigation);
+                            for _ in 0..event.count() {
+                                self.songs_dir
+                                    .prev(context.config.scrolloff, context.config.wrap_navigation);
+                            }
                             self.prepare_preview(context);
 
                             context.render()?;
@@ -990,25 +1055,33 @@ impl Pane for SearchPane {
                         CommonAction::MoveDown => {}
                         CommonAction::MoveUp => {}
                         CommonAction::DownHalf => {
-                            self.songs_dir.next_half_viewport(context.config.scrolloff);
+                            for _ in 0..event.count() {
+                                self.songs_dir.next_half_viewport(context.config.scrolloff);
+                            }
                             self.prepare_preview(context);
 
                             context.render()?;
                         }
                         CommonAction::UpHalf => {
-                            self.songs_dir.prev_half_viewport(context.config.scrolloff);
+                            for _ in 0..event.count() {
+                                self.songs_dir.prev_half_viewport(context.config.scrolloff);
+                            }
                             self.prepare_preview(context);
 
                             context.render()?;
                         }
                         CommonAction::PageDown => {
-                            self.songs_dir.next_viewport(context.config.scrolloff);
+                            for _ in 0..event.count() {
+                                self.songs_dir.next_viewport(context.config.scrolloff);
+                            }
                             self.prepare_preview(context);
 
                             context.render()?;
                         }
                         CommonAction::PageUp => {
-                            self.songs_dir.prev_viewport(context.config.scrolloff);
+                            for _ in 0..event.count() {
+                                self.songs_dir.prev_viewport(context.config.scrolloff);
+                            }
                             self.prepare_preview(context);
 
                             context.render()?;
@@ -1115,6 +1188,10 @@ impl Pane for SearchPane {
         }
         Ok(())
     }
+
+    fn remote_state(&mut self, _context: &AppContext) -> Result<PaneRemoteState> {
+        Ok(PaneRemoteState::from_dir(&self.songs_dir))
+    }
 }
 
 enum FocusedInputGroup<T, F, B> {
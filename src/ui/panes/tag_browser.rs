@@ -4,11 +4,12 @@ use anyhow::{Context, Result};
 use itertools::Itertools;
 use ratatui::{Frame, prelude::Rect};
 
-use super::Pane;
+use super::{Pane, PaneRemoteState};
 use crate::{
     MpdQueryResult,
     config::{
         artists::{AlbumDisplayMode, AlbumSortMode},
+        keys::ArtistsActions,
         tabs::PaneType,
     },
     context::AppContext,
@@ -46,6 +47,8 @@ pub struct TagBrowserPane {
     browser: Browser<DirOrSong>,
     initialized: bool,
     cache: TagBrowserCache,
+    album_display_mode: AlbumDisplayMode,
+    album_sort_by: AlbumSortMode,
 }
 
 const INIT: &str = "init";
@@ -70,7 +73,7 @@ impl TagBrowserPane {
         root_tag: Tag,
         target_pane: PaneType,
         separator: Option<String>,
-        _context: &AppContext,
+        context: &AppContext,
     ) -> Self {
         Self {
             root_tag,
@@ -82,6 +85,8 @@ impl TagBrowserPane {
             browser: Browser::new(),
             initialized: false,
             cache: TagBrowserCache::default(),
+            album_display_mode: context.config.artists.album_display_mode,
+            album_sort_by: context.config.artists.album_sort_by,
         }
     }
 
@@ -182,8 +187,8 @@ impl TagBrowserPane {
         data: Vec<Song>,
         context: &AppContext,
     ) -> &CachedRootTag {
-        let display_mode = context.config.artists.album_display_mode;
-        let sort_mode = context.config.artists.album_sort_by;
+        let display_mode = self.album_display_mode;
+        let sort_mode = self.album_sort_by;
 
         let cached_artist = self.cache.0.entry(artist).or_default();
 
@@ -245,6 +250,62 @@ impl TagBrowserPane {
 
         cached_artist
     }
+
+    fn handle_artists_action(&mut self, event: &mut KeyEvent, context: &AppContext) -> Result<()> {
+        let Some(action) = event.as_artists_action(context) else {
+            return Ok(());
+        };
+
+        match action {
+            ArtistsActions::CycleAlbumSortMode => {
+                self.album_sort_by = self.album_sort_by.cycle();
+                match self.album_sort_by {
+                    AlbumSortMode::Name => status_info!("Sorting albums by name"),
+                    AlbumSortMode::Date => status_info!("Sorting albums by date"),
+                }
+            }
+            ArtistsActions::CycleAlbumDisplayMode => {
+                self.album_display_mode = self.album_display_mode.cycle();
+                match self.album_display_mode {
+                    AlbumDisplayMode::SplitByDate => status_info!("Splitting albums by date"),
+                    AlbumDisplayMode::NameOnly => status_info!("Showing only album names"),
+                }
+            }
+        }
+
+        self.regroup_albums(context)
+    }
+
+    /// Groups the already fetched songs into albums again after the sort or
+    /// display mode changed.
+    fn regroup_albums(&mut self, context: &AppContext) -> Result<()> {
+        let cache = std::mem::take(&mut self.cache.0);
+        for (artist, CachedRootTag(albums)) in cache {
+            let songs = albums.into_iter().flat_map(|album| album.songs).collect_vec();
+            self.process_songs(artist, songs, context);
+        }
+
+        // Album names might have changed so there is nothing to stay in
+        if self.stack.path().len() > 1 {
+            self.stack.pop();
+        }
+
+        if let [artist] = self.stack.path() {
+            if let Some(albums) = self.cache.0.get(artist) {
+                let albums = albums
+                    .0
+                    .iter()
+                    .map(|CachedAlbum { name, .. }| DirOrSong::name_only(name.to_owned()))
+                    .collect();
+                self.stack.replace(albums);
+            }
+        }
+
+        self.prepare_preview(context)?;
+        context.render()?;
+
+        Ok(())
+    }
 }
 
 impl Pane for TagBrowserPane {
@@ -294,6 +355,16 @@ impl Pane for TagBrowserPane {
                 self.initialized = false;
                 self.before_show(context)?;
             }
+            UiEvent::ConfigChanged => {
+                let artists = &context.config.artists;
+                if artists.album_display_mode != self.album_display_mode
+                    || artists.album_sort_by != self.album_sort_by
+                {
+                    self.album_display_mode = artists.album_display_mode;
+                    self.album_sort_by = artists.album_sort_by;
+                    self.regroup_albums(context)?;
+                }
+            }
             _ => {}
         }
         Ok(())
@@ -305,6 +376,7 @@ impl Pane for TagBrowserPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, context: &mut AppContext) -> Result<()> {
         self.handle_filter_input(event, context)?;
+        self.handle_artists_action(event, context)?;
         self.handle_common_action(event, context)?;
         self.handle_global_action(event, context)?;
         Ok(())
@@ -407,6 +479,10 @@ impl Pane for TagBrowserPane {
         }
         Ok(())
     }
+
+    fn remote_state(&mut self, _context: &AppContext) -> Result<PaneRemoteState> {
+        Ok(PaneRemoteState::from_dir(self.stack().current()))
+    }
 }
 
 impl BrowserPane<DirOrSong> for TagBrowserPane {
@@ -690,6 +766,7 @@ impl BrowserPane<DirOrSong> for TagBrowserPane {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use rstest::rstest;
 
@@ -803,4 +880,27 @@ mod tests {
         assert_eq!(result[0].name, "album_b");
         assert_eq!(result[1].name, "album_a");
     }
+
+    #[rstest]
+    fn cycling_display_mode_regroups_cached_albums(
+        mut app_context: AppContext,
+        mut config: Config,
+    ) {
+        config.artists.album_display_mode = AlbumDisplayMode::SplitByDate;
+        config.artists.album_sort_by = AlbumSortMode::Name;
+        app_context.config = std::sync::Arc::new(config);
+        let mut pane = TagBrowserPane::new(Tag::Artist, PaneType::Artists, None, &app_context);
+        let artist = String::from("artist");
+        let songs = vec![song("album_a", "2020"), song("album_a", "2021"), song("album_b", "2022")];
+        pane.process_songs(artist.clone(), songs, &app_context);
+
+        pane.album_display_mode = pane.album_display_mode.cycle();
+        pane.regroup_albums(&app_context).unwrap();
+
+        let CachedRootTag(result) = &pane.cache.0[&artist];
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "album_a");
+        assert_eq!(result[0].songs.len(), 2);
+        assert_eq!(result[1].name, "album_b");
+    }
 }
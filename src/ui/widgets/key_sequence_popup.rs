@@ -0,0 +1,96 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use crate::{context::AppContext, shared::key_event::PendingKeys};
+
+const COLUMN_SPACING: u16 = 3;
+
+/// Popup at the bottom of the screen listing all of the keybinds which
+/// continue the currently pending key sequence.
+pub struct KeySequencePopup<'a> {
+    context: &'a AppContext,
+    pending: &'a PendingKeys,
+}
+
+impl<'a> KeySequencePopup<'a> {
+    pub fn new(context: &'a AppContext, pending: &'a PendingKeys) -> Self {
+        Self { context, pending }
+    }
+}
+
+impl Widget for KeySequencePopup<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer) {
+        let config = &self.context.config;
+        let key_style = config.theme.highlighted_item_style;
+        let text_style = config.as_text_style();
+
+        let lines: Vec<Line> = config
+            .keybinds
+            .continuations(&self.pending.sequence)
+            .into_iter()
+            .map(|(keys, description)| {
+                Line::from(vec![
+                    Span::styled(keys.to_string(), key_style),
+                    Span::styled(" → ", text_style),
+                    Span::styled(description, text_style),
+                ])
+            })
+            .collect();
+        if lines.is_empty() || area.width < 3 || area.height < 3 {
+            return;
+        }
+
+        let inner_width = area.width - 2;
+        let column_width = lines
+            .iter()
+            .map(|line| u16::try_from(line.width()).unwrap_or(u16::MAX))
+            .max()
+            .unwrap_or_default()
+            .min(inner_width);
+        let columns = ((inner_width + COLUMN_SPACING) / (column_width + COLUMN_SPACING)).max(1);
+        let rows = u16::try_from(lines.len().div_ceil(columns.into()))
+            .unwrap_or(u16::MAX)
+            .min(area.height - 2);
+
+        let popup_area = Rect {
+            x: area.x,
+            y: area.y + area.height - rows - 2,
+            width: area.width,
+            height: rows + 2,
+        };
+        Clear.render(popup_area, buf);
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(config.as_border_style())
+            .title(format!(" {} ", self.pending));
+        if let Some(bg_color) = config.theme.modal_background_color {
+            block = block.style(Style::default().bg(bg_color));
+        }
+        let inner = block.inner(popup_area);
+        block.render(popup_area, buf);
+
+        // Fill the columns top to bottom first, same as a directory listing
+        for (idx, line) in lines.into_iter().enumerate() {
+            let (Ok(column), Ok(row)) =
+                (u16::try_from(idx / usize::from(rows)), u16::try_from(idx % usize::from(rows)))
+            else {
+                break;
+            };
+            if column >= columns {
+                break;
+            }
+
+            let x = inner.x + column * (column_width + COLUMN_SPACING);
+            line.render(
+                Rect { x, y: inner.y + row, width: column_width.min(inner.right() - x), height: 1 },
+                buf,
+            );
+        }
+    }
+}
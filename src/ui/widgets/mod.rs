@@ -10,6 +10,7 @@ pub mod browser;
 pub mod button;
 pub mod header;
 pub mod input;
+pub mod key_sequence_popup;
 pub mod progress_bar;
 pub mod scan_status;
 pub mod scrolling_line;